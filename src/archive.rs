@@ -0,0 +1,104 @@
+// src/archive.rs
+use std::io::{Cursor, Read};
+use zip::ZipArchive;
+
+// One file inside a zip, with its path inside the archive and its bytes
+#[derive(Debug, Clone, PartialEq)]
+pub struct ZipMember {
+    pub name: String,
+    pub data: Vec<u8>,
+}
+
+// Files of a zip archive. Encrypted members are skipped and nested archives are not
+// unpacked.
+pub fn read_zip(bytes: &[u8]) -> Result<Vec<ZipMember>, String> {
+    let mut archive =
+        ZipArchive::new(Cursor::new(bytes)).map_err(|e| format!("Not a zip archive: {}", e))?;
+
+    let mut members = Vec::new();
+    for index in 0..archive.len() {
+        let mut file = match archive.by_index(index) {
+            Ok(file) => file,
+            Err(zip::result::ZipError::UnsupportedArchive(msg))
+                if msg == zip::result::ZipError::PASSWORD_REQUIRED =>
+            {
+                continue;
+            }
+            Err(e) => return Err(format!("Corrupt zip entry {}: {}", index, e)),
+        };
+        // Folders are implied by member paths
+        if file.is_dir() {
+            continue;
+        }
+        let name = file.name().to_string();
+        let mut data = Vec::new();
+        file.read_to_end(&mut data)
+            .map_err(|e| format!("Could not read {}: {}", name, e))?;
+        members.push(ZipMember { name, data });
+    }
+    Ok(members)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_scan::FileScanner;
+    use crate::graph::{FileGraph, GraphNode};
+    use petgraph::visit::{EdgeRef, IntoEdgeReferences};
+    use std::io::Write;
+    use std::path::PathBuf;
+    use zip::write::SimpleFileOptions;
+
+    fn zip_bytes(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        writer
+            .add_directory("sub/", SimpleFileOptions::default())
+            .unwrap();
+        for (name, content) in files {
+            writer
+                .start_file(*name, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn zip_members_become_nodes_with_link_edges() {
+        let bytes = zip_bytes(&[("a.md", "See [b](sub/b.md)"), ("sub/b.md", "[[../a]]")]);
+        let members = read_zip(&bytes).unwrap();
+        let names: Vec<_> = members.iter().map(|member| member.name.as_str()).collect();
+        assert_eq!(names, vec!["a.md", "sub/b.md"]);
+
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("notes.zip");
+        std::fs::write(&zip_path, &bytes).unwrap();
+        let mut scanner = FileScanner::new(dir.path());
+        scanner
+            .scan_directory_with_progress(dir.path(), std::sync::mpsc::channel().0)
+            .unwrap();
+        let mut graph = FileGraph::new();
+        graph.build_from_scanner(&scanner, usize::MAX, &mut |_| {});
+
+        let a = GraphNode::File(zip_path.join("a.md"));
+        let b = GraphNode::File(zip_path.join("sub/b.md"));
+        let mut edges: Vec<_> = graph
+            .graph
+            .edge_references()
+            .map(|edge| {
+                (
+                    graph.graph[edge.source()].clone(),
+                    graph.graph[edge.target()].clone(),
+                )
+            })
+            .collect();
+        edges.sort_by_key(|(source, _)| source != &a);
+        assert_eq!(edges, vec![(a.clone(), b.clone()), (b, a)]);
+        assert!(
+            !graph
+                .graph
+                .node_weights()
+                .any(|node| node == &GraphNode::File(PathBuf::from(&zip_path)))
+        );
+    }
+}
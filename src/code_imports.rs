@@ -0,0 +1,158 @@
+// src/code_imports.rs
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::path::{Path, PathBuf};
+
+// Python: `import a.b, c` and `from .a import b`
+static PY_IMPORT_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?m)^\s*import\s+([\w.]+(?:\s*,\s*[\w.]+)*)").unwrap());
+static PY_FROM_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?m)^\s*from\s+(\.*)([\w.]*)\s+import\b").unwrap());
+// Rust: `mod name;` (not inline modules) and `use crate::name...`
+static RS_MOD_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?m)^\s*(?:pub(?:\([^)]*\))?\s+)?mod\s+(\w+)\s*;").unwrap());
+static RS_USE_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?m)^\s*(?:pub(?:\([^)]*\))?\s+)?use\s+crate::(\w+)").unwrap());
+// C: quoted includes only; angle brackets are system headers
+static C_INCLUDE_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?m)^\s*#\s*include\s+"([^"]+)""#).unwrap());
+
+// Files a source file imports, for the languages we understand. Only imports that
+// resolve to an existing file are returned, so unknown modules add no edges.
+pub fn code_imports(path: &Path, content: &str) -> Vec<PathBuf> {
+    let Some(dir) = path.parent() else {
+        return Vec::new();
+    };
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+
+    let candidates = match ext.as_str() {
+        "py" => python_candidates(dir, content),
+        "rs" => rust_candidates(path, dir, content),
+        "c" | "cpp" | "h" => C_INCLUDE_RE
+            .captures_iter(content)
+            .map(|cap| vec![dir.join(&cap[1])])
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    let mut imports = Vec::new();
+    for options in candidates {
+        if let Some(found) = options.into_iter().find(|p| p.is_file())
+            && found != path
+            && !imports.contains(&found)
+        {
+            imports.push(found);
+        }
+    }
+    imports
+}
+
+// `a.b` -> a/b.py or a/b/__init__.py under `base`
+fn python_module_files(base: &Path, module: &str) -> Vec<PathBuf> {
+    let module_path: PathBuf = module.split('.').collect();
+    vec![
+        base.join(&module_path).with_extension("py"),
+        base.join(&module_path).join("__init__.py"),
+    ]
+}
+
+// Absolute imports are only matched against siblings of the importing file
+fn python_candidates(dir: &Path, content: &str) -> Vec<Vec<PathBuf>> {
+    let mut candidates = Vec::new();
+    for cap in PY_IMPORT_RE.captures_iter(content) {
+        for module in cap[1].split(',') {
+            candidates.push(python_module_files(dir, module.trim()));
+        }
+    }
+    for cap in PY_FROM_RE.captures_iter(content) {
+        // One dot is the current package, each further dot goes up a level
+        let mut base = dir.to_path_buf();
+        for _ in 1..cap[1].len() {
+            if !base.pop() {
+                break;
+            }
+        }
+        if !cap[2].is_empty() {
+            candidates.push(python_module_files(&base, &cap[2]));
+        } else {
+            candidates.push(vec![base.join("__init__.py")]);
+        }
+    }
+    candidates
+}
+
+fn rust_candidates(path: &Path, dir: &Path, content: &str) -> Vec<Vec<PathBuf>> {
+    // Child modules of foo.rs live in foo/, those of main.rs, lib.rs and mod.rs beside them
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+    let module_dir = if ["main", "lib", "mod"].contains(&stem) {
+        dir.to_path_buf()
+    } else {
+        dir.join(stem)
+    };
+
+    let mut candidates = Vec::new();
+    for cap in RS_MOD_RE.captures_iter(content) {
+        candidates.push(vec![
+            module_dir.join(format!("{}.rs", &cap[1])),
+            module_dir.join(&cap[1]).join("mod.rs"),
+        ]);
+    }
+
+    // `crate::` paths start at the nearest enclosing src directory
+    if let Some(src_dir) = path.ancestors().find(|p| p.ends_with("src")) {
+        for cap in RS_USE_RE.captures_iter(content) {
+            candidates.push(vec![
+                src_dir.join(format!("{}.rs", &cap[1])),
+                src_dir.join(&cap[1]).join("mod.rs"),
+            ]);
+        }
+    }
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_scan::FileScanner;
+
+    #[test]
+    fn python_sibling_imports_become_links() {
+        let dir = tempfile::tempdir().unwrap();
+        let main = dir.path().join("main.py");
+        let content = "import os, helper\nfrom pkg import tool\nfrom missing import x\n";
+        for (name, text) in [
+            ("main.py", content),
+            ("helper.py", ""),
+            ("pkg/__init__.py", ""),
+        ] {
+            let path = dir.path().join(name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, text).unwrap();
+        }
+
+        // `os` and `missing` aren't files in the folder, so they add nothing
+        assert_eq!(
+            code_imports(&main, content),
+            [
+                dir.path().join("helper.py"),
+                dir.path().join("pkg/__init__.py")
+            ]
+        );
+
+        for parse_code_imports in [true, false] {
+            let mut scanner = FileScanner::new(dir.path());
+            scanner.parse_code_imports = parse_code_imports;
+            scanner
+                .scan_directory_with_progress(dir.path(), std::sync::mpsc::channel().0)
+                .unwrap();
+            assert_eq!(
+                scanner.files[&main].contains(&dir.path().join("helper.py")),
+                parse_code_imports
+            );
+        }
+    }
+}
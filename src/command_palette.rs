@@ -0,0 +1,258 @@
+// src/command_palette.rs
+use eframe::egui::{self, Key, Modifiers};
+use std::cmp::Reverse;
+
+use crate::keybindings::{Action, KeyBindings};
+use crate::ui::GraphMode;
+use crate::utils::fuzzy_score;
+
+// Everything the palette can run; each maps onto a handler FileGraphApp already has
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Command {
+    Action(Action),
+    SwitchMode(GraphMode),
+    ExportGraph { graphml: bool },
+    ExportTags { inverted: bool },
+    ExportCooccurrence { csv: bool },
+    ImportGraph,
+    Rescan,
+    FindDuplicates,
+    ToggleDirectoryPanel,
+    ToggleContentPanel,
+    ToggleSettings,
+    ToggleKeyBindings,
+    ToggleCycles,
+    ToggleScanStats,
+    ToggleCurvedEdges,
+    ToggleEdgeBundling,
+    ToggleEdgeFlow,
+    ToggleFollowSelected,
+    TogglePlacementMode,
+}
+
+impl Command {
+    pub fn all() -> Vec<Command> {
+        let mut commands: Vec<Command> = Action::ALL
+            .into_iter()
+            .filter(|&action| action != Action::CommandPalette)
+            .map(Command::Action)
+            .collect();
+        commands.extend([
+            Command::SwitchMode(GraphMode::Links),
+            Command::SwitchMode(GraphMode::Tags),
+            Command::SwitchMode(GraphMode::Unified),
+            Command::ExportGraph { graphml: false },
+            Command::ExportGraph { graphml: true },
+            Command::ExportTags { inverted: false },
+            Command::ExportTags { inverted: true },
+            Command::ExportCooccurrence { csv: true },
+            Command::ExportCooccurrence { csv: false },
+            Command::ImportGraph,
+            Command::Rescan,
+            Command::FindDuplicates,
+            Command::ToggleDirectoryPanel,
+            Command::ToggleContentPanel,
+            Command::ToggleSettings,
+            Command::ToggleKeyBindings,
+            Command::ToggleCycles,
+            Command::ToggleScanStats,
+            Command::ToggleCurvedEdges,
+            Command::ToggleEdgeBundling,
+            Command::ToggleEdgeFlow,
+            Command::ToggleFollowSelected,
+            Command::TogglePlacementMode,
+        ]);
+        commands
+    }
+
+    pub fn label(self) -> String {
+        match self {
+            Command::Action(action) => action.label().to_string(),
+            Command::SwitchMode(mode) => format!("Graph mode: {}", mode.label()),
+            Command::ExportGraph { graphml: false } => "Export graph as JSON".to_string(),
+            Command::ExportGraph { graphml: true } => "Export graph as GraphML".to_string(),
+            Command::ExportTags { inverted: false } => "Export tags (file → tags)".to_string(),
+            Command::ExportTags { inverted: true } => "Export tags (tag → files)".to_string(),
+            Command::ExportCooccurrence { csv: true } => {
+                "Export tag co-occurrence as CSV".to_string()
+            }
+            Command::ExportCooccurrence { csv: false } => {
+                "Export tag co-occurrence as JSON".to_string()
+            }
+            Command::ImportGraph => "Import graph".to_string(),
+            Command::Rescan => "Rescan directory".to_string(),
+            Command::FindDuplicates => "Find duplicate files".to_string(),
+            Command::ToggleDirectoryPanel => "Toggle directory panel".to_string(),
+            Command::ToggleContentPanel => "Toggle content panel".to_string(),
+            Command::ToggleSettings => "Toggle settings window".to_string(),
+            Command::ToggleKeyBindings => "Toggle key bindings window".to_string(),
+            Command::ToggleCycles => "Toggle link cycles window".to_string(),
+            Command::ToggleScanStats => "Toggle scan statistics window".to_string(),
+            Command::ToggleCurvedEdges => "Toggle curved edges".to_string(),
+            Command::ToggleEdgeBundling => "Toggle edge bundling".to_string(),
+            Command::ToggleEdgeFlow => "Toggle animated edge flow".to_string(),
+            Command::ToggleFollowSelected => "Toggle follow selected".to_string(),
+            Command::TogglePlacementMode => "Toggle node placement".to_string(),
+        }
+    }
+}
+
+// Commands matching `query`, best match first; all commands when the query is empty
+pub fn filter_commands(query: &str) -> Vec<Command> {
+    let mut scored: Vec<(i32, Command)> = Command::all()
+        .into_iter()
+        .filter_map(|command| fuzzy_score(query, &command.label()).map(|score| (score, command)))
+        .collect();
+    // Stable, so equal scores keep the listing order
+    scored.sort_by_key(|(score, _)| Reverse(*score));
+    scored.into_iter().map(|(_, command)| command).collect()
+}
+
+#[derive(Debug, Default)]
+pub struct CommandPalette {
+    pub open: bool,
+    query: String,
+    selected: usize,
+}
+
+impl CommandPalette {
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        self.query.clear();
+        self.selected = 0;
+    }
+
+    // The command picked with Enter or a click this frame; Escape closes the palette
+    pub fn show(&mut self, ctx: &egui::Context, keybindings: &KeyBindings) -> Option<Command> {
+        if !self.open {
+            return None;
+        }
+        let (up, down, enter, escape) = ctx.input_mut(|i| {
+            (
+                i.consume_key(Modifiers::NONE, Key::ArrowUp),
+                i.consume_key(Modifiers::NONE, Key::ArrowDown),
+                i.consume_key(Modifiers::NONE, Key::Enter),
+                i.consume_key(Modifiers::NONE, Key::Escape),
+            )
+        });
+
+        let mut chosen = None;
+        egui::Window::new("Command Palette")
+            .title_bar(false)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 60.0))
+            .fixed_size(egui::vec2(420.0, 0.0))
+            .show(ctx, |ui| {
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut self.query)
+                        .hint_text("Type a command...")
+                        .desired_width(f32::INFINITY),
+                );
+                response.request_focus();
+                if response.changed() {
+                    self.selected = 0;
+                }
+
+                let matches = filter_commands(&self.query);
+                if matches.is_empty() {
+                    ui.weak("No matching commands");
+                    return;
+                }
+                if down {
+                    self.selected = (self.selected + 1) % matches.len();
+                }
+                if up {
+                    self.selected = self.selected.checked_sub(1).unwrap_or(matches.len() - 1);
+                }
+                self.selected = self.selected.min(matches.len() - 1);
+
+                egui::ScrollArea::vertical()
+                    .max_height(320.0)
+                    .show(ui, |ui| {
+                        for (i, &command) in matches.iter().enumerate() {
+                            let is_selected = i == self.selected;
+                            let row = ui.horizontal(|ui| {
+                                let label = ui.selectable_label(is_selected, command.label());
+                                if let Command::Action(action) = command {
+                                    ui.with_layout(
+                                        egui::Layout::right_to_left(egui::Align::Center),
+                                        |ui| {
+                                            ui.weak(
+                                                ui.ctx()
+                                                    .format_shortcut(&keybindings.shortcut(action)),
+                                            );
+                                        },
+                                    );
+                                }
+                                label
+                            });
+                            if is_selected && (up || down) {
+                                row.inner.scroll_to_me(None);
+                            }
+                            if row.inner.clicked() {
+                                chosen = Some(command);
+                            }
+                        }
+                    });
+                if enter {
+                    chosen = matches.get(self.selected).copied();
+                }
+            });
+
+        if chosen.is_some() || escape {
+            self.open = false;
+        }
+        chosen
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key_press(key: Key) -> egui::Event {
+        egui::Event::Key {
+            key,
+            physical_key: None,
+            pressed: true,
+            repeat: false,
+            modifiers: Modifiers::NONE,
+        }
+    }
+
+    #[test]
+    fn fuzzy_query_ranks_the_closest_command_first() {
+        assert_eq!(filter_commands("").len(), Command::all().len());
+        assert_eq!(filter_commands("dupl")[0], Command::FindDuplicates);
+        assert_eq!(
+            filter_commands("export graphml")[0],
+            Command::ExportGraph { graphml: true }
+        );
+        assert!(
+            filter_commands("toggle")
+                .iter()
+                .all(|command| command.label().to_lowercase().contains("toggle"))
+        );
+        assert!(filter_commands("zzzz").is_empty());
+    }
+
+    #[test]
+    fn enter_runs_the_highlighted_command_and_closes() {
+        let ctx = egui::Context::default();
+        let keybindings = KeyBindings::default();
+        let mut palette = CommandPalette::default();
+        palette.toggle();
+        palette.query = "toggle".to_string();
+
+        let input = egui::RawInput {
+            events: vec![key_press(Key::ArrowDown), key_press(Key::Enter)],
+            ..Default::default()
+        };
+        let mut chosen = None;
+        let _ = ctx.run(input, |ctx| chosen = palette.show(ctx, &keybindings));
+
+        assert_eq!(chosen, Some(filter_commands("toggle")[1]));
+        assert!(!palette.open);
+    }
+}
@@ -0,0 +1,271 @@
+// src/file_edits.rs
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::utils::rename_link_targets;
+
+// A file change made from the graph, with what is needed to reverse it
+#[derive(Debug, Clone)]
+pub enum FileEdit {
+    AddTag {
+        tag: String,
+        rewrite: Rewrite,
+    },
+    Create {
+        path: PathBuf,
+        written: blake3::Hash,
+    },
+    // Moved to the system trash
+    Delete {
+        path: PathBuf,
+    },
+    // `rewrites` are the files whose links were pointed at the new name
+    Rename {
+        from: PathBuf,
+        to: PathBuf,
+        rewrites: Vec<Rewrite>,
+    },
+}
+
+// A file's content before an edit, and a hash of what the edit wrote, so undoing never
+// overwrites changes made to the file since
+#[derive(Debug, Clone)]
+pub struct Rewrite {
+    pub path: PathBuf,
+    previous_content: String,
+    written: blake3::Hash,
+}
+
+impl Rewrite {
+    fn is_unchanged(&self) -> bool {
+        fs::read(&self.path).is_ok_and(|bytes| blake3::hash(&bytes) == self.written)
+    }
+
+    fn restore(&self) -> Result<(), String> {
+        fs::write(&self.path, &self.previous_content)
+            .map_err(|e| format!("Failed to restore {}: {}", self.path.display(), e))
+    }
+}
+
+// Replace `path`'s content with `content`, remembering `previous_content`
+pub fn rewrite_file(
+    path: &Path,
+    previous_content: String,
+    content: &str,
+) -> Result<Rewrite, String> {
+    fs::write(path, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(Rewrite {
+        path: path.to_path_buf(),
+        previous_content,
+        written: blake3::hash(content.as_bytes()),
+    })
+}
+
+// `Untitled.md`, or `Untitled 2.md` and so on when that is taken
+pub fn untitled_note_path(dir: &Path) -> PathBuf {
+    let mut path = dir.join("Untitled.md");
+    let mut n = 2;
+    while path.exists() {
+        path = dir.join(format!("Untitled {}.md", n));
+        n += 1;
+    }
+    path
+}
+
+// A new note headed with its name; fails rather than overwrite an existing file
+pub fn create_note(path: &Path) -> Result<FileEdit, String> {
+    let title = path.file_stem().unwrap_or_default().to_string_lossy();
+    let content = format!("# {}\n", title);
+    fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)
+        .and_then(|mut file| std::io::Write::write_all(&mut file, content.as_bytes()))
+        .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    Ok(FileEdit::Create {
+        path: path.to_path_buf(),
+        written: blake3::hash(content.as_bytes()),
+    })
+}
+
+pub fn delete_to_trash(path: &Path) -> Result<FileEdit, String> {
+    trash::delete(path).map_err(|e| format!("Failed to delete {}: {}", path.display(), e))?;
+    Ok(FileEdit::Delete {
+        path: path.to_path_buf(),
+    })
+}
+
+// Rename `from` to `to` and point the links in `referencing` files at the new name.
+// Files that can't be read or written keep their old links.
+pub fn rename_file(from: &Path, to: &Path, referencing: &[PathBuf]) -> Result<FileEdit, String> {
+    if to.exists() {
+        return Err(format!("{} already exists", to.display()));
+    }
+    fs::rename(from, to).map_err(|e| format!("Failed to rename {}: {}", from.display(), e))?;
+
+    let mut rewrites = Vec::new();
+    for path in referencing {
+        let path = if path == from { to } else { path.as_path() };
+        let Ok(content) = fs::read_to_string(path) else {
+            continue;
+        };
+        if let Some(updated) = rename_link_targets(&content, from, to)
+            && let Ok(rewrite) = rewrite_file(path, content, &updated)
+        {
+            rewrites.push(rewrite);
+        }
+    }
+    Ok(FileEdit::Rename {
+        from: from.to_path_buf(),
+        to: to.to_path_buf(),
+        rewrites,
+    })
+}
+
+// Reverse `edit`. Nothing is touched when a file it wrote has been changed since.
+pub fn undo(edit: &FileEdit) -> Result<(), String> {
+    match edit {
+        FileEdit::AddTag { tag, rewrite } => {
+            if !rewrite.is_unchanged() {
+                return Err(format!(
+                    "{} changed after #{} was added",
+                    rewrite.path.display(),
+                    tag
+                ));
+            }
+            rewrite.restore()
+        }
+        FileEdit::Create { path, written } => {
+            if !fs::read(path).is_ok_and(|bytes| blake3::hash(&bytes) == *written) {
+                return Err(format!("{} changed after it was created", path.display()));
+            }
+            fs::remove_file(path).map_err(|e| format!("Failed to delete {}: {}", path.display(), e))
+        }
+        FileEdit::Delete { path } => restore_from_trash(path),
+        FileEdit::Rename { from, to, rewrites } => {
+            if from.exists() {
+                return Err(format!("{} exists again", from.display()));
+            }
+            if !to.exists() {
+                return Err(format!("{} no longer exists", to.display()));
+            }
+            if let Some(changed) = rewrites.iter().find(|rewrite| !rewrite.is_unchanged()) {
+                return Err(format!(
+                    "{} changed after its links were renamed",
+                    changed.path.display()
+                ));
+            }
+            fs::rename(to, from)
+                .map_err(|e| format!("Failed to rename {}: {}", to.display(), e))?;
+            // A file linking to itself was rewritten under its new name
+            for rewrite in rewrites {
+                let mut rewrite = rewrite.clone();
+                if rewrite.path == *to {
+                    rewrite.path = from.clone();
+                }
+                rewrite.restore()?;
+            }
+            Ok(())
+        }
+    }
+}
+
+// The most recently trashed file that was at `path`
+#[cfg(any(
+    target_os = "windows",
+    all(
+        unix,
+        not(target_os = "macos"),
+        not(target_os = "ios"),
+        not(target_os = "android")
+    )
+))]
+fn restore_from_trash(path: &Path) -> Result<(), String> {
+    let parent = path.parent().and_then(|parent| parent.canonicalize().ok());
+    let item = trash::os_limited::list()
+        .map_err(|e| format!("Failed to read the trash: {}", e))?
+        .into_iter()
+        .filter(|item| {
+            Some(&item.original_parent) == parent.as_ref()
+                && Some(item.name.as_os_str()) == path.file_name()
+        })
+        .max_by_key(|item| item.time_deleted)
+        .ok_or_else(|| format!("{} is no longer in the trash", path.display()))?;
+    trash::os_limited::restore_all([item])
+        .map_err(|e| format!("Failed to restore {}: {}", path.display(), e))
+}
+
+#[cfg(not(any(
+    target_os = "windows",
+    all(
+        unix,
+        not(target_os = "macos"),
+        not(target_os = "ios"),
+        not(target_os = "android")
+    )
+)))]
+fn restore_from_trash(path: &Path) -> Result<(), String> {
+    Err(format!(
+        "Restore {} from the trash by hand; this system doesn't allow it",
+        path.display()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undoing_rename_restores_name_and_links() {
+        let dir = tempfile::tempdir().unwrap();
+        let from = dir.path().join("b.md");
+        let to = dir.path().join("c.md");
+        let linking = dir.path().join("a.md");
+        let original = "See [b](b.md), [[b]] and [[b|the b note]].\n";
+        fs::write(&from, "# b\n").unwrap();
+        fs::write(&linking, original).unwrap();
+
+        let edit = rename_file(&from, &to, std::slice::from_ref(&linking)).unwrap();
+        assert!(!from.exists() && to.exists());
+        assert_eq!(
+            fs::read_to_string(&linking).unwrap(),
+            "See [b](c.md), [[c]] and [[c|the b note]].\n"
+        );
+
+        undo(&edit).unwrap();
+        assert!(from.exists() && !to.exists());
+        assert_eq!(fs::read_to_string(&linking).unwrap(), original);
+    }
+
+    #[test]
+    fn undo_leaves_files_changed_since_the_edit_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("note.md");
+        fs::write(&path, "text\n").unwrap();
+        let edit = FileEdit::AddTag {
+            tag: "todo".to_string(),
+            rewrite: rewrite_file(&path, "text\n".to_string(), "text\n#todo\n").unwrap(),
+        };
+        fs::write(&path, "text\n#todo\nmore text\n").unwrap();
+
+        assert!(undo(&edit).is_err());
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "text\n#todo\nmore text\n"
+        );
+    }
+
+    #[test]
+    fn undoing_create_removes_the_new_note() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = untitled_note_path(dir.path());
+        let edit = create_note(&path).unwrap();
+        assert_eq!(
+            untitled_note_path(dir.path()),
+            dir.path().join("Untitled 2.md")
+        );
+
+        undo(&edit).unwrap();
+        assert!(!path.exists());
+    }
+}
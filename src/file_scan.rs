@@ -1,18 +1,59 @@
 // src/file_scan.rs
-use crate::utils::{is_image_path, is_pdf_path};
+use crate::archive::read_zip;
+use crate::code_imports::code_imports;
+use crate::html_links::html_links;
+use crate::utils::{
+    is_code_path, is_external_link, is_hidden_path, is_html_path, is_image_path, is_pdf_path,
+    is_zip_path, normalize_lexically, path_glob, tag_matches_filter,
+};
+use globset::{GlobSet, GlobSetBuilder};
+use ignore::gitignore::Gitignore;
 use regex::Regex;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+// How often a streaming scan shares its partial results
+const STREAM_INTERVAL: Duration = Duration::from_millis(300);
 
 pub struct FileScanner {
     root_path: PathBuf,
     current_scan_path: PathBuf,
     pub show_hidden: bool,
+    // Treat imports in code files (Rust, Python, C) as links
+    pub parse_code_imports: bool,
+    // Skip whatever the .gitignore files met during the walk ignore
+    pub respect_gitignore: bool,
+    // Rules of the folders being walked, outermost first
+    gitignores: Vec<Gitignore>,
+    // Folder levels below the scanned one to descend into; 0 reads only its own files
+    max_depth: Option<usize>,
+    // Files larger than this many bytes are skipped
+    max_file_size: Option<u64>,
+    // Globs relative to the scan root; matching files and folders are skipped
+    exclude_globs: GlobSet,
     pub files: HashMap<PathBuf, Vec<PathBuf>>,
+    pub external_links: HashMap<PathBuf, Vec<String>>,
     pub images: Vec<PathBuf>,
     pub tags: HashMap<PathBuf, Vec<String>>,
+    // Reference-style links whose `[ref]: target` definition is missing
+    pub broken_links: HashMap<PathBuf, Vec<String>>,
+    // Display text of `[[target|alias]]` wikilinks, by file and then resolved target
+    pub link_aliases: HashMap<PathBuf, HashMap<PathBuf, String>>,
+    // Files and folders that could not be read; the scan carries on without them
+    pub scan_errors: Vec<(PathBuf, String)>,
+    // Size and modification time read during the scan, so the UI never stats files itself
+    pub file_metadata: HashMap<PathBuf, (u64, SystemTime)>,
+    // Text of files read from inside zip archives, which can't be read back from disk
+    pub archive_text: HashMap<PathBuf, String>,
+    // Shared scanner that receives partial results while this one scans
+    stream_target: Option<Arc<Mutex<FileScanner>>>,
+    last_stream: Option<Instant>,
+    // Results of an earlier scan, reused for text files that haven't changed since
+    previous: Option<Box<FileScanner>>,
 }
 
 impl FileScanner {
@@ -22,9 +63,24 @@ impl FileScanner {
             root_path: root_path.as_ref().to_path_buf(),
             current_scan_path: path,
             show_hidden: false,
+            parse_code_imports: false,
+            respect_gitignore: false,
+            gitignores: Vec::new(),
+            max_depth: None,
+            max_file_size: None,
+            exclude_globs: GlobSet::empty(),
             files: HashMap::new(),
+            external_links: HashMap::new(),
             images: Vec::new(),
             tags: HashMap::new(),
+            broken_links: HashMap::new(),
+            link_aliases: HashMap::new(),
+            scan_errors: Vec::new(),
+            file_metadata: HashMap::new(),
+            archive_text: HashMap::new(),
+            stream_target: None,
+            last_stream: None,
+            previous: None,
         }
     }
 
@@ -32,15 +88,341 @@ impl FileScanner {
         self.show_hidden = show;
     }
 
+    pub fn set_max_depth(&mut self, max_depth: Option<usize>) {
+        self.max_depth = max_depth;
+    }
+
+    pub fn set_max_file_size(&mut self, max_file_size: Option<u64>) {
+        self.max_file_size = max_file_size;
+    }
+
     pub fn root_path(&self) -> &PathBuf {
         &self.root_path
     }
 
+    // Empty scanner with the same settings, filled without holding the shared lock
+    pub fn detached(&self) -> Self {
+        let mut scanner = Self::new(&self.root_path);
+        scanner.show_hidden = self.show_hidden;
+        scanner.parse_code_imports = self.parse_code_imports;
+        scanner.respect_gitignore = self.respect_gitignore;
+        scanner.max_depth = self.max_depth;
+        scanner.max_file_size = self.max_file_size;
+        scanner.exclude_globs = self.exclude_globs.clone();
+        scanner
+    }
+
+    // Settings and results, for building graphs without holding the shared lock
+    pub fn snapshot(&self) -> Self {
+        let mut scanner = self.detached();
+        scanner.files = self.files.clone();
+        scanner.external_links = self.external_links.clone();
+        scanner.images = self.images.clone();
+        scanner.tags = self.tags.clone();
+        scanner.broken_links = self.broken_links.clone();
+        scanner.link_aliases = self.link_aliases.clone();
+        scanner.file_metadata = self.file_metadata.clone();
+        scanner
+    }
+
+    // Like detached, but text files whose size and modification time still match what
+    // this scanner recorded are copied from its results instead of being read again
+    pub fn incremental(&self) -> Self {
+        let mut previous = Self::new(&self.root_path);
+        previous.files = self.files.clone();
+        previous.external_links = self.external_links.clone();
+        previous.tags = self.tags.clone();
+        previous.broken_links = self.broken_links.clone();
+        previous.link_aliases = self.link_aliases.clone();
+        previous.file_metadata = self.file_metadata.clone();
+
+        let mut scanner = self.detached();
+        scanner.previous = Some(Box::new(previous));
+        scanner
+    }
+
+    // Copy `path`'s results from the previous scan; false if it changed or is new
+    fn reuse_previous(&mut self, path: &Path) -> bool {
+        let Some(previous) = &self.previous else {
+            return false;
+        };
+        let metadata = self.file_metadata.get(path);
+        if metadata.is_none() || previous.file_metadata.get(path) != metadata {
+            return false;
+        }
+        let Some(links) = previous.files.get(path) else {
+            return false;
+        };
+        self.files.insert(path.to_path_buf(), links.clone());
+        if let Some(urls) = previous.external_links.get(path) {
+            self.external_links.insert(path.to_path_buf(), urls.clone());
+        }
+        if let Some(tags) = previous.tags.get(path) {
+            self.tags.insert(path.to_path_buf(), tags.clone());
+        }
+        if let Some(broken) = previous.broken_links.get(path) {
+            self.broken_links.insert(path.to_path_buf(), broken.clone());
+        }
+        if let Some(aliases) = previous.link_aliases.get(path) {
+            self.link_aliases
+                .insert(path.to_path_buf(), aliases.clone());
+        }
+        true
+    }
+
+    // Replaces the exclude globs; on an invalid glob nothing changes
+    pub fn set_exclude_globs(&mut self, globs: &[String]) -> Result<(), String> {
+        let mut builder = GlobSetBuilder::new();
+        for glob in globs {
+            builder.add(path_glob(glob).map_err(|e| format!("Invalid glob {:?}: {}", glob, e))?);
+        }
+        self.exclude_globs = builder.build().map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    // Folders are matched with a trailing `/`, so `**/archive/**` skips the folder itself
+    fn is_excluded(&self, path: &Path) -> bool {
+        if self.exclude_globs.is_empty() {
+            return false;
+        }
+        let Ok(relative) = path.strip_prefix(&self.root_path) else {
+            return false;
+        };
+        let mut relative = relative.to_string_lossy().replace('\\', "/");
+        if path.is_dir() {
+            relative.push('/');
+        }
+        self.exclude_globs.is_match(&relative)
+    }
+
+    // Hidden, excluded, .gitignored or oversized: left out of the scan
+    fn is_skipped(&self, path: &Path) -> bool {
+        (!self.show_hidden && is_hidden_path(path))
+            || self.is_excluded(path)
+            || self.is_gitignored(path)
+            || self.is_too_large(path)
+    }
+
+    fn is_too_large(&self, path: &Path) -> bool {
+        self.max_file_size.is_some_and(|max_file_size| {
+            fs::metadata(path)
+                .is_ok_and(|metadata| metadata.is_file() && metadata.len() > max_file_size)
+        })
+    }
+
+    // Share results with `target` as they are found, so the graph can grow during the scan
+    pub fn stream_into(&mut self, target: Arc<Mutex<FileScanner>>) {
+        self.stream_target = Some(target);
+    }
+
+    // Add (or overwrite) everything found so far by `partial`. Links are resolved
+    // once the scan completes and merge_scan replaces these entries.
+    pub fn add_partial(&mut self, partial: &FileScanner) {
+        for (path, links) in &partial.files {
+            self.files.insert(path.clone(), links.clone());
+        }
+        for (path, urls) in &partial.external_links {
+            self.external_links.insert(path.clone(), urls.clone());
+        }
+        for (path, tags) in &partial.tags {
+            self.tags.insert(path.clone(), tags.clone());
+        }
+        for (path, aliases) in &partial.link_aliases {
+            self.link_aliases.insert(path.clone(), aliases.clone());
+        }
+        for (path, metadata) in &partial.file_metadata {
+            self.file_metadata.insert(path.clone(), *metadata);
+        }
+        let known: HashSet<&PathBuf> = self.images.iter().collect();
+        let new_images: Vec<PathBuf> = partial
+            .images
+            .iter()
+            .filter(|path| !known.contains(path))
+            .cloned()
+            .collect();
+        self.images.extend(new_images);
+    }
+
+    // Hand partial results to the stream target, at most every STREAM_INTERVAL
+    fn stream_partial(&mut self) {
+        let Some(target) = self.stream_target.clone() else {
+            return;
+        };
+        if self
+            .last_stream
+            .is_some_and(|last| last.elapsed() < STREAM_INTERVAL)
+        {
+            return;
+        }
+        // Skip this round rather than wait while the UI holds the lock
+        if let Ok(mut target) = target.try_lock() {
+            target.add_partial(self);
+            self.last_stream = Some(Instant::now());
+        }
+    }
+
+    // Replace everything previously scanned under `path` with the results in `scanned`
+    pub fn merge_scan(&mut self, path: &Path, scanned: FileScanner) {
+        self.files.retain(|k, _| !k.starts_with(path));
+        self.external_links.retain(|k, _| !k.starts_with(path));
+        self.tags.retain(|k, _| !k.starts_with(path));
+        self.images.retain(|k| !k.starts_with(path));
+        self.broken_links.retain(|k, _| !k.starts_with(path));
+        self.link_aliases.retain(|k, _| !k.starts_with(path));
+        self.scan_errors.retain(|(k, _)| !k.starts_with(path));
+        self.file_metadata.retain(|k, _| !k.starts_with(path));
+        self.archive_text.retain(|k, _| !k.starts_with(path));
+
+        self.files.extend(scanned.files);
+        self.external_links.extend(scanned.external_links);
+        self.tags.extend(scanned.tags);
+        self.images.extend(scanned.images);
+        self.broken_links.extend(scanned.broken_links);
+        self.link_aliases.extend(scanned.link_aliases);
+        self.scan_errors.extend(scanned.scan_errors);
+        self.file_metadata.extend(scanned.file_metadata);
+        self.archive_text.extend(scanned.archive_text);
+        self.current_scan_path = scanned.current_scan_path;
+    }
+
+    pub fn clear(&mut self) {
+        self.files.clear();
+        self.external_links.clear();
+        self.tags.clear();
+        self.images.clear();
+        self.broken_links.clear();
+        self.link_aliases.clear();
+        self.scan_errors.clear();
+        self.file_metadata.clear();
+        self.archive_text.clear();
+    }
+
+    pub fn tag_count(&self, path: &Path) -> usize {
+        self.tags.get(path).map_or(0, |tags| tags.len())
+    }
+
+    // `filter_lower` as for `tag_matches_filter`
+    pub fn has_tag_matching(&self, path: &Path, filter_lower: &str) -> bool {
+        self.tags
+            .get(path)
+            .is_some_and(|tags| tags.iter().any(|tag| tag_matches_filter(tag, filter_lower)))
+    }
+
+    // File -> tags, or tag -> files when inverted, with sorted keys and values
+    pub fn export_tags_json(&self, inverted: bool) -> serde_json::Result<String> {
+        let mut mapping: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for (path, tags) in &self.tags {
+            let path = path.display().to_string();
+            if inverted {
+                for tag in tags {
+                    mapping.entry(tag.clone()).or_default().push(path.clone());
+                }
+            } else {
+                mapping
+                    .entry(path)
+                    .or_default()
+                    .extend(tags.iter().cloned());
+            }
+        }
+        for values in mapping.values_mut() {
+            values.sort();
+            values.dedup();
+        }
+        serde_json::to_string_pretty(&mapping)
+    }
+
+    // Sends progress below 1.0 only: the caller reports completion once it has merged
+    // the results, so the UI never mistakes a half-merged scan for a finished one
     pub fn scan_directory_with_progress(
         &mut self,
         path: &Path,
         progress_sender: Sender<(f32, String)>,
     ) -> Result<(), String> {
+        self.gitignores.clear();
+        self.scan_directory_at_depth(path, &progress_sender, 0)
+    }
+
+    // Modification times of the files a scan of `path` would read, found with the same
+    // hidden, exclude, .gitignore, size and depth rules
+    pub fn scannable_files(&mut self, path: &Path) -> HashMap<PathBuf, SystemTime> {
+        self.gitignores.clear();
+        let mut files = HashMap::new();
+        self.collect_scannable_files(path, 0, &mut files);
+        files
+    }
+
+    fn collect_scannable_files(
+        &mut self,
+        dir: &Path,
+        depth: usize,
+        files: &mut HashMap<PathBuf, SystemTime>,
+    ) {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return;
+        };
+        let pushed_gitignore = self.respect_gitignore && self.push_gitignore(dir);
+        for entry in entries.filter_map(|e| e.ok()) {
+            let path = entry.path();
+            if self.is_skipped(&path) {
+                continue;
+            }
+            if path.is_dir() {
+                if self.max_depth.is_none_or(|max_depth| depth < max_depth) {
+                    self.collect_scannable_files(&path, depth + 1, files);
+                }
+            } else if let Ok(modified) = entry.metadata().and_then(|m| m.modified()) {
+                files.insert(path, modified);
+            }
+        }
+        if pushed_gitignore {
+            self.gitignores.pop();
+        }
+    }
+
+    // Adds the rules of `dir`'s .gitignore, if it has one; true when rules were added
+    fn push_gitignore(&mut self, dir: &Path) -> bool {
+        let file = dir.join(".gitignore");
+        if !file.is_file() {
+            return false;
+        }
+        // Rules that parsed are still used when others in the file are invalid
+        let (gitignore, error) = Gitignore::new(&file);
+        if let Some(e) = error {
+            self.scan_errors.push((file, e.to_string()));
+        }
+        self.gitignores.push(gitignore);
+        true
+    }
+
+    // The deepest .gitignore with a matching rule decides, so a nested `!pattern` wins
+    fn is_gitignored(&self, path: &Path) -> bool {
+        let is_dir = path.is_dir();
+        for gitignore in self.gitignores.iter().rev() {
+            let matched = gitignore.matched(path, is_dir);
+            if matched.is_ignore() {
+                return true;
+            }
+            if matched.is_whitelist() {
+                return false;
+            }
+        }
+        false
+    }
+
+    // `depth` counts folders below the one the scan started in. Progress is best effort:
+    // the scan carries on when nobody is listening any more.
+    fn scan_directory_at_depth(
+        &mut self,
+        path: &Path,
+        progress_sender: &Sender<(f32, String)>,
+        depth: usize,
+    ) -> Result<(), String> {
+        // A zip archive is scanned like a read-only folder of its members
+        if is_zip_path(path) && path.is_file() {
+            self.current_scan_path = path.to_path_buf();
+            self.scan_archive(path);
+            return Ok(());
+        }
         if !path.is_dir() {
             return Err(format!("Path is not a directory: {:?}", path));
         }
@@ -49,38 +431,56 @@ impl FileScanner {
 
         // Clear previous results for this path
         self.files.retain(|k, _| !k.starts_with(path));
+        self.external_links.retain(|k, _| !k.starts_with(path));
         self.tags.retain(|k, _| !k.starts_with(path));
         self.images.retain(|k| !k.starts_with(path));
+        self.broken_links.retain(|k, _| !k.starts_with(path));
+        self.link_aliases.retain(|k, _| !k.starts_with(path));
+        self.scan_errors.retain(|(k, _)| !k.starts_with(path));
+        self.file_metadata.retain(|k, _| !k.starts_with(path));
+        self.archive_text.retain(|k, _| !k.starts_with(path));
 
-        let entries: Vec<_> = fs::read_dir(path)
-            .map_err(|e| e.to_string())?
-            .filter_map(|e| e.ok())
-            .collect();
+        // An unreadable folder is skipped like an unreadable file
+        let entries: Vec<_> = match fs::read_dir(path) {
+            Ok(entries) => entries.filter_map(|e| e.ok()).collect(),
+            Err(e) => {
+                self.scan_errors.push((path.to_path_buf(), e.to_string()));
+                return Ok(());
+            }
+        };
+
+        let pushed_gitignore = self.respect_gitignore && self.push_gitignore(path);
 
         let total = entries.len();
         for (i, entry) in entries.into_iter().enumerate() {
             let path = entry.path();
-            if !self.show_hidden
-                && path
-                    .file_name()
-                    .and_then(|name| name.to_str())
-                    .map_or(false, |name| name.starts_with('.'))
-            {
-                continue; // Skip hidden files if show_hidden is false
+            if self.is_skipped(&path) {
+                continue;
             }
 
             let progress = (i as f32) / (total as f32);
-            progress_sender
-                .send((progress, format!("Scanning: {}", path.display())))
-                .map_err(|e| e.to_string())?;
+            let _ = progress_sender.send((progress, format!("Scanning: {}", path.display())));
 
-            // If a directory, recursively scan it
+            // If a directory, recursively scan it, unless that goes past the depth limit
             if path.is_dir() {
-                self.scan_directory_with_progress(&path, progress_sender.clone())?;
+                if self.max_depth.is_none_or(|max_depth| depth < max_depth)
+                    && let Err(e) = self.scan_directory_at_depth(&path, progress_sender, depth + 1)
+                {
+                    self.scan_errors.push((path, e));
+                }
+            } else if is_zip_path(&path) {
+                self.scan_archive(&path);
+                self.stream_partial();
             } else {
-                self.process_file(&path)?;
+                if let Err(e) = self.process_file(&path) {
+                    self.scan_errors.push((path, e));
+                }
+                self.stream_partial();
             }
         }
+        if pushed_gitignore {
+            self.gitignores.pop();
+        }
 
         // Resolve links after scanning
         let mut resolved_files = HashMap::new();
@@ -97,47 +497,585 @@ impl FileScanner {
             resolved_files.insert(file_path.clone(), resolved_links_for_file);
         }
         self.files = resolved_files;
-
-        progress_sender
-            .send((1.0, "Scan complete".to_string()))
-            .map_err(|e| e.to_string())?;
+        for aliases in self.link_aliases.values_mut() {
+            *aliases = aliases
+                .drain()
+                .map(|(link, alias)| {
+                    if link.is_relative() {
+                        (self.current_scan_path.join(link), alias)
+                    } else {
+                        (link, alias)
+                    }
+                })
+                .collect();
+        }
         Ok(())
     }
 
+    // Re-read one file's size and modification time, e.g. after NexusView edits it
+    pub fn refresh_metadata(&mut self, path: &Path) {
+        match fs::metadata(path) {
+            Ok(metadata) => {
+                let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                self.file_metadata
+                    .insert(path.to_path_buf(), (metadata.len(), modified));
+            }
+            Err(_) => {
+                self.file_metadata.remove(path);
+            }
+        }
+    }
+
     fn process_file(&mut self, path: &Path) -> Result<(), String> {
         if path.is_file() {
+            self.refresh_metadata(path);
             if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
                 if is_image_path(path) {
                     self.files.insert(path.to_path_buf(), Vec::new());
                     self.images.push(path.to_path_buf());
                 } else if is_pdf_path(path) {
                     self.files.insert(path.to_path_buf(), Vec::new());
-                } else if let Ok(content) = fs::read_to_string(path) {
-                    let mut links = Vec::new();
-                    let link_re = Regex::new(r"\[([^\]]+)\]\(([^)]+)\)|\[\[([^\]]+)\]\]").unwrap();
-
-                    for cap in link_re.captures_iter(&content) {
-                        if let Some(link) = cap.get(2) {
-                            links.push(PathBuf::from(link.as_str()));
-                        } else if let Some(link) = cap.get(3) {
-                            links.push(PathBuf::from(link.as_str()));
-                        }
+                } else if !self.reuse_previous(path) {
+                    let content = match fs::read_to_string(path) {
+                        Ok(content) => content,
+                        // Not UTF-8 text, e.g. a binary file: nothing to parse
+                        Err(e) if e.kind() == std::io::ErrorKind::InvalidData => return Ok(()),
+                        Err(e) => return Err(e.to_string()),
+                    };
+                    self.process_content(path, &content);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Links, external links and tags of one text file
+    fn process_content(&mut self, path: &Path, content: &str) {
+        let mut links = Vec::new();
+        let mut external_links = Vec::new();
+        let mut broken_links = Vec::new();
+        let mut aliases = HashMap::new();
+        let link_re =
+            Regex::new(r"\[([^\]]+)\]\(([^)]+)\)|\[\[([^\]]+)\]\]|\[([^\]]+)\]\[([^\]]*)\]")
+                .unwrap();
+        let definitions = reference_definitions(content);
+
+        for cap in link_re.captures_iter(content) {
+            // Reference-style `[text][ref]`; `[text][]` uses the text as the ref
+            if let Some(text) = cap.get(4) {
+                let label = cap
+                    .get(5)
+                    .map(|m| m.as_str())
+                    .filter(|label| !label.trim().is_empty())
+                    .unwrap_or(text.as_str());
+                match definitions.get(&normalize_reference_label(label)) {
+                    Some(target) if is_external_link(target) => external_links.push(target.clone()),
+                    Some(target) => links.push(PathBuf::from(target)),
+                    None => broken_links.push(label.to_string()),
+                }
+            } else if let Some(link) = cap.get(2) {
+                // Web URLs become external nodes instead of file paths
+                if is_external_link(link.as_str()) {
+                    external_links.push(link.as_str().trim().to_string());
+                } else {
+                    links.push(PathBuf::from(link.as_str()));
+                }
+            } else if let Some(link) = cap.get(3) {
+                // `[[target|alias]]` links to the target and displays the alias
+                match link.as_str().split_once('|') {
+                    Some((target, alias)) => {
+                        let target = PathBuf::from(target.trim());
+                        aliases.insert(target.clone(), alias.trim().to_string());
+                        links.push(target);
                     }
+                    None => links.push(PathBuf::from(link.as_str())),
+                }
+            }
+        }
 
-                    self.files.insert(path.to_path_buf(), links);
+        if self.parse_code_imports && is_code_path(path) {
+            links.extend(code_imports(path, content));
+        }
+        if is_html_path(path) {
+            let html = html_links(path, &self.root_path, content);
+            links.extend(html.files);
+            external_links.extend(html.external);
+        }
 
-                    let tag_re = Regex::new(r"#(\w+)").unwrap();
-                    let tags: Vec<_> = tag_re
-                        .captures_iter(&content)
-                        .filter_map(|c| c.get(1))
-                        .map(|m| m.as_str().to_string())
-                        .collect();
-                    if !tags.is_empty() {
-                        self.tags.insert(path.to_path_buf(), tags);
+        self.files.insert(path.to_path_buf(), links);
+        if !external_links.is_empty() {
+            self.external_links
+                .insert(path.to_path_buf(), external_links);
+        }
+        if !broken_links.is_empty() {
+            self.broken_links.insert(path.to_path_buf(), broken_links);
+        }
+        if !aliases.is_empty() {
+            self.link_aliases.insert(path.to_path_buf(), aliases);
+        }
+
+        // One entry per tag in first-seen order, lowercased so `#TODO` and `#todo` match
+        let tag_re = Regex::new(r"#(\w+)").unwrap();
+        let mut seen = HashSet::new();
+        let tags: Vec<_> = tag_re
+            .captures_iter(content)
+            .filter_map(|c| c.get(1))
+            .map(|m| m.as_str().to_string())
+            .chain(frontmatter_tags(content))
+            .map(|tag| tag.to_lowercase())
+            .filter(|tag| seen.insert(tag.clone()))
+            .collect();
+        if !tags.is_empty() {
+            self.tags.insert(path.to_path_buf(), tags);
+        }
+    }
+
+    // Members become files under the archive's path, e.g. `notes.zip/a/b.md`. Relative
+    // links resolve inside the archive; nested archives are left unopened.
+    fn scan_archive(&mut self, zip_path: &Path) {
+        let modified = fs::metadata(zip_path)
+            .and_then(|metadata| metadata.modified())
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+        let members = match fs::read(zip_path)
+            .map_err(|e| e.to_string())
+            .and_then(|bytes| read_zip(&bytes))
+        {
+            Ok(members) => members,
+            Err(e) => {
+                self.scan_errors.push((zip_path.to_path_buf(), e));
+                return;
+            }
+        };
+
+        for member in members {
+            let path = zip_path.join(&member.name);
+            if (!self.show_hidden && is_hidden_path(&path)) || self.is_excluded(&path) {
+                continue;
+            }
+            self.file_metadata
+                .insert(path.clone(), (member.data.len() as u64, modified));
+            // Images, PDFs and other binary members are shown as plain nodes
+            let Ok(content) = String::from_utf8(member.data) else {
+                self.files.insert(path, Vec::new());
+                continue;
+            };
+            self.process_content(&path, &content);
+            if let Some(dir) = path.parent() {
+                let resolve = |link: PathBuf| {
+                    if link.is_relative() {
+                        normalize_lexically(&dir.join(link))
+                    } else {
+                        link
                     }
+                };
+                if let Some(links) = self.files.get_mut(&path) {
+                    *links = links.drain(..).map(resolve).collect();
+                }
+                if let Some(aliases) = self.link_aliases.get_mut(&path) {
+                    *aliases = aliases
+                        .drain()
+                        .map(|(link, alias)| (resolve(link), alias))
+                        .collect();
                 }
             }
+            self.archive_text.insert(path, content);
         }
-        Ok(())
+    }
+}
+
+// Reference labels match case-insensitively with runs of whitespace collapsed
+fn normalize_reference_label(label: &str) -> String {
+    label
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+// `[ref]: target` definitions in a markdown file, keyed by normalized label
+fn reference_definitions(content: &str) -> HashMap<String, String> {
+    let definition_re = Regex::new(r"(?m)^ {0,3}\[([^\]]+)\]:[ \t]*<?([^\s>]+)>?").unwrap();
+    let mut definitions = HashMap::new();
+    for cap in definition_re.captures_iter(content) {
+        // The first definition of a label wins
+        definitions
+            .entry(normalize_reference_label(&cap[1]))
+            .or_insert_with(|| cap[2].to_string());
+    }
+    definitions
+}
+
+// `tags:` of a leading `---` frontmatter block, written as `[a, b]`, `a, b` or a `- a`
+// list. A leading `#` is dropped, so these merge with inline tags of the same name.
+fn frontmatter_tags(content: &str) -> Vec<String> {
+    let mut lines = content.lines();
+    if lines.next().map(str::trim_end) != Some("---") {
+        return Vec::new();
+    }
+    let clean = |value: &str| {
+        value
+            .trim()
+            .trim_matches(['"', '\''])
+            .trim_start_matches('#')
+            .to_string()
+    };
+
+    let mut tags = Vec::new();
+    let mut in_tag_list = false;
+    for line in lines {
+        let trimmed = line.trim();
+        if trimmed == "---" || trimmed == "..." {
+            tags.retain(|tag: &String| !tag.is_empty());
+            return tags;
+        }
+        if in_tag_list {
+            if let Some(item) = trimmed.strip_prefix('-') {
+                tags.push(clean(item));
+                continue;
+            }
+            if trimmed.is_empty() {
+                continue;
+            }
+            in_tag_list = false;
+        }
+        // Only the top-level key; nested `tags:` belong to something else
+        if let Some(value) = line.strip_prefix("tags:") {
+            let value = value.trim();
+            if value.is_empty() {
+                in_tag_list = true;
+            } else {
+                let value = value.trim_start_matches('[').trim_end_matches(']');
+                tags.extend(value.split(',').map(clean));
+            }
+        }
+    }
+    // No closing `---`, so this was never frontmatter
+    Vec::new()
+}
+
+// Groups of files with identical contents. Files larger than `max_size` are skipped.
+pub fn find_duplicates(paths: &[PathBuf], max_size: u64) -> Vec<Vec<PathBuf>> {
+    // Only files sharing a size can be identical, so hash those alone
+    let mut by_size: HashMap<u64, Vec<&PathBuf>> = HashMap::new();
+    for path in paths {
+        if let Ok(metadata) = fs::metadata(path)
+            && metadata.is_file()
+            && metadata.len() <= max_size
+        {
+            by_size.entry(metadata.len()).or_default().push(path);
+        }
+    }
+
+    let mut by_hash: HashMap<blake3::Hash, Vec<PathBuf>> = HashMap::new();
+    for candidates in by_size.values().filter(|candidates| candidates.len() > 1) {
+        for path in candidates {
+            if let Ok(bytes) = fs::read(path) {
+                by_hash
+                    .entry(blake3::hash(&bytes))
+                    .or_default()
+                    .push((*path).clone());
+            }
+        }
+    }
+
+    let mut groups: Vec<Vec<PathBuf>> = by_hash
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .map(|mut group| {
+            group.sort();
+            group
+        })
+        .collect();
+    groups.sort();
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::channel;
+
+    fn write(path: &Path, content: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn only_files_with_identical_bytes_are_duplicates() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = [
+            ("a.md", "same text"),
+            ("sub/copy.md", "same text"),
+            // Same size, different bytes
+            ("other.md", "some text"),
+            ("short.md", "same"),
+        ];
+        for (name, content) in files {
+            write(&dir.path().join(name), content);
+        }
+        let paths: Vec<_> = files
+            .iter()
+            .map(|(name, _)| dir.path().join(name))
+            .collect();
+
+        assert_eq!(
+            find_duplicates(&paths, u64::MAX),
+            vec![vec![
+                dir.path().join("a.md"),
+                dir.path().join("sub/copy.md")
+            ]]
+        );
+        // Files over the size cap aren't hashed
+        assert!(find_duplicates(&paths, 4).is_empty());
+    }
+
+    #[test]
+    fn tag_filter_keeps_only_files_carrying_a_matching_tag() {
+        let dir = tempfile::tempdir().unwrap();
+        for (file, content) in [
+            ("a.md", "#project/alpha [[b]]"),
+            ("b.md", "#reading"),
+            ("c.md", "#project/beta"),
+            ("plain.md", "[[a]]"),
+        ] {
+            write(&dir.path().join(file), content);
+        }
+        let mut scanner = FileScanner::new(dir.path());
+        scanner
+            .scan_directory_with_progress(dir.path(), channel().0)
+            .unwrap();
+
+        // The file set Links mode draws when the tag filter is "project"
+        let mut kept: Vec<_> = scanner
+            .files
+            .keys()
+            .filter(|path| scanner.has_tag_matching(path, "project"))
+            .map(|path| path.file_name().unwrap().to_owned())
+            .collect();
+        kept.sort();
+        assert_eq!(kept, ["a.md", "c.md"]);
+        assert!(!scanner.has_tag_matching(&dir.path().join("plain.md"), ""));
+    }
+
+    #[test]
+    fn tags_export_as_json_by_file_and_by_tag() {
+        let mut scanner = FileScanner::new("/notes");
+        for (path, tags) in [
+            ("/notes/b.md", vec!["rust", "egui", "rust"]),
+            ("/notes/a.md", vec!["rust"]),
+        ] {
+            scanner.tags.insert(
+                PathBuf::from(path),
+                tags.into_iter().map(String::from).collect(),
+            );
+        }
+
+        let by_file = r#"{
+  "/notes/a.md": [
+    "rust"
+  ],
+  "/notes/b.md": [
+    "egui",
+    "rust"
+  ]
+}"#;
+        let by_tag = r#"{
+  "egui": [
+    "/notes/b.md"
+  ],
+  "rust": [
+    "/notes/a.md",
+    "/notes/b.md"
+  ]
+}"#;
+        assert_eq!(scanner.export_tags_json(false).unwrap(), by_file);
+        assert_eq!(scanner.export_tags_json(true).unwrap(), by_tag);
+    }
+
+    #[test]
+    fn scan_finishes_while_the_shared_scanner_stays_locked() {
+        let dir = tempfile::tempdir().unwrap();
+        for i in 0..50 {
+            write(&dir.path().join(format!("note{i}.md")), "#tag [[note0]]");
+        }
+        let shared = Arc::new(Mutex::new(FileScanner::new(dir.path())));
+        let mut scanned = shared.lock().unwrap().detached();
+        scanned.stream_into(shared.clone());
+
+        // Held by "the UI" for the whole file loop
+        let guard = shared.lock().unwrap();
+        let (done_sender, done_receiver) = channel();
+        let path = dir.path().to_path_buf();
+        let scan = std::thread::spawn(move || {
+            let result = scanned.scan_directory_with_progress(&path, channel().0);
+            done_sender.send(()).unwrap();
+            (scanned, result)
+        });
+        done_receiver
+            .recv_timeout(std::time::Duration::from_secs(10))
+            .expect("scan waited on the scanner lock");
+        drop(guard);
+
+        let (scanned, result) = scan.join().unwrap();
+        result.unwrap();
+        shared.lock().unwrap().merge_scan(dir.path(), scanned);
+        assert_eq!(shared.lock().unwrap().files.len(), 50);
+    }
+
+    #[test]
+    fn excluded_globs_are_skipped_and_the_rest_scanned() {
+        let dir = tempfile::tempdir().unwrap();
+        for file in [
+            "a.md",
+            "draft.tmp.md",
+            "archive/old.md",
+            "deep/archive/older.md",
+            "deep/keep.md",
+            "deep/nested.tmp.md",
+        ] {
+            write(&dir.path().join(file), "#tag");
+        }
+
+        let mut scanner = FileScanner::new(dir.path());
+        let globs = ["**/archive/**".to_string(), "*.tmp.md".to_string()];
+        scanner.set_exclude_globs(&globs).unwrap();
+        assert!(scanner.set_exclude_globs(&["a/[b".to_string()]).is_err());
+        scanner
+            .scan_directory_with_progress(dir.path(), channel().0)
+            .unwrap();
+
+        let mut scanned: Vec<_> = scanner
+            .files
+            .keys()
+            .map(|path| path.strip_prefix(dir.path()).unwrap().to_path_buf())
+            .collect();
+        scanned.sort();
+        // `*` stays within one folder, so the nested draft is kept
+        assert_eq!(
+            scanned,
+            ["a.md", "deep/keep.md", "deep/nested.tmp.md"]
+                .map(PathBuf::from)
+                .to_vec()
+        );
+    }
+
+    #[test]
+    fn files_over_the_size_cap_are_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        write(&dir.path().join("small.md"), "#a");
+        write(&dir.path().join("sub/large.md"), &"#b ".repeat(100));
+
+        let mut scanner = FileScanner::new(dir.path());
+        scanner.set_max_file_size(Some(100));
+        scanner
+            .scan_directory_with_progress(dir.path(), channel().0)
+            .unwrap();
+
+        assert_eq!(
+            scanner.files.keys().collect::<Vec<_>>(),
+            vec![&dir.path().join("small.md")]
+        );
+    }
+
+    #[test]
+    fn scan_finishes_after_progress_receiver_is_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        write(&dir.path().join("a/one.md"), "#first");
+        write(&dir.path().join("b/two.md"), "#second");
+        write(&dir.path().join("three.md"), "[one](a/one.md)");
+
+        let (sender, receiver) = channel();
+        drop(receiver);
+        let mut scanner = FileScanner::new(dir.path());
+        scanner
+            .scan_directory_with_progress(dir.path(), sender)
+            .unwrap();
+
+        assert_eq!(scanner.files.len(), 3);
+        assert!(scanner.scan_errors.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unreadable_file_is_recorded_and_skipped() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let unreadable = dir.path().join("locked/secret.md");
+        write(&dir.path().join("readable.md"), "#kept");
+        write(&dir.path().join("nested/also.md"), "#kept");
+        write(&unreadable, "#hidden");
+        fs::set_permissions(&unreadable, fs::Permissions::from_mode(0o000)).unwrap();
+        // Privileged users read it anyway, so there is nothing to test
+        if fs::read(&unreadable).is_ok() {
+            return;
+        }
+
+        let (sender, _receiver) = channel();
+        let mut scanner = FileScanner::new(dir.path());
+        scanner
+            .scan_directory_with_progress(dir.path(), sender)
+            .unwrap();
+
+        assert!(scanner.files.contains_key(&dir.path().join("readable.md")));
+        assert!(
+            scanner
+                .files
+                .contains_key(&dir.path().join("nested/also.md"))
+        );
+        assert!(!scanner.files.contains_key(&unreadable));
+        let errors: Vec<&PathBuf> = scanner.scan_errors.iter().map(|(path, _)| path).collect();
+        assert_eq!(errors, [&unreadable]);
+    }
+
+    #[test]
+    fn wikilink_aliases_leave_only_the_target_path() {
+        let dir = tempfile::tempdir().unwrap();
+        write(
+            &dir.path().join("note.md"),
+            "[[a|b]] then [[a]] and [a](b.md)",
+        );
+        let mut scanner = FileScanner::new(dir.path());
+        scanner
+            .scan_directory_with_progress(dir.path(), channel().0)
+            .unwrap();
+        let note = dir.path().join("note.md");
+
+        assert_eq!(
+            scanner.files[&note],
+            [
+                dir.path().join("a"),
+                dir.path().join("a"),
+                dir.path().join("b.md")
+            ]
+        );
+        assert_eq!(
+            scanner.link_aliases[&note],
+            HashMap::from([(dir.path().join("a"), "b".to_string())])
+        );
+        assert!(!scanner.broken_links.contains_key(&note));
+    }
+
+    #[test]
+    fn frontmatter_and_inline_tags_merge_without_duplicates() {
+        let dir = tempfile::tempdir().unwrap();
+        write(
+            &dir.path().join("inline.md"),
+            "---\ntitle: Graphs\ntags: [rust, \"graph\"]\n---\nBody #Rust and #notes #graph\n",
+        );
+        write(
+            &dir.path().join("list.md"),
+            "---\ntags:\n  - '#ideas'\n  - rust\n---\n#ideas again\n",
+        );
+        let mut scanner = FileScanner::new(dir.path());
+        scanner
+            .scan_directory_with_progress(dir.path(), channel().0)
+            .unwrap();
+
+        assert_eq!(
+            scanner.tags[&dir.path().join("inline.md")],
+            ["rust", "notes", "graph"]
+        );
+        assert_eq!(scanner.tags[&dir.path().join("list.md")], ["ideas", "rust"]);
     }
 }
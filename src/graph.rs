@@ -1,19 +1,84 @@
 // src/graph.rs
 use crate::file_scan;
 use petgraph::stable_graph::StableGraph;
-use petgraph::{Graph, graph::NodeIndex};
-use std::collections::HashMap;
-use std::path::PathBuf;
+use petgraph::visit::{EdgeRef, IntoEdgeReferences};
+use petgraph::{Direction, graph::NodeIndex};
+use rand::Rng;
+use rand::seq::IndexedRandom;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+// Pseudo-tag grouping files that have no tags
+pub const UNTAGGED_TAG: &str = "(untagged)";
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum GraphNode {
-    File(String),
+    // The real path, so files with non-UTF-8 names still resolve
+    File(PathBuf),
     Tag(String),
+    External(String),
+    // A folder standing in for the files in it, when directory grouping is on
+    Directory(PathBuf),
+}
+
+// Out- and in-neighbours of every node, so neighbour queries don't scan all edges
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AdjacencyIndex {
+    neighbors: HashMap<NodeIndex, (Vec<NodeIndex>, Vec<NodeIndex>)>,
+}
+
+impl AdjacencyIndex {
+    pub fn from_edges(edges: &[(NodeIndex, NodeIndex)]) -> Self {
+        let mut neighbors: HashMap<NodeIndex, (Vec<NodeIndex>, Vec<NodeIndex>)> = HashMap::new();
+        for &(source, target) in edges {
+            neighbors.entry(source).or_default().0.push(target);
+            neighbors.entry(target).or_default().1.push(source);
+        }
+        Self { neighbors }
+    }
+
+    pub fn from_graph(graph: &StableGraph<GraphNode, ()>) -> Self {
+        let edges: Vec<_> = graph
+            .edge_references()
+            .map(|edge| (edge.source(), edge.target()))
+            .collect();
+        Self::from_edges(&edges)
+    }
+
+    pub fn outgoing(&self, node: NodeIndex) -> &[NodeIndex] {
+        self.neighbors.get(&node).map_or(&[], |(out, _)| out)
+    }
+
+    pub fn incoming(&self, node: NodeIndex) -> &[NodeIndex] {
+        self.neighbors
+            .get(&node)
+            .map_or(&[], |(_, incoming)| incoming)
+    }
+
+    // Neighbours in either direction; a node linked both ways appears twice
+    pub fn neighbors(&self, node: NodeIndex) -> impl Iterator<Item = NodeIndex> + '_ {
+        self.outgoing(node)
+            .iter()
+            .chain(self.incoming(node))
+            .copied()
+    }
 }
 
 pub struct FileGraph {
     pub graph: StableGraph<GraphNode, ()>,
     pub node_indices: HashMap<PathBuf, NodeIndex>,
+    pub external_node_indices: HashMap<String, NodeIndex>,
+    pub directory_node_indices: HashMap<PathBuf, NodeIndex>,
+    // When set, files are drawn as one node per folder, except in these expanded folders
+    pub directory_grouping: Option<HashSet<PathBuf>>,
+    // Only filled by update_unified
+    pub tag_node_indices: HashMap<String, NodeIndex>,
+    pub adjacency: AdjacencyIndex,
+    // Nodes left out because the graph reached its node cap
+    pub omitted_nodes: usize,
 }
 
 pub struct TagGraph {
@@ -21,6 +86,10 @@ pub struct TagGraph {
     pub file_node_indices: HashMap<PathBuf, NodeIndex>,
     pub image_node_indices: HashMap<PathBuf, NodeIndex>,
     pub tag_node_indices: HashMap<String, NodeIndex>,
+    pub cooccurrence: HashMap<(NodeIndex, NodeIndex), usize>,
+    pub adjacency: AdjacencyIndex,
+    // Nodes left out because the graph reached its node cap
+    pub omitted_nodes: usize,
 }
 
 impl FileGraph {
@@ -28,44 +97,310 @@ impl FileGraph {
         Self {
             graph: StableGraph::new(),
             node_indices: HashMap::new(),
+            external_node_indices: HashMap::new(),
+            directory_node_indices: HashMap::new(),
+            directory_grouping: None,
+            tag_node_indices: HashMap::new(),
+            adjacency: AdjacencyIndex::default(),
+            omitted_nodes: 0,
         }
     }
 
-    pub fn build_from_scanner(&mut self, scanner: &file_scan::FileScanner) {
+    // Stops adding nodes at `max_nodes`; the scanner keeps everything, and the number
+    // left out is reported in `omitted_nodes`
+    // `progress` gets the fraction of files processed so far, from 0 to 1
+    pub fn build_from_scanner(
+        &mut self,
+        scanner: &file_scan::FileScanner,
+        max_nodes: usize,
+        progress: &mut dyn FnMut(f32),
+    ) {
         self.graph.clear();
         self.node_indices.clear();
+        self.external_node_indices.clear();
+        self.directory_node_indices.clear();
+        self.tag_node_indices.clear();
+        self.update_with_progress(scanner, max_nodes, progress);
+    }
 
-        // Add all files as nodes, including orphaned ones
-        for (path, _) in &scanner.files {
-            let node_data = GraphNode::File(path.display().to_string());
-            let node_idx = self.graph.add_node(node_data);
-            self.node_indices.insert(path.clone(), node_idx);
+    // Links graph plus a node per tag, with an edge from each file to its tags
+    pub fn update_unified(&mut self, scanner: &file_scan::FileScanner, max_nodes: usize) {
+        self.update_from_scanner(scanner, max_nodes);
+
+        let mut tags: Vec<&String> = scanner
+            .tags
+            .iter()
+            .filter(|(path, _)| self.node_indices.contains_key(*path))
+            .flat_map(|(_, tags)| tags)
+            .collect();
+        tags.sort();
+        tags.dedup();
+        let tag_room = max_nodes.saturating_sub(
+            self.node_indices.len()
+                + self.directory_node_indices.len()
+                + self.external_node_indices.len(),
+        );
+        self.omitted_nodes += tags.len().saturating_sub(tag_room);
+        tags.truncate(tag_room);
+        let wanted_tags: HashSet<&String> = tags.iter().copied().collect();
+
+        self.tag_node_indices.retain(|tag, idx| {
+            let keep = wanted_tags.contains(tag);
+            if !keep {
+                self.graph.remove_node(*idx);
+            }
+            keep
+        });
+        for tag in tags {
+            if !self.tag_node_indices.contains_key(tag) {
+                let node_idx = self.graph.add_node(GraphNode::Tag(tag.clone()));
+                self.tag_node_indices.insert(tag.clone(), node_idx);
+            }
         }
 
-        // Add all images as nodes
+        // update_from_scanner already cleared the tag edges along with the rest
+        for (path, tags) in &scanner.tags {
+            if let Some(&file_idx) = self.node_indices.get(path) {
+                for tag in tags {
+                    if let Some(&tag_idx) = self.tag_node_indices.get(tag) {
+                        self.graph.add_edge(file_idx, tag_idx, ());
+                    }
+                }
+            }
+        }
+        self.adjacency = AdjacencyIndex::from_graph(&self.graph);
+    }
+
+    // Like build_from_scanner, but nodes that are still wanted keep their indices, so
+    // their layout survives while files are added (e.g. during a scan)
+    pub fn update_from_scanner(&mut self, scanner: &file_scan::FileScanner, max_nodes: usize) {
+        self.update_with_progress(scanner, max_nodes, &mut |_| {});
+    }
+
+    fn update_with_progress(
+        &mut self,
+        scanner: &file_scan::FileScanner,
+        max_nodes: usize,
+        progress: &mut dyn FnMut(f32),
+    ) {
+        // All files, including orphans and images. Sorted so the same files make the
+        // cut every time.
+        let mut paths: Vec<&PathBuf> = scanner.files.keys().collect();
         for path in &scanner.images {
+            if !scanner.files.contains_key(path) {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+        paths.dedup();
+        self.omitted_nodes = paths.len().saturating_sub(max_nodes);
+        paths.truncate(max_nodes);
+        let wanted_files: HashSet<&PathBuf> = paths.iter().copied().collect();
+
+        // With directory grouping, files outside expanded folders share their folder's node
+        let group_of = |path: &Path| -> Option<PathBuf> {
+            let expanded = self.directory_grouping.as_ref()?;
+            let dir = path.parent()?;
+            (!expanded.contains(dir)).then(|| dir.to_path_buf())
+        };
+        let (grouped, paths): (Vec<&PathBuf>, Vec<&PathBuf>) =
+            paths.into_iter().partition(|path| group_of(path).is_some());
+        let mut dirs: Vec<PathBuf> = grouped.iter().filter_map(|path| group_of(path)).collect();
+        dirs.sort();
+        dirs.dedup();
+        let wanted_dirs: HashSet<&PathBuf> = dirs.iter().collect();
+        let wanted_nodes: HashSet<&PathBuf> = paths.iter().copied().collect();
+
+        // External URLs, shared by every file linking to the same URL
+        let mut urls: Vec<&String> = scanner
+            .external_links
+            .iter()
+            .filter(|(source, _)| wanted_files.contains(source))
+            .flat_map(|(_, urls)| urls)
+            .collect();
+        urls.sort();
+        urls.dedup();
+        let url_room = max_nodes.saturating_sub(paths.len() + dirs.len());
+        self.omitted_nodes += urls.len().saturating_sub(url_room);
+        urls.truncate(url_room);
+        let wanted_urls: HashSet<&String> = urls.iter().copied().collect();
+
+        // Drop nodes that are gone, add new ones
+        self.node_indices.retain(|path, idx| {
+            let keep = wanted_nodes.contains(path);
+            if !keep {
+                self.graph.remove_node(*idx);
+            }
+            keep
+        });
+        self.directory_node_indices.retain(|dir, idx| {
+            let keep = wanted_dirs.contains(dir);
+            if !keep {
+                self.graph.remove_node(*idx);
+            }
+            keep
+        });
+        self.external_node_indices.retain(|url, idx| {
+            let keep = wanted_urls.contains(url);
+            if !keep {
+                self.graph.remove_node(*idx);
+            }
+            keep
+        });
+        // Adding nodes and linking their files each count as one step
+        let total_steps = (paths.len() + scanner.files.len()).max(1) as f32;
+        let mut steps = 0;
+        for path in paths {
+            steps += 1;
+            progress(steps as f32 / total_steps);
             if !self.node_indices.contains_key(path) {
-                let node_data = GraphNode::File(path.display().to_string());
-                let node_idx = self.graph.add_node(node_data);
+                let node_idx = self.graph.add_node(GraphNode::File(path.clone()));
                 self.node_indices.insert(path.clone(), node_idx);
             }
         }
+        for dir in dirs {
+            if !self.directory_node_indices.contains_key(&dir) {
+                let node_idx = self.graph.add_node(GraphNode::Directory(dir.clone()));
+                self.directory_node_indices.insert(dir, node_idx);
+            }
+        }
+        for url in urls {
+            if !self.external_node_indices.contains_key(url) {
+                let node_idx = self.graph.add_node(GraphNode::External(url.clone()));
+                self.external_node_indices.insert(url.clone(), node_idx);
+            }
+        }
 
-        // Add links between nodes
+        // The node each wanted file is drawn as: its own, or its folder's
+        let file_node = |path: &Path| -> Option<NodeIndex> {
+            match group_of(path) {
+                Some(dir) => self.directory_node_indices.get(&dir).copied(),
+                None => self.node_indices.get(path).copied(),
+            }
+        };
+
+        // Files by stem, so `[[index]]` still finds `notes/index.md`
+        let files_by_stem = files_by_stem(wanted_files.iter().copied());
+
+        // Edges carry no layout, so they are simply rebuilt. A folder node gets the union
+        // of its files' links, once each, without links between its own files.
+        let mut edges = Vec::new();
         for (source_path, links) in &scanner.files {
-            if let Some(&source_idx) = self.node_indices.get(source_path) {
-                for target_path in links {
-                    if let Some(&target_idx) = self.node_indices.get(target_path) {
-                        self.graph.add_edge(source_idx, target_idx, ());
+            steps += 1;
+            progress(steps as f32 / total_steps);
+            if !wanted_files.contains(source_path) {
+                continue;
+            }
+            let Some(source_idx) = file_node(source_path) else {
+                continue;
+            };
+            for target_path in links {
+                let target_path = wanted_files
+                    .get(target_path)
+                    .copied()
+                    .or_else(|| resolve_by_stem(target_path, source_path, &files_by_stem));
+                if let Some(target_idx) = target_path.and_then(|path| file_node(path)) {
+                    edges.push((source_idx, target_idx, target_path != Some(source_path)));
+                }
+            }
+        }
+        for (source_path, urls) in &scanner.external_links {
+            if wanted_files.contains(source_path)
+                && let Some(source_idx) = file_node(source_path)
+            {
+                for url in urls {
+                    if let Some(&url_idx) = self.external_node_indices.get(url) {
+                        edges.push((source_idx, url_idx, true));
                     }
                 }
             }
         }
+
+        self.graph.clear_edges();
+        let mut directory_edges = HashSet::new();
+        for (source_idx, target_idx, distinct_files) in edges {
+            let grouped = matches!(self.graph[source_idx], GraphNode::Directory(_))
+                || matches!(self.graph[target_idx], GraphNode::Directory(_));
+            if grouped
+                && ((source_idx == target_idx && distinct_files)
+                    || !directory_edges.insert((source_idx, target_idx)))
+            {
+                continue;
+            }
+            self.graph.add_edge(source_idx, target_idx, ());
+        }
+        self.adjacency = AdjacencyIndex::from_graph(&self.graph);
+    }
+
+    // Files linking to `path`, sorted
+    pub fn backlinks(&self, path: &Path) -> Vec<PathBuf> {
+        let Some(&node_idx) = self.node_indices.get(path) else {
+            return Vec::new();
+        };
+        let mut sources: Vec<PathBuf> = self
+            .adjacency
+            .incoming(node_idx)
+            .iter()
+            .filter(|&&source| source != node_idx)
+            .filter_map(|&source| match &self.graph[source] {
+                GraphNode::File(source_path) => Some(source_path.clone()),
+                _ => None,
+            })
+            .collect();
+        sources.sort();
+        sources.dedup();
+        sources
     }
 
     pub fn node_indices(&self) -> &HashMap<PathBuf, NodeIndex> {
         &self.node_indices
     }
+
+    // Nodes with no links in or out, across the whole graph
+    pub fn orphans(&self) -> HashSet<NodeIndex> {
+        self.graph
+            .node_indices()
+            .filter(|&node_idx| self.graph.neighbors_undirected(node_idx).next().is_none())
+            .collect()
+    }
+}
+
+// Files grouped by stem for `resolve_by_stem`, each group sorted by path
+pub fn files_by_stem<'a>(
+    files: impl IntoIterator<Item = &'a PathBuf>,
+) -> HashMap<&'a OsStr, Vec<&'a PathBuf>> {
+    let mut by_stem: HashMap<&OsStr, Vec<&PathBuf>> = HashMap::new();
+    for path in files {
+        if let Some(stem) = path.file_stem() {
+            by_stem.entry(stem).or_default().push(path);
+        }
+    }
+    for candidates in by_stem.values_mut() {
+        candidates.sort();
+    }
+    by_stem
+}
+
+// A link naming no scanned file goes to a file with the same stem: `<link>.md` first,
+// then one in the source's folder, then the first by path
+pub fn resolve_by_stem<'a>(
+    link: &Path,
+    source: &Path,
+    files_by_stem: &HashMap<&OsStr, Vec<&'a PathBuf>>,
+) -> Option<&'a PathBuf> {
+    let candidates = files_by_stem.get(link.file_stem()?)?;
+    let mut with_md = link.as_os_str().to_owned();
+    with_md.push(".md");
+    candidates
+        .iter()
+        .find(|candidate| candidate.as_os_str() == with_md)
+        .or_else(|| {
+            candidates
+                .iter()
+                .find(|candidate| candidate.parent() == source.parent())
+        })
+        .or_else(|| candidates.first())
+        .copied()
 }
 
 impl TagGraph {
@@ -75,46 +410,164 @@ impl TagGraph {
             file_node_indices: HashMap::new(),
             tag_node_indices: HashMap::new(),
             image_node_indices: HashMap::new(),
+            cooccurrence: HashMap::new(),
+            adjacency: AdjacencyIndex::default(),
+            omitted_nodes: 0,
         }
     }
 
-    pub fn build_from_tags(&mut self, scanner: &file_scan::FileScanner) {
+    pub fn build_from_tags(
+        &mut self,
+        scanner: &file_scan::FileScanner,
+        include_untagged: bool,
+        max_nodes: usize,
+        progress: &mut dyn FnMut(f32),
+    ) {
         self.graph.clear();
         self.file_node_indices.clear();
         self.image_node_indices.clear();
         self.tag_node_indices.clear();
+        self.cooccurrence.clear();
+        self.update_tags_with_progress(scanner, include_untagged, max_nodes, progress);
+    }
 
-        // Add all files with tags
+    // Bring the graph in line with the scanner without rebuilding it, so existing
+    // nodes keep their indices (and with them their physics positions). At most
+    // `max_nodes` file and image nodes are kept; tag nodes don't count towards it.
+    pub fn update_from_tags(
+        &mut self,
+        scanner: &file_scan::FileScanner,
+        include_untagged: bool,
+        max_nodes: usize,
+    ) {
+        self.update_tags_with_progress(scanner, include_untagged, max_nodes, &mut |_| {});
+    }
+
+    fn update_tags_with_progress(
+        &mut self,
+        scanner: &file_scan::FileScanner,
+        include_untagged: bool,
+        max_nodes: usize,
+        progress: &mut dyn FnMut(f32),
+    ) {
+        let all_images: HashSet<&PathBuf> = scanner.images.iter().collect();
+
+        // Wanted tags per file node, mirroring build_from_tags
+        let mut wanted: HashMap<&PathBuf, HashSet<&str>> = HashMap::new();
         for (file_path, tags) in &scanner.tags {
             if !tags.is_empty() {
-                let node_data = GraphNode::File(file_path.display().to_string());
-                let node_idx = self.graph.add_node(node_data);
-                self.file_node_indices.insert(file_path.clone(), node_idx);
+                wanted.insert(file_path, tags.iter().map(String::as_str).collect());
+            }
+        }
+        if include_untagged {
+            for path in scanner.files.keys() {
+                if scanner.tags.get(path).is_none_or(|tags| tags.is_empty())
+                    && !all_images.contains(path)
+                {
+                    wanted.insert(path, HashSet::from([UNTAGGED_TAG]));
+                }
+            }
+        }
+
+        // Over the cap, keep the same (sorted) files every time
+        let wanted_count = wanted.len() + all_images.len();
+        if wanted.len() > max_nodes {
+            let mut paths: Vec<&PathBuf> = wanted.keys().copied().collect();
+            paths.sort();
+            for path in &paths[max_nodes..] {
+                wanted.remove(*path);
             }
         }
+        let mut images: Vec<&PathBuf> = all_images.into_iter().collect();
+        images.sort();
+        images.truncate(max_nodes - wanted.len());
+        let images: HashSet<&PathBuf> = images.into_iter().collect();
+        self.omitted_nodes = wanted_count - wanted.len() - images.len();
+
+        // Drop files and images that are gone
+        self.file_node_indices.retain(|path, idx| {
+            let keep = wanted.contains_key(path);
+            if !keep {
+                self.graph.remove_node(*idx);
+            }
+            keep
+        });
+        self.image_node_indices.retain(|path, idx| {
+            let keep = images.contains(path);
+            if !keep {
+                self.graph.remove_node(*idx);
+            }
+            keep
+        });
+
+        // Each image and each file is one step
+        let total_steps = (images.len() + wanted.len()).max(1) as f32;
+        let mut steps = 0;
 
-        // Add all images
-        for image_path in &scanner.images {
-            if !self.image_node_indices.contains_key(image_path) {
-                let node_data = GraphNode::File(image_path.display().to_string());
+        // Add new images, only those within the cap
+        for image_path in &images {
+            steps += 1;
+            progress(steps as f32 / total_steps);
+            if !self.image_node_indices.contains_key(*image_path) {
+                let node_data = GraphNode::File((*image_path).clone());
                 let node_idx = self.graph.add_node(node_data);
-                self.image_node_indices.insert(image_path.clone(), node_idx);
+                self.image_node_indices
+                    .insert((*image_path).clone(), node_idx);
             }
         }
 
-        // Create tag relationships
-        for (file_path, tags) in &scanner.tags {
-            if let Some(&file_node_idx) = self.file_node_indices.get(file_path) {
-                for tag in tags {
-                    let tag_node_idx =
-                        *self.tag_node_indices.entry(tag.clone()).or_insert_with(|| {
-                            let node_data = GraphNode::Tag(tag.clone());
-                            self.graph.add_node(node_data)
-                        });
+        // Add new files and reconcile their tag edges
+        for (file_path, tags) in &wanted {
+            steps += 1;
+            progress(steps as f32 / total_steps);
+            let file_node_idx = *self
+                .file_node_indices
+                .entry((*file_path).clone())
+                .or_insert_with(|| self.graph.add_node(GraphNode::File((*file_path).clone())));
+
+            let stale_edges: Vec<_> = self
+                .graph
+                .edges_directed(file_node_idx, Direction::Incoming)
+                .filter(|edge| match &self.graph[edge.source()] {
+                    GraphNode::Tag(tag) => !tags.contains(tag.as_str()),
+                    _ => false,
+                })
+                .map(|edge| edge.id())
+                .collect();
+            for edge in stale_edges {
+                self.graph.remove_edge(edge);
+            }
+
+            for tag in tags {
+                let tag_node_idx = *self
+                    .tag_node_indices
+                    .entry(tag.to_string())
+                    .or_insert_with(|| self.graph.add_node(GraphNode::Tag(tag.to_string())));
+                if self.graph.find_edge(tag_node_idx, file_node_idx).is_none() {
                     self.graph.add_edge(tag_node_idx, file_node_idx, ());
                 }
             }
         }
+
+        // Tags no file uses any more
+        self.tag_node_indices.retain(|_, idx| {
+            let keep = self.graph.neighbors_undirected(*idx).next().is_some();
+            if !keep {
+                self.graph.remove_node(*idx);
+            }
+            keep
+        });
+
+        self.cooccurrence.clear();
+        for ((tag_a, tag_b), count) in tag_cooccurrence(&scanner.tags) {
+            if let (Some(&a_idx), Some(&b_idx)) = (
+                self.tag_node_indices.get(&tag_a),
+                self.tag_node_indices.get(&tag_b),
+            ) {
+                self.cooccurrence.insert((a_idx, b_idx), count);
+            }
+        }
+        self.adjacency = AdjacencyIndex::from_graph(&self.graph);
     }
 
     pub fn file_node_indices(&self) -> &HashMap<PathBuf, NodeIndex> {
@@ -125,3 +578,826 @@ impl TagGraph {
         &self.tag_node_indices
     }
 }
+
+// Any one of the eligible nodes, or None when there are none
+pub fn pick_random_node(eligible: &[NodeIndex], rng: &mut impl Rng) -> Option<NodeIndex> {
+    eligible.choose(rng).copied()
+}
+
+// Groups of nodes that link to each other in a loop: strongly connected components of
+// two or more nodes, plus nodes linking to themselves. Largest first.
+pub fn link_cycles(graph: &StableGraph<GraphNode, ()>) -> Vec<Vec<NodeIndex>> {
+    let mut cycles: Vec<Vec<NodeIndex>> = petgraph::algo::tarjan_scc(graph)
+        .into_iter()
+        .filter(|component| match component.as_slice() {
+            [node] => graph.contains_edge(*node, *node),
+            _ => true,
+        })
+        .collect();
+    cycles.sort_by_key(|cycle| std::cmp::Reverse(cycle.len()));
+    cycles
+}
+
+// Nodes with no edges at all
+pub fn orphan_nodes(nodes: &[NodeIndex], edges: &[(NodeIndex, NodeIndex)]) -> Vec<NodeIndex> {
+    let linked: HashSet<NodeIndex> = edges
+        .iter()
+        .flat_map(|&(source, target)| [source, target])
+        .collect();
+    nodes
+        .iter()
+        .copied()
+        .filter(|node| !linked.contains(node))
+        .collect()
+}
+
+// Keep nodes with at least `min_degree` incident edges (0 keeps everything)
+pub fn filter_by_min_degree(
+    nodes: &[NodeIndex],
+    edges: &[(NodeIndex, NodeIndex)],
+    min_degree: usize,
+) -> (Vec<NodeIndex>, Vec<(NodeIndex, NodeIndex)>) {
+    if min_degree == 0 {
+        return (nodes.to_vec(), edges.to_vec());
+    }
+
+    let mut degrees: HashMap<NodeIndex, usize> = HashMap::new();
+    for &(source, target) in edges {
+        *degrees.entry(source).or_default() += 1;
+        *degrees.entry(target).or_default() += 1;
+    }
+
+    let kept_nodes: Vec<NodeIndex> = nodes
+        .iter()
+        .copied()
+        .filter(|node| degrees.get(node).copied().unwrap_or(0) >= min_degree)
+        .collect();
+    let kept: HashSet<NodeIndex> = kept_nodes.iter().copied().collect();
+    let kept_edges = edges
+        .iter()
+        .copied()
+        .filter(|(source, target)| kept.contains(source) && kept.contains(target))
+        .collect();
+
+    (kept_nodes, kept_edges)
+}
+
+// Node plus its direct neighbours, and the edges connecting them
+pub fn neighborhood(
+    node: NodeIndex,
+    adjacency: &AdjacencyIndex,
+) -> (HashSet<NodeIndex>, HashSet<(NodeIndex, NodeIndex)>) {
+    let mut nodes = HashSet::from([node]);
+    let mut incident_edges = HashSet::new();
+    for &target in adjacency.outgoing(node) {
+        nodes.insert(target);
+        incident_edges.insert((node, target));
+    }
+    for &source in adjacency.incoming(node) {
+        nodes.insert(source);
+        incident_edges.insert((source, node));
+    }
+    (nodes, incident_edges)
+}
+
+// Nodes within `depth` hops of `center`, and the edges between them
+pub fn restrict_to_neighborhood(
+    nodes: &[NodeIndex],
+    edges: &[(NodeIndex, NodeIndex)],
+    center: NodeIndex,
+    depth: usize,
+) -> (Vec<NodeIndex>, Vec<(NodeIndex, NodeIndex)>) {
+    let adjacency = AdjacencyIndex::from_edges(edges);
+    let mut reached = HashSet::from([center]);
+    let mut frontier = vec![center];
+    for _ in 0..depth {
+        let mut next_frontier = Vec::new();
+        for &from in &frontier {
+            for to in adjacency.neighbors(from) {
+                if reached.insert(to) {
+                    next_frontier.push(to);
+                }
+            }
+        }
+        if next_frontier.is_empty() {
+            break;
+        }
+        frontier = next_frontier;
+    }
+
+    let kept_nodes = nodes
+        .iter()
+        .copied()
+        .filter(|node| reached.contains(node))
+        .collect();
+    let kept_edges = edges
+        .iter()
+        .copied()
+        .filter(|(source, target)| reached.contains(source) && reached.contains(target))
+        .collect();
+
+    (kept_nodes, kept_edges)
+}
+
+// The draw set while `isolated` is chosen, or the whole set once it's cleared or no
+// longer drawn
+pub fn isolate(
+    nodes: Vec<NodeIndex>,
+    edges: Vec<(NodeIndex, NodeIndex)>,
+    isolated: Option<NodeIndex>,
+    depth: usize,
+) -> (Vec<NodeIndex>, Vec<(NodeIndex, NodeIndex)>) {
+    match isolated {
+        Some(center) if nodes.contains(&center) => {
+            restrict_to_neighborhood(&nodes, &edges, center, depth)
+        }
+        _ => (nodes, edges),
+    }
+}
+
+// Number of files each unordered pair of tags appears on together
+pub fn tag_cooccurrence(tags: &HashMap<PathBuf, Vec<String>>) -> HashMap<(String, String), usize> {
+    let mut counts = HashMap::new();
+    for file_tags in tags.values() {
+        let mut unique: Vec<&String> = file_tags.iter().collect();
+        unique.sort();
+        unique.dedup();
+        for (i, tag_a) in unique.iter().enumerate() {
+            for tag_b in &unique[i + 1..] {
+                *counts
+                    .entry(((*tag_a).clone(), (*tag_b).clone()))
+                    .or_insert(0) += 1;
+            }
+        }
+    }
+    counts
+}
+
+// Tag -> files carrying it
+pub fn tag_file_index(tags: &HashMap<PathBuf, Vec<String>>) -> HashMap<&str, Vec<&Path>> {
+    let mut index: HashMap<&str, Vec<&Path>> = HashMap::new();
+    for (path, file_tags) in tags {
+        for tag in file_tags {
+            index.entry(tag.as_str()).or_default().push(path.as_path());
+        }
+    }
+    index
+}
+
+// Number of tags on each tagged file node, for the badges drawn in Links mode
+pub fn tag_badge_counts(
+    graph: &FileGraph,
+    scanner: &file_scan::FileScanner,
+) -> HashMap<NodeIndex, usize> {
+    graph
+        .node_indices
+        .iter()
+        .filter_map(|(path, &node_idx)| {
+            let count = scanner.tag_count(path);
+            (count > 0).then_some((node_idx, count))
+        })
+        .collect()
+}
+
+// Size and modification time of the file nodes among `nodes`, from the scan rather
+// than the disk, so hovering or sizing nodes costs no stat calls
+pub fn node_metadata(
+    graph: &StableGraph<GraphNode, ()>,
+    scanner: &file_scan::FileScanner,
+    nodes: &[NodeIndex],
+) -> HashMap<NodeIndex, (u64, SystemTime)> {
+    nodes
+        .iter()
+        .filter_map(|&node_idx| match &graph[node_idx] {
+            GraphNode::File(path) => scanner
+                .file_metadata
+                .get(Path::new(path))
+                .map(|&metadata| (node_idx, metadata)),
+            _ => None,
+        })
+        .collect()
+}
+
+// Other files with at least one of `path`'s tags
+pub fn files_sharing_tags<'a>(
+    path: &Path,
+    tags: &HashMap<PathBuf, Vec<String>>,
+    index: &HashMap<&str, Vec<&'a Path>>,
+) -> HashSet<&'a Path> {
+    tags.get(path)
+        .into_iter()
+        .flatten()
+        .filter_map(|tag| index.get(tag.as_str()))
+        .flatten()
+        .copied()
+        .filter(|&other| other != path)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_scan::FileScanner;
+
+    // Scanner holding `files` with their already resolved links
+    fn scanner_with(files: &[(&str, &[&str])]) -> FileScanner {
+        let mut scanner = FileScanner::new("/notes");
+        for (path, links) in files {
+            scanner.files.insert(
+                PathBuf::from(path),
+                links.iter().map(PathBuf::from).collect(),
+            );
+        }
+        scanner
+    }
+
+    fn edge_paths(graph: &FileGraph) -> Vec<(GraphNode, GraphNode)> {
+        let mut edges: Vec<_> = graph
+            .graph
+            .edge_references()
+            .map(|edge| {
+                (
+                    graph.graph[edge.source()].clone(),
+                    graph.graph[edge.target()].clone(),
+                )
+            })
+            .collect();
+        edges.sort_by_key(|(source, target)| format!("{:?}{:?}", source, target));
+        edges
+    }
+
+    fn file(path: &str) -> GraphNode {
+        GraphNode::File(PathBuf::from(path))
+    }
+
+    // Writes `files` into a temporary folder, scans it and builds the links graph
+    fn scan_files(files: &[(&str, &str)]) -> (tempfile::TempDir, FileScanner, FileGraph) {
+        let dir = tempfile::tempdir().unwrap();
+        for (name, content) in files {
+            let path = dir.path().join(name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+        let mut scanner = FileScanner::new(dir.path());
+        scanner
+            .scan_directory_with_progress(dir.path(), std::sync::mpsc::channel().0)
+            .unwrap();
+        let mut graph = FileGraph::new();
+        graph.build_from_scanner(&scanner, usize::MAX, &mut |_| {});
+        (dir, scanner, graph)
+    }
+
+    fn tag_file(scanner: &mut FileScanner, path: &str, tags: &[&str]) {
+        scanner.files.insert(PathBuf::from(path), Vec::new());
+        scanner.tags.insert(
+            PathBuf::from(path),
+            tags.iter().map(|tag| tag.to_string()).collect(),
+        );
+    }
+
+    #[test]
+    fn min_degree_filter_keeps_nodes_with_enough_links() {
+        let [a, b, c, d, lone] = [0, 1, 2, 3, 4].map(NodeIndex::new);
+        // a - b - c, b - d: b has degree 3, the rest 1, lone 0
+        let nodes = vec![a, b, c, d, lone];
+        let edges = vec![(a, b), (b, c), (d, b)];
+
+        let (kept, kept_edges) = filter_by_min_degree(&nodes, &edges, 1);
+        assert_eq!(kept, vec![a, b, c, d]);
+        assert_eq!(kept_edges, edges);
+
+        // Only b is left, so no edge has both ends kept
+        let (kept, kept_edges) = filter_by_min_degree(&nodes, &edges, 2);
+        assert_eq!(kept, vec![b]);
+        assert!(kept_edges.is_empty());
+    }
+
+    fn all_indices(graph: &TagGraph) -> HashMap<GraphNode, NodeIndex> {
+        graph
+            .graph
+            .node_indices()
+            .map(|node_idx| (graph.graph[node_idx].clone(), node_idx))
+            .collect()
+    }
+
+    #[test]
+    fn focus_keeps_the_hovered_node_its_neighbours_and_their_edges() {
+        let [a, b, c, d, far] = [0, 1, 2, 3, 4].map(NodeIndex::new);
+        // a -> b, c -> a, b -> d, d -> far
+        let adjacency = AdjacencyIndex::from_edges(&[(a, b), (c, a), (b, d), (d, far)]);
+
+        let (nodes, edges) = neighborhood(a, &adjacency);
+        assert_eq!(nodes, HashSet::from([a, b, c]));
+        assert_eq!(edges, HashSet::from([(a, b), (c, a)]));
+
+        let (nodes, edges) = neighborhood(far, &adjacency);
+        assert_eq!(nodes, HashSet::from([far, d]));
+        assert_eq!(edges, HashSet::from([(d, far)]));
+    }
+
+    #[test]
+    fn isolating_keeps_the_neighbourhood_and_clearing_restores_all() {
+        let [a, b, c, d, e] = [0, 1, 2, 3, 4].map(NodeIndex::new);
+        let nodes = vec![a, b, c, d, e];
+        // a - b - c - d, and e on its own
+        let edges = vec![(a, b), (c, b), (c, d)];
+
+        let (kept, kept_edges) = isolate(nodes.clone(), edges.clone(), Some(b), 1);
+        assert_eq!(kept, [a, b, c]);
+        assert_eq!(kept_edges, [(a, b), (c, b)]);
+        let (kept, _) = isolate(nodes.clone(), edges.clone(), Some(b), 2);
+        assert_eq!(kept, [a, b, c, d]);
+
+        assert_eq!(
+            isolate(nodes.clone(), edges.clone(), None, 1),
+            (nodes.clone(), edges.clone())
+        );
+        // A filtered-out node can't be isolated, so nothing is hidden
+        let (kept, _) = isolate(nodes[..4].to_vec(), edges.clone(), Some(e), 1);
+        assert_eq!(kept, [a, b, c, d]);
+    }
+
+    #[test]
+    fn tags_on_the_same_two_files_get_a_cooccurrence_edge_of_weight_two() {
+        let mut scanner = scanner_with(&[]);
+        tag_file(&mut scanner, "/notes/a.md", &["rust", "egui"]);
+        tag_file(&mut scanner, "/notes/b.md", &["egui", "rust", "rust"]);
+        tag_file(&mut scanner, "/notes/c.md", &["rust", "books"]);
+
+        let mut tag_graph = TagGraph::new();
+        tag_graph.build_from_tags(&scanner, false, usize::MAX, &mut |_| {});
+        let tag = |name: &str| tag_graph.tag_node_indices[name];
+
+        assert_eq!(tag_graph.cooccurrence.len(), 2);
+        assert_eq!(tag_graph.cooccurrence[&(tag("egui"), tag("rust"))], 2);
+        assert_eq!(tag_graph.cooccurrence[&(tag("books"), tag("rust"))], 1);
+    }
+
+    #[test]
+    fn untagged_option_links_files_without_tags_to_the_untagged_group() {
+        let mut scanner = scanner_with(&[("/notes/plain.md", &[]), ("/notes/empty.md", &[])]);
+        tag_file(&mut scanner, "/notes/tagged.md", &["rust"]);
+        scanner
+            .tags
+            .insert(PathBuf::from("/notes/empty.md"), Vec::new());
+        scanner.images.push(PathBuf::from("/notes/pic.png"));
+        scanner
+            .files
+            .insert(PathBuf::from("/notes/pic.png"), Vec::new());
+
+        let mut tag_graph = TagGraph::new();
+        tag_graph.build_from_tags(&scanner, false, usize::MAX, &mut |_| {});
+        assert!(!tag_graph.tag_node_indices.contains_key(UNTAGGED_TAG));
+        assert!(
+            !tag_graph
+                .file_node_indices
+                .contains_key(Path::new("/notes/plain.md"))
+        );
+
+        tag_graph.build_from_tags(&scanner, true, usize::MAX, &mut |_| {});
+        let untagged = tag_graph.tag_node_indices[UNTAGGED_TAG];
+        let mut grouped: Vec<_> = tag_graph
+            .graph
+            .neighbors(untagged)
+            .map(|idx| tag_graph.graph[idx].clone())
+            .collect();
+        grouped.sort_by_key(|node| format!("{node:?}"));
+        assert_eq!(grouped, [file("/notes/empty.md"), file("/notes/plain.md")]);
+    }
+
+    #[test]
+    fn hovered_node_highlights_only_its_visible_incident_edges() {
+        let [hovered, a, b, c] = [0, 1, 2, 3].map(NodeIndex::new);
+        // Links both ways with a, one out to b; b -> c doesn't touch the hovered node
+        let visible_edges = [(hovered, a), (a, hovered), (hovered, b), (b, c)];
+        let adjacency = AdjacencyIndex::from_edges(&visible_edges);
+
+        let (_, incident) = neighborhood(hovered, &adjacency);
+        let highlighted: Vec<_> = visible_edges
+            .iter()
+            .filter(|edge| incident.contains(edge))
+            .copied()
+            .collect();
+        assert_eq!(highlighted, [(hovered, a), (a, hovered), (hovered, b)]);
+    }
+
+    #[test]
+    fn reference_style_links_resolve_through_their_definitions() {
+        let (dir, scanner, graph) = scan_files(&[
+            (
+                "x.md",
+                "See [x][1] and [gone][missing].\n\n[1]: target.md \"Title\"\n",
+            ),
+            ("target.md", "no links"),
+        ]);
+        let path = |name: &str| GraphNode::File(dir.path().join(name));
+
+        assert_eq!(edge_paths(&graph), [(path("x.md"), path("target.md"))]);
+        assert_eq!(
+            scanner.broken_links[&dir.path().join("x.md")],
+            ["missing".to_string()]
+        );
+    }
+
+    #[test]
+    fn tooltip_metadata_comes_from_the_scan_cache() {
+        // Neither file exists on disk, so anything found came from the cache
+        let mut scanner = scanner_with(&[("/notes/a.md", &[]), ("/notes/b.md", &[])]);
+        let modified = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000);
+        scanner
+            .file_metadata
+            .insert(PathBuf::from("/notes/a.md"), (1234, modified));
+        let mut graph = FileGraph::new();
+        graph.build_from_scanner(&scanner, usize::MAX, &mut |_| {});
+        let a = graph.node_indices[Path::new("/notes/a.md")];
+        let b = graph.node_indices[Path::new("/notes/b.md")];
+
+        let metadata = node_metadata(&graph.graph, &scanner, &[a, b]);
+        assert_eq!(metadata, HashMap::from([(a, (1234, modified))]));
+    }
+
+    #[test]
+    fn orphans_are_exactly_the_nodes_without_edges() {
+        let nodes: Vec<NodeIndex> = (0..6).map(NodeIndex::new).collect();
+        // 4 only links to itself, which still counts as linked
+        let edges = [
+            (nodes[0], nodes[1]),
+            (nodes[2], nodes[1]),
+            (nodes[4], nodes[4]),
+        ];
+
+        let degree_zero: Vec<NodeIndex> = nodes
+            .iter()
+            .copied()
+            .filter(|node| !edges.iter().any(|&(s, t)| s == *node || t == *node))
+            .collect();
+        assert_eq!(orphan_nodes(&nodes, &edges), degree_zero);
+        assert_eq!(degree_zero, [nodes[3], nodes[5]]);
+        assert_eq!(orphan_nodes(&nodes, &[]), nodes);
+    }
+
+    #[test]
+    fn adjacency_index_agrees_with_petgraph_neighbours() {
+        let mut graph = StableGraph::new();
+        let nodes: Vec<NodeIndex> = (0..6)
+            .map(|i| graph.add_node(file(&format!("/notes/{}.md", i))))
+            .collect();
+        for (source, target) in [(0, 1), (1, 0), (1, 2), (3, 1), (4, 4), (2, 5), (5, 3)] {
+            graph.add_edge(nodes[source], nodes[target], ());
+        }
+        // Removed nodes and their edges don't linger in the index
+        graph.remove_node(nodes[2]);
+
+        let adjacency = AdjacencyIndex::from_graph(&graph);
+        let sorted = |iter: &mut dyn Iterator<Item = NodeIndex>| {
+            let mut nodes: Vec<_> = iter.collect();
+            nodes.sort();
+            nodes
+        };
+        for node in graph.node_indices() {
+            assert_eq!(
+                sorted(&mut adjacency.outgoing(node).iter().copied()),
+                sorted(&mut graph.neighbors_directed(node, Direction::Outgoing)),
+            );
+            assert_eq!(
+                sorted(&mut adjacency.incoming(node).iter().copied()),
+                sorted(&mut graph.neighbors_directed(node, Direction::Incoming)),
+            );
+        }
+        assert!(adjacency.outgoing(nodes[2]).is_empty());
+    }
+
+    #[test]
+    fn unified_graph_has_link_and_tag_edges() {
+        let mut scanner = scanner_with(&[("/notes/a.md", &["/notes/b.md"]), ("/notes/b.md", &[])]);
+        scanner
+            .tags
+            .insert(PathBuf::from("/notes/a.md"), vec!["rust".to_string()]);
+        scanner
+            .tags
+            .insert(PathBuf::from("/notes/b.md"), vec!["rust".to_string()]);
+
+        let mut unified = FileGraph::new();
+        unified.update_unified(&scanner, usize::MAX);
+        let rust = GraphNode::Tag("rust".to_string());
+        assert_eq!(
+            edge_paths(&unified),
+            [
+                (file("/notes/a.md"), file("/notes/b.md")),
+                (file("/notes/a.md"), rust.clone()),
+                (file("/notes/b.md"), rust),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8_file_names_resolve_to_the_exact_path() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let (dir, _, _) = scan_files(&[("a.md", "")]);
+        // Latin-1 "café.md", not valid UTF-8
+        let odd = dir.path().join(OsStr::from_bytes(b"caf\xe9.md"));
+        std::fs::write(&odd, "[[a]]").unwrap();
+        let mut scanner = FileScanner::new(dir.path());
+        scanner
+            .scan_directory_with_progress(dir.path(), std::sync::mpsc::channel().0)
+            .unwrap();
+        let mut graph = FileGraph::new();
+        graph.build_from_scanner(&scanner, usize::MAX, &mut |_| {});
+
+        let node_idx = graph.node_indices[&odd];
+        assert_eq!(graph.graph[node_idx], GraphNode::File(odd.clone()));
+        assert_eq!(
+            edge_paths(&graph),
+            [(
+                GraphNode::File(odd),
+                GraphNode::File(dir.path().join("a.md"))
+            )]
+        );
+    }
+
+    #[test]
+    fn shared_tag_highlight_finds_files_with_any_common_tag() {
+        let tags: HashMap<PathBuf, Vec<String>> = [
+            ("/notes/a.md", vec!["rust", "gui"]),
+            ("/notes/b.md", vec!["rust"]),
+            ("/notes/c.md", vec!["gui", "design"]),
+            ("/notes/d.md", vec!["books"]),
+            ("/notes/e.md", vec![]),
+        ]
+        .into_iter()
+        .map(|(path, tags)| {
+            (
+                PathBuf::from(path),
+                tags.into_iter().map(String::from).collect(),
+            )
+        })
+        .collect();
+        let index = tag_file_index(&tags);
+
+        let shared = files_sharing_tags(Path::new("/notes/a.md"), &tags, &index);
+        assert_eq!(
+            shared,
+            HashSet::from([Path::new("/notes/b.md"), Path::new("/notes/c.md")])
+        );
+        assert!(files_sharing_tags(Path::new("/notes/d.md"), &tags, &index).is_empty());
+        assert!(files_sharing_tags(Path::new("/notes/e.md"), &tags, &index).is_empty());
+        assert!(files_sharing_tags(Path::new("/notes/new.md"), &tags, &index).is_empty());
+    }
+
+    #[test]
+    fn badges_count_tags_of_tagged_files_only() {
+        let mut scanner = scanner_with(&[("/notes/plain.md", &[])]);
+        tag_file(&mut scanner, "/notes/tagged.md", &["a", "b", "c"]);
+        let mut graph = FileGraph::new();
+        graph.update_from_scanner(&scanner, 100);
+
+        let counts = tag_badge_counts(&graph, &scanner);
+        assert_eq!(
+            counts,
+            HashMap::from([(graph.node_indices[Path::new("/notes/tagged.md")], 3)])
+        );
+    }
+
+    #[test]
+    fn files_linking_one_url_share_its_external_node() {
+        let mut scanner = scanner_with(&[("/notes/a.md", &[]), ("/notes/b.md", &[])]);
+        let url = "https://example.com/".to_string();
+        for path in ["/notes/a.md", "/notes/b.md"] {
+            scanner
+                .external_links
+                .insert(PathBuf::from(path), vec![url.clone()]);
+        }
+
+        let mut graph = FileGraph::new();
+        graph.update_from_scanner(&scanner, 100);
+
+        let externals: Vec<_> = graph
+            .graph
+            .node_indices()
+            .filter(|&idx| matches!(graph.graph[idx], GraphNode::External(_)))
+            .collect();
+        assert_eq!(externals, vec![graph.external_node_indices[&url]]);
+        assert_eq!(
+            graph
+                .graph
+                .neighbors_directed(externals[0], petgraph::Direction::Incoming)
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn partial_scan_builds_valid_graph_that_grows_in_place() {
+        let partial = scanner_with(&[
+            ("/notes/a.md", &["/notes/b.md"]),
+            ("/notes/b.md", &["/notes/c.md"]),
+        ]);
+        let mut streamed = FileScanner::new("/notes");
+        streamed.add_partial(&partial);
+
+        let mut graph = FileGraph::new();
+        graph.update_from_scanner(&streamed, 100);
+        assert_eq!(graph.graph.node_count(), 2);
+        // The link to the file not scanned yet waits for it instead of dangling
+        assert_eq!(
+            edge_paths(&graph),
+            [(file("/notes/a.md"), file("/notes/b.md"))]
+        );
+        let a_idx = graph.node_indices[Path::new("/notes/a.md")];
+        let b_idx = graph.node_indices[Path::new("/notes/b.md")];
+
+        let complete = scanner_with(&[
+            ("/notes/a.md", &["/notes/b.md"]),
+            ("/notes/b.md", &["/notes/c.md"]),
+            ("/notes/c.md", &[]),
+        ]);
+        graph.update_from_scanner(&complete, 100);
+        assert_eq!(graph.graph.node_count(), 3);
+        assert_eq!(graph.node_indices[Path::new("/notes/a.md")], a_idx);
+        assert_eq!(graph.node_indices[Path::new("/notes/b.md")], b_idx);
+        assert_eq!(edge_paths(&graph).len(), 2);
+    }
+
+    #[test]
+    fn adding_tagged_file_adds_only_its_nodes_and_edges() {
+        let mut scanner = FileScanner::new("/notes");
+        tag_file(&mut scanner, "/notes/a.md", &["x"]);
+        tag_file(&mut scanner, "/notes/b.md", &["y"]);
+        let mut graph = TagGraph::new();
+        graph.update_from_tags(&scanner, false, 100);
+        let before = all_indices(&graph);
+        let edges_before = graph.graph.edge_count();
+
+        tag_file(&mut scanner, "/notes/c.md", &["x", "z"]);
+        graph.update_from_tags(&scanner, false, 100);
+
+        let after = all_indices(&graph);
+        for (node, node_idx) in &before {
+            assert_eq!(after.get(node), Some(node_idx));
+        }
+        let mut added: Vec<&GraphNode> = after
+            .keys()
+            .filter(|node| !before.contains_key(*node))
+            .collect();
+        added.sort_by_key(|node| format!("{:?}", node));
+        assert_eq!(
+            added,
+            [&file("/notes/c.md"), &GraphNode::Tag("z".to_string())]
+        );
+        assert_eq!(graph.graph.edge_count(), edges_before + 2);
+    }
+
+    #[test]
+    fn tag_graph_stops_at_cap_and_counts_the_rest() {
+        let mut scanner = FileScanner::new("/notes");
+        for path in ["/notes/a.md", "/notes/b.md", "/notes/c.md"] {
+            tag_file(&mut scanner, path, &["x"]);
+        }
+        // Listed twice, as happens when streamed results overlap
+        for path in ["/notes/p.png", "/notes/q.png", "/notes/p.png"] {
+            scanner.images.push(PathBuf::from(path));
+        }
+
+        let mut graph = TagGraph::new();
+        graph.update_from_tags(&scanner, false, 4);
+        assert_eq!(graph.file_node_indices.len(), 3);
+        assert_eq!(graph.image_node_indices.len(), 1);
+        assert_eq!(graph.omitted_nodes, 1);
+
+        graph.update_from_tags(&scanner, false, 2);
+        assert_eq!(graph.file_node_indices.len(), 2);
+        assert!(graph.image_node_indices.is_empty());
+        assert_eq!(graph.omitted_nodes, 3);
+        // The tag plus the two kept files
+        assert_eq!(graph.graph.node_count(), 3);
+    }
+
+    #[test]
+    fn unified_graph_cap_counts_folder_nodes() {
+        let mut scanner = scanner_with(&[("/notes/sub/b.md", &[]), ("/notes/sub/c.md", &[])]);
+        tag_file(&mut scanner, "/notes/a.md", &["x", "y"]);
+        let mut graph = FileGraph::new();
+        graph.directory_grouping = Some(HashSet::from([PathBuf::from("/notes")]));
+
+        // a.md and the `sub` folder leave room for one of the two tags
+        graph.update_unified(&scanner, 3);
+        assert_eq!(graph.directory_node_indices.len(), 1);
+        assert_eq!(graph.tag_node_indices.len(), 1);
+        assert_eq!(graph.graph.node_count(), 3);
+        assert_eq!(graph.omitted_nodes, 1);
+    }
+
+    #[test]
+    fn html_hrefs_become_edges_to_local_files_and_urls() {
+        let html = concat!(
+            "<p><a href=\"page.html#intro\">Intro</a>\n",
+            "<a class='note' HREF='../notes/a.md'>Note</a>\n",
+            "<img alt=\"\" src=\"pic.png\">\n",
+            "<a href=\"https://example.com\">Site</a>\n",
+            "<a href=\"#top\">Top</a> <a href=\"mailto:me@example.com\">Mail</a>\n",
+            "<a href=\"missing.html\">Gone</a></p>\n",
+        );
+        let (dir, _, graph) = scan_files(&[
+            ("docs/index.html", html),
+            ("docs/page.html", "<p>No links</p>"),
+            ("docs/pic.png", ""),
+            ("notes/a.md", ""),
+        ]);
+        let path = |name: &str| GraphNode::File(dir.path().join(name));
+        let index = path("docs/index.html");
+
+        let mut expected = vec![
+            (index.clone(), path("docs/page.html")),
+            (index.clone(), path("notes/a.md")),
+            (index.clone(), path("docs/pic.png")),
+            (
+                index.clone(),
+                GraphNode::External("https://example.com".to_string()),
+            ),
+        ];
+        expected.sort_by_key(|(source, target)| format!("{:?}{:?}", source, target));
+        assert_eq!(edge_paths(&graph), expected);
+    }
+
+    #[test]
+    fn cycles_are_found_and_acyclic_graphs_report_none() {
+        let scanner = scanner_with(&[
+            ("/notes/a.md", &["/notes/b.md"]),
+            ("/notes/b.md", &["/notes/c.md"]),
+            ("/notes/c.md", &["/notes/a.md", "/notes/d.md"]),
+            ("/notes/d.md", &[]),
+        ]);
+        let mut graph = FileGraph::new();
+        graph.build_from_scanner(&scanner, usize::MAX, &mut |_| {});
+        let node = |path: &str| graph.node_indices[Path::new(path)];
+
+        let cycles = link_cycles(&graph.graph);
+        assert_eq!(cycles.len(), 1);
+        let mut cycle = cycles[0].clone();
+        cycle.sort();
+        let mut expected = vec![
+            node("/notes/a.md"),
+            node("/notes/b.md"),
+            node("/notes/c.md"),
+        ];
+        expected.sort();
+        assert_eq!(cycle, expected);
+
+        let acyclic = scanner_with(&[
+            ("/notes/a.md", &["/notes/b.md", "/notes/c.md"]),
+            ("/notes/b.md", &["/notes/c.md"]),
+            ("/notes/c.md", &[]),
+        ]);
+        let mut graph = FileGraph::new();
+        graph.build_from_scanner(&acyclic, usize::MAX, &mut |_| {});
+        assert!(link_cycles(&graph.graph).is_empty());
+
+        // A note linking to itself is a cycle of one
+        let mut graph = StableGraph::new();
+        let selfish = graph.add_node(file("/notes/self.md"));
+        let other = graph.add_node(file("/notes/other.md"));
+        graph.add_edge(selfish, selfish, ());
+        graph.add_edge(selfish, other, ());
+        assert_eq!(link_cycles(&graph), [vec![selfish]]);
+    }
+
+    #[test]
+    fn wikilinks_resolve_by_basename_across_folders() {
+        let (dir, _, graph) = scan_files(&[
+            ("journal/today.md", "Plans in [[plan]], see [[index]]"),
+            ("journal/index.md", ""),
+            ("projects/plan.md", "Back to [[index]]"),
+            ("projects/index.md", ""),
+        ]);
+        let path = |name: &str| GraphNode::File(dir.path().join(name));
+
+        // `index` is ambiguous, so each note gets the one in its own folder
+        let mut expected = vec![
+            (path("journal/today.md"), path("projects/plan.md")),
+            (path("journal/today.md"), path("journal/index.md")),
+            (path("projects/plan.md"), path("projects/index.md")),
+        ];
+        expected.sort_by_key(|(source, target)| format!("{:?}{:?}", source, target));
+        assert_eq!(edge_paths(&graph), expected);
+    }
+
+    #[test]
+    fn repeated_tags_give_a_single_tag_edge() {
+        let (dir, scanner, _) = scan_files(&[(
+            "note.md",
+            "#todo first\n#todo second #TODO\n- [ ] #Todo #todo\n",
+        )]);
+        let note = dir.path().join("note.md");
+        assert_eq!(scanner.tags[&note], ["todo"]);
+
+        let mut tag_graph = TagGraph::new();
+        tag_graph.build_from_tags(&scanner, false, usize::MAX, &mut |_| {});
+        let file_idx = tag_graph.file_node_indices[&note];
+        let tag_idx = tag_graph.tag_node_indices["todo"];
+        assert_eq!(tag_graph.tag_node_indices.len(), 1);
+        assert_eq!(tag_graph.graph.edge_count(), 1);
+        assert!(tag_graph.graph.contains_edge(tag_idx, file_idx));
+    }
+}
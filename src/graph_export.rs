@@ -0,0 +1,345 @@
+// src/graph_export.rs
+use eframe::egui;
+use petgraph::graph::NodeIndex;
+use petgraph::stable_graph::StableGraph;
+use petgraph::visit::{EdgeRef, IntoEdgeReferences};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::path::PathBuf;
+
+use crate::graph::{AdjacencyIndex, FileGraph, GraphNode, tag_cooccurrence};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportedNode {
+    pub id: usize,
+    pub node: GraphNode,
+    // Layout coordinates, present when positions were exported
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub x: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub y: Option<f32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportedEdge {
+    pub source: usize,
+    pub target: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportedGraph {
+    pub nodes: Vec<ExportedNode>,
+    pub edges: Vec<ExportedEdge>,
+}
+
+impl ExportedGraph {
+    // Node ids are the graph's node indices; positions are taken from the layout when given
+    pub fn from_graph(
+        graph: &StableGraph<GraphNode, ()>,
+        positions: Option<&HashMap<NodeIndex, egui::Vec2>>,
+    ) -> Self {
+        let nodes = graph
+            .node_indices()
+            .map(|node_idx| {
+                let position = positions.and_then(|positions| positions.get(&node_idx));
+                ExportedNode {
+                    id: node_idx.index(),
+                    node: graph[node_idx].clone(),
+                    x: position.map(|pos| pos.x),
+                    y: position.map(|pos| pos.y),
+                }
+            })
+            .collect();
+        let edges = graph
+            .edge_references()
+            .map(|edge| ExportedEdge {
+                source: edge.source().index(),
+                target: edge.target().index(),
+            })
+            .collect();
+        Self { nodes, edges }
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+
+    // Rebuild a graph from an export, without touching the filesystem, so files that
+    // no longer exist still show up. Returns the exported positions by new node index.
+    pub fn to_file_graph(&self) -> Result<(FileGraph, HashMap<NodeIndex, egui::Vec2>), String> {
+        let mut file_graph = FileGraph::new();
+        let mut by_id = HashMap::new();
+        let mut positions = HashMap::new();
+        for node in &self.nodes {
+            let node_idx = file_graph.graph.add_node(node.node.clone());
+            if by_id.insert(node.id, node_idx).is_some() {
+                return Err(format!("Duplicate node id {}", node.id));
+            }
+            match &node.node {
+                GraphNode::File(path) => {
+                    file_graph.node_indices.insert(path.clone(), node_idx);
+                }
+                GraphNode::Tag(tag) => {
+                    file_graph.tag_node_indices.insert(tag.clone(), node_idx);
+                }
+                GraphNode::External(url) => {
+                    file_graph
+                        .external_node_indices
+                        .insert(url.clone(), node_idx);
+                }
+                GraphNode::Directory(dir) => {
+                    file_graph
+                        .directory_node_indices
+                        .insert(dir.clone(), node_idx);
+                }
+            }
+            if let (Some(x), Some(y)) = (node.x, node.y) {
+                positions.insert(node_idx, egui::vec2(x, y));
+            }
+        }
+        for edge in &self.edges {
+            let (Some(&source), Some(&target)) = (by_id.get(&edge.source), by_id.get(&edge.target))
+            else {
+                return Err(format!(
+                    "Edge {} -> {} refers to a missing node",
+                    edge.source, edge.target
+                ));
+            };
+            file_graph.graph.add_edge(source, target, ());
+        }
+        file_graph.adjacency = AdjacencyIndex::from_graph(&file_graph.graph);
+        Ok((file_graph, positions))
+    }
+
+    pub fn to_graphml(&self) -> String {
+        let mut out = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n  \
+             <key id=\"kind\" for=\"node\" attr.name=\"kind\" attr.type=\"string\"/>\n  \
+             <key id=\"label\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>\n  \
+             <key id=\"x\" for=\"node\" attr.name=\"x\" attr.type=\"float\"/>\n  \
+             <key id=\"y\" for=\"node\" attr.name=\"y\" attr.type=\"float\"/>\n  \
+             <graph id=\"G\" edgedefault=\"directed\">\n",
+        );
+        for node in &self.nodes {
+            let (kind, label) = match &node.node {
+                GraphNode::File(path) => ("file", path.display().to_string()),
+                GraphNode::Tag(tag) => ("tag", tag.clone()),
+                GraphNode::External(url) => ("url", url.clone()),
+                GraphNode::Directory(dir) => ("directory", dir.display().to_string()),
+            };
+            out.push_str(&format!(
+                "    <node id=\"n{}\">\n      <data key=\"kind\">{}</data>\n      <data key=\"label\">{}</data>\n",
+                node.id,
+                kind,
+                escape_xml(&label)
+            ));
+            if let (Some(x), Some(y)) = (node.x, node.y) {
+                out.push_str(&format!(
+                    "      <data key=\"x\">{}</data>\n      <data key=\"y\">{}</data>\n",
+                    x, y
+                ));
+            }
+            out.push_str("    </node>\n");
+        }
+        for edge in &self.edges {
+            out.push_str(&format!(
+                "    <edge source=\"n{}\" target=\"n{}\"/>\n",
+                edge.source, edge.target
+            ));
+        }
+        out.push_str("  </graph>\n</graphml>\n");
+        out
+    }
+}
+
+// Tag-by-tag counts of files carrying both tags; the diagonal is each tag's file count
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CooccurrenceMatrix {
+    pub tags: Vec<String>,
+    // Row-major, in the order of `tags`
+    pub counts: Vec<Vec<usize>>,
+}
+
+impl CooccurrenceMatrix {
+    pub fn from_tags(tags: &HashMap<PathBuf, Vec<String>>) -> Self {
+        let names: Vec<String> = tags
+            .values()
+            .flatten()
+            .cloned()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        let position: HashMap<&str, usize> = names
+            .iter()
+            .enumerate()
+            .map(|(i, tag)| (tag.as_str(), i))
+            .collect();
+
+        let mut counts = vec![vec![0; names.len()]; names.len()];
+        for file_tags in tags.values() {
+            let unique: BTreeSet<&str> = file_tags.iter().map(String::as_str).collect();
+            for tag in unique {
+                let i = position[tag];
+                counts[i][i] += 1;
+            }
+        }
+        for ((tag_a, tag_b), count) in tag_cooccurrence(tags) {
+            let (a, b) = (position[tag_a.as_str()], position[tag_b.as_str()]);
+            counts[a][b] = count;
+            counts[b][a] = count;
+        }
+        Self {
+            tags: names,
+            counts,
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    // Header row of tags, then one row per tag starting with its name
+    pub fn to_csv(&self) -> String {
+        let mut out = String::new();
+        for tag in &self.tags {
+            out.push(',');
+            out.push_str(&escape_csv(tag));
+        }
+        out.push('\n');
+        for (tag, row) in self.tags.iter().zip(&self.counts) {
+            out.push_str(&escape_csv(tag));
+            for count in row {
+                out.push_str(&format!(",{}", count));
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+fn escape_csv(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn sample_graph() -> (StableGraph<GraphNode, ()>, [NodeIndex; 3]) {
+        let mut graph = StableGraph::new();
+        let a = graph.add_node(GraphNode::File(PathBuf::from("/notes/a & b.md")));
+        let tag = graph.add_node(GraphNode::Tag("rust".to_string()));
+        let url = graph.add_node(GraphNode::External("https://example.com".to_string()));
+        graph.add_edge(a, tag, ());
+        graph.add_edge(a, url, ());
+        (graph, [a, tag, url])
+    }
+
+    #[test]
+    fn json_export_imports_back_to_the_same_graph() {
+        let (graph, [a, tag, _]) = sample_graph();
+        let positions = HashMap::from([(a, egui::vec2(1.0, 2.0))]);
+        let exported = ExportedGraph::from_graph(&graph, Some(&positions));
+
+        let imported = ExportedGraph::from_json(&exported.to_json().unwrap()).unwrap();
+        assert_eq!(imported, exported);
+
+        let (file_graph, imported_positions) = imported.to_file_graph().unwrap();
+        let new_a = file_graph.node_indices()[Path::new("/notes/a & b.md")];
+        let new_tag = file_graph.tag_node_indices["rust"];
+        assert_eq!(graph[tag], file_graph.graph[new_tag]);
+        assert_eq!(file_graph.graph.edge_count(), 2);
+        assert!(file_graph.graph.contains_edge(new_a, new_tag));
+        assert_eq!(file_graph.adjacency.outgoing(new_a).len(), 2);
+        assert_eq!(
+            imported_positions,
+            HashMap::from([(new_a, egui::vec2(1.0, 2.0))])
+        );
+
+        let mut broken = exported.clone();
+        broken.edges.push(ExportedEdge {
+            source: 0,
+            target: 99,
+        });
+        assert!(broken.to_file_graph().is_err());
+    }
+
+    #[test]
+    fn export_carries_the_simulator_positions() {
+        let (graph, [a, tag, url]) = sample_graph();
+        let positions = HashMap::from([(a, egui::vec2(12.5, -40.0)), (tag, egui::vec2(0.0, 3.0))]);
+
+        let exported = ExportedGraph::from_graph(&graph, Some(&positions));
+        let coordinates: Vec<_> = exported.nodes.iter().map(|node| (node.x, node.y)).collect();
+        assert_eq!(
+            coordinates,
+            [
+                (Some(12.5), Some(-40.0)),
+                (Some(0.0), Some(3.0)),
+                (None, None)
+            ]
+        );
+        assert_eq!(exported.nodes[2].id, url.index());
+
+        let graphml = exported.to_graphml();
+        assert!(graphml.contains(
+            "<data key=\"label\">/notes/a &amp; b.md</data>\n      \
+             <data key=\"x\">12.5</data>\n      <data key=\"y\">-40</data>"
+        ));
+        assert!(graphml.contains("<edge source=\"n0\" target=\"n1\"/>"));
+
+        // Without a layout no coordinates are written
+        let unpositioned = ExportedGraph::from_graph(&graph, None);
+        assert!(unpositioned.nodes.iter().all(|node| node.x.is_none()));
+        assert!(!unpositioned.to_json().unwrap().contains("\"x\""));
+    }
+
+    #[test]
+    fn cooccurrence_matrix_is_symmetric_with_file_counts_on_the_diagonal() {
+        let tags = HashMap::from([
+            (
+                PathBuf::from("/notes/a.md"),
+                vec!["rust".to_string(), "cli".to_string(), "rust".to_string()],
+            ),
+            (
+                PathBuf::from("/notes/b.md"),
+                vec!["rust".to_string(), "gui".to_string()],
+            ),
+            (
+                PathBuf::from("/notes/c.md"),
+                vec!["cli".to_string(), "rust".to_string()],
+            ),
+            (PathBuf::from("/notes/d.md"), vec!["gui".to_string()]),
+        ]);
+        let matrix = CooccurrenceMatrix::from_tags(&tags);
+
+        assert_eq!(matrix.tags, ["cli", "gui", "rust"]);
+        assert_eq!(matrix.counts, [vec![2, 0, 2], vec![0, 2, 1], vec![2, 1, 3]]);
+        for (i, row) in matrix.counts.iter().enumerate() {
+            for (j, &count) in row.iter().enumerate() {
+                assert_eq!(count, matrix.counts[j][i]);
+            }
+        }
+        assert_eq!(
+            matrix.to_csv(),
+            ",cli,gui,rust\ncli,2,0,2\ngui,0,2,1\nrust,2,1,3\n"
+        );
+    }
+}
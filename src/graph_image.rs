@@ -0,0 +1,359 @@
+// src/graph_image.rs
+use ab_glyph::{Font, FontRef, ScaleFont};
+use eframe::egui::{self, Color32, Pos2, Rect};
+use petgraph::graph::NodeIndex;
+use std::collections::HashSet;
+use tiny_skia::{
+    FillRule, Paint, PathBuilder, Pixmap, PremultipliedColorU8, Stroke, StrokeDash, Transform,
+};
+
+// Gap between a node and its label, and the label's side padding, in points
+const LABEL_GAP: f32 = 5.0;
+const LABEL_PADDING: f32 = 4.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PictureNode {
+    pub node: NodeIndex,
+    pub center: Pos2,
+    pub radius: f32,
+    pub color: Color32,
+    pub border: Color32,
+    // Orphans get a dashed border
+    pub dashed: bool,
+    // External URLs are drawn as squares
+    pub square: bool,
+    pub label: String,
+    pub label_size: f32,
+    // The label's background, in screen points
+    pub label_rect: Rect,
+    pub label_color: Color32,
+    pub label_background: Color32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PictureEdge {
+    pub source: NodeIndex,
+    pub target: NodeIndex,
+    pub from: Pos2,
+    pub to: Pos2,
+    // Set for curved and bundled edges
+    pub control: Option<Pos2>,
+    pub arrow: [Pos2; 2],
+    pub stroke: egui::Stroke,
+}
+
+// The graph view as plain shapes, in screen points, recorded while it is drawn. Drawn
+// again offscreen it leaves out the windows, tooltips and menus painted over the panel.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GraphPicture {
+    pub nodes: Vec<PictureNode>,
+    pub edges: Vec<PictureEdge>,
+    pub background: Color32,
+}
+
+impl GraphPicture {
+    // `center` and the nodes one link away from it, in either direction
+    pub fn neighborhood(&self, center: NodeIndex) -> Self {
+        let mut nodes: HashSet<NodeIndex> = self
+            .edges
+            .iter()
+            .filter_map(|edge| {
+                if edge.source == center {
+                    Some(edge.target)
+                } else if edge.target == center {
+                    Some(edge.source)
+                } else {
+                    None
+                }
+            })
+            .collect();
+        nodes.insert(center);
+        self.restricted_to(&nodes)
+    }
+
+    // Only `nodes`, and the edges with both ends among them
+    pub fn restricted_to(&self, nodes: &HashSet<NodeIndex>) -> Self {
+        Self {
+            nodes: self
+                .nodes
+                .iter()
+                .filter(|node| nodes.contains(&node.node))
+                .cloned()
+                .collect(),
+            edges: self
+                .edges
+                .iter()
+                .filter(|edge| nodes.contains(&edge.source) && nodes.contains(&edge.target))
+                .cloned()
+                .collect(),
+            background: self.background,
+        }
+    }
+
+    // Smallest rect holding every node and its label
+    pub fn bounds(&self) -> Rect {
+        self.nodes.iter().fold(Rect::NOTHING, |bounds, node| {
+            bounds.union(node.label_rect).union(Rect::from_center_size(
+                node.center,
+                egui::Vec2::splat(node.radius * 2.0),
+            ))
+        })
+    }
+
+    // Draws what lies inside `frame` at `pixels_per_point` pixels per screen point
+    pub fn render(&self, frame: Rect, pixels_per_point: f32, font: &FontRef) -> egui::ColorImage {
+        let width = ((frame.width() * pixels_per_point).round() as u32).max(1);
+        let height = ((frame.height() * pixels_per_point).round() as u32).max(1);
+        let mut pixmap = Pixmap::new(width, height).expect("image size is at least 1x1");
+        pixmap.fill(skia_color(self.background));
+        let transform = Transform::from_translate(-frame.min.x, -frame.min.y)
+            .post_scale(pixels_per_point, pixels_per_point);
+
+        for edge in &self.edges {
+            let mut path = PathBuilder::new();
+            path.move_to(edge.from.x, edge.from.y);
+            match edge.control {
+                Some(control) => path.quad_to(control.x, control.y, edge.to.x, edge.to.y),
+                None => path.line_to(edge.to.x, edge.to.y),
+            }
+            for tip in edge.arrow {
+                path.move_to(edge.to.x, edge.to.y);
+                path.line_to(tip.x, tip.y);
+            }
+            if let Some(path) = path.finish() {
+                let stroke = Stroke {
+                    width: edge.stroke.width,
+                    ..Stroke::default()
+                };
+                pixmap.stroke_path(&path, &paint(edge.stroke.color), &stroke, transform, None);
+            }
+        }
+
+        for node in &self.nodes {
+            let shape = if node.square {
+                tiny_skia::Rect::from_xywh(
+                    node.center.x - node.radius * 0.9,
+                    node.center.y - node.radius * 0.9,
+                    node.radius * 1.8,
+                    node.radius * 1.8,
+                )
+                .map(PathBuilder::from_rect)
+            } else {
+                PathBuilder::from_circle(node.center.x, node.center.y, node.radius)
+            };
+            let Some(shape) = shape else {
+                continue;
+            };
+            pixmap.fill_path(
+                &shape,
+                &paint(node.color),
+                FillRule::Winding,
+                transform,
+                None,
+            );
+            let border = Stroke {
+                width: 1.5,
+                dash: node
+                    .dashed
+                    .then(|| StrokeDash::new(vec![4.0, 3.0], 0.0))
+                    .flatten(),
+                ..Stroke::default()
+            };
+            pixmap.stroke_path(&shape, &paint(node.border), &border, transform, None);
+        }
+
+        // Labels go on top of every node, as in the view
+        for node in &self.nodes {
+            if node.label.is_empty() {
+                continue;
+            }
+            let scaled = font.as_scaled(node.label_size * pixels_per_point);
+            let text_width: f32 = node
+                .label
+                .chars()
+                .map(|c| scaled.h_advance(font.glyph_id(c)))
+                .sum();
+            let top_left = egui::pos2(node.center.x, node.center.y + node.radius + LABEL_GAP);
+            let padding = LABEL_PADDING * pixels_per_point;
+            let origin = (top_left - frame.min) * pixels_per_point;
+            if let Some(background) = tiny_skia::Rect::from_xywh(
+                origin.x - padding,
+                origin.y,
+                text_width + 2.0 * padding,
+                scaled.height(),
+            ) {
+                pixmap.fill_rect(
+                    background,
+                    &paint(node.label_background),
+                    Transform::identity(),
+                    None,
+                );
+            }
+            draw_text(
+                &mut pixmap,
+                font,
+                &node.label,
+                node.label_size * pixels_per_point,
+                egui::pos2(origin.x, origin.y),
+                node.label_color,
+            );
+        }
+
+        egui::ColorImage::from_rgba_premultiplied([width as usize, height as usize], pixmap.data())
+    }
+}
+
+fn skia_color(color: Color32) -> tiny_skia::Color {
+    let [r, g, b, a] = color.to_srgba_unmultiplied();
+    tiny_skia::Color::from_rgba8(r, g, b, a)
+}
+
+fn paint(color: Color32) -> Paint<'static> {
+    let mut paint = Paint::default();
+    paint.set_color(skia_color(color));
+    paint.anti_alias = true;
+    paint
+}
+
+// One line of text with its top-left corner at `origin`, in pixels
+fn draw_text(
+    pixmap: &mut Pixmap,
+    font: &FontRef,
+    text: &str,
+    size: f32,
+    origin: Pos2,
+    color: Color32,
+) {
+    let scaled = font.as_scaled(size);
+    let (width, height) = (pixmap.width() as i32, pixmap.height() as i32);
+    let [r, g, b, a] = color.to_srgba_unmultiplied();
+    let pixels = pixmap.pixels_mut();
+    let mut x = origin.x;
+    for c in text.chars() {
+        let glyph_id = font.glyph_id(c);
+        let glyph =
+            glyph_id.with_scale_and_position(size, ab_glyph::point(x, origin.y + scaled.ascent()));
+        x += scaled.h_advance(glyph_id);
+        let Some(outlined) = font.outline_glyph(glyph) else {
+            continue;
+        };
+        let bounds = outlined.px_bounds();
+        outlined.draw(|gx, gy, coverage| {
+            let px = bounds.min.x as i32 + gx as i32;
+            let py = bounds.min.y as i32 + gy as i32;
+            if px < 0 || py < 0 || px >= width || py >= height {
+                return;
+            }
+            // Source-over blend of premultiplied colours
+            let alpha = a as f32 / 255.0 * coverage.clamp(0.0, 1.0);
+            let pixel = &mut pixels[(py * width + px) as usize];
+            let blend =
+                |src: u8, dst: u8| (src as f32 * alpha + dst as f32 * (1.0 - alpha)).round() as u8;
+            let out_alpha = (alpha * 255.0 + pixel.alpha() as f32 * (1.0 - alpha)).round() as u8;
+            if let Some(blended) = PremultipliedColorU8::from_rgba(
+                blend(r, pixel.red()).min(out_alpha),
+                blend(g, pixel.green()).min(out_alpha),
+                blend(b, pixel.blue()).min(out_alpha),
+                out_alpha,
+            ) {
+                *pixel = blended;
+            }
+        });
+    }
+}
+
+// The proportional font egui draws the graph with
+pub fn default_font_bytes() -> Option<Vec<u8>> {
+    let definitions = egui::FontDefinitions::default();
+    let name = definitions
+        .families
+        .get(&egui::FontFamily::Proportional)?
+        .first()?;
+    Some(definitions.font_data.get(name)?.font.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn font() -> FontRef<'static> {
+        FontRef::try_from_slice(epaint_font()).unwrap()
+    }
+
+    fn epaint_font() -> &'static [u8] {
+        static FONT: std::sync::OnceLock<Vec<u8>> = std::sync::OnceLock::new();
+        FONT.get_or_init(|| default_font_bytes().expect("egui ships a default font"))
+    }
+
+    fn node(index: usize, x: f32) -> PictureNode {
+        let center = egui::pos2(x, 50.0);
+        PictureNode {
+            node: NodeIndex::new(index),
+            center,
+            radius: 8.0,
+            color: Color32::RED,
+            border: Color32::from_gray(100),
+            dashed: false,
+            square: false,
+            label: String::new(),
+            label_size: 14.0,
+            label_rect: Rect::from_min_size(center, egui::Vec2::ZERO),
+            label_color: Color32::WHITE,
+            label_background: Color32::from_black_alpha(120),
+        }
+    }
+
+    fn edge(source: usize, target: usize) -> PictureEdge {
+        PictureEdge {
+            source: NodeIndex::new(source),
+            target: NodeIndex::new(target),
+            from: egui::pos2(0.0, 0.0),
+            to: egui::pos2(0.0, 0.0),
+            control: None,
+            arrow: [egui::pos2(0.0, 0.0); 2],
+            stroke: egui::Stroke::new(1.0, Color32::WHITE),
+        }
+    }
+
+    #[test]
+    fn neighborhood_image_draws_only_the_node_and_its_links() {
+        // 0 -> 1 -> 2 -> 3, and 3 -> 1
+        let picture = GraphPicture {
+            nodes: (0..4).map(|i| node(i, 20.0 + 50.0 * i as f32)).collect(),
+            edges: vec![edge(0, 1), edge(1, 2), edge(2, 3), edge(3, 1)],
+            background: Color32::BLACK,
+        };
+
+        let around_two = picture.neighborhood(NodeIndex::new(2));
+        let nodes: Vec<_> = around_two.nodes.iter().map(|n| n.node.index()).collect();
+        let edges: Vec<_> = around_two
+            .edges
+            .iter()
+            .map(|e| (e.source.index(), e.target.index()))
+            .collect();
+        assert_eq!(nodes, vec![1, 2, 3]);
+        assert_eq!(edges, vec![(1, 2), (2, 3), (3, 1)]);
+
+        // Node 0 sits in the frame but was left out, so its spot stays background
+        let frame = Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(200.0, 100.0));
+        let image = around_two.render(frame, 1.0, &font());
+        assert_eq!(image[(20, 50)], Color32::BLACK);
+        assert_eq!(image[(70, 50)], Color32::RED);
+        assert_eq!(
+            around_two.bounds(),
+            Rect::from_min_max(egui::pos2(62.0, 42.0), egui::pos2(178.0, 58.0))
+        );
+    }
+
+    #[test]
+    fn render_matches_frame_times_scale() {
+        let picture = GraphPicture {
+            background: Color32::from_gray(27),
+            ..GraphPicture::default()
+        };
+        let frame = Rect::from_min_size(egui::pos2(30.0, 40.0), egui::vec2(200.0, 100.0));
+
+        assert_eq!(picture.render(frame, 1.0, &font()).size, [200, 100]);
+        assert_eq!(picture.render(frame, 2.5, &font()).size, [500, 250]);
+    }
+}
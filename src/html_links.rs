@@ -0,0 +1,56 @@
+// src/html_links.rs
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::path::{Path, PathBuf};
+
+use crate::utils::{is_external_link, normalize_lexically};
+
+// `<a href="...">` and `<img src="...">`, single or double quoted
+static HREF_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?i)<a\b[^>]*?\bhref\s*=\s*["']([^"']+)["']"#).unwrap());
+static IMG_SRC_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?i)<img\b[^>]*?\bsrc\s*=\s*["']([^"']+)["']"#).unwrap());
+
+#[derive(Debug, Default, PartialEq)]
+pub struct HtmlLinks {
+    // Local targets that exist on disk
+    pub files: Vec<PathBuf>,
+    pub external: Vec<String>,
+}
+
+// Links in an HTML file. Relative hrefs resolve against the file's folder and
+// root-relative ones (`/docs/a.html`) against `root`; query strings and fragments
+// are dropped. Local targets that don't exist add no edges.
+pub fn html_links(path: &Path, root: &Path, content: &str) -> HtmlLinks {
+    let Some(dir) = path.parent() else {
+        return HtmlLinks::default();
+    };
+    let mut links = HtmlLinks::default();
+    let targets = HREF_RE
+        .captures_iter(content)
+        .chain(IMG_SRC_RE.captures_iter(content))
+        .map(|cap| cap[1].trim().to_string());
+    for target in targets {
+        if is_external_link(&target) {
+            if !links.external.contains(&target) {
+                links.external.push(target);
+            }
+            continue;
+        }
+        // In-page anchors, mail and script links have no file behind them
+        let local = target.split(['#', '?']).next().unwrap_or("");
+        if local.is_empty() || local.contains(':') {
+            continue;
+        }
+        let joined = match local.strip_prefix('/') {
+            Some(from_root) => root.join(from_root),
+            None => dir.join(local),
+        };
+        let resolved =
+            normalize_lexically(Path::new(&joined.to_string_lossy().replace("%20", " ")));
+        if resolved.is_file() && resolved != path && !links.files.contains(&resolved) {
+            links.files.push(resolved);
+        }
+    }
+    links
+}
@@ -0,0 +1,197 @@
+// src/keybindings.rs
+use eframe::egui::{self, Key, KeyboardShortcut, Modifiers};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Action {
+    FocusSelected,
+    ToggleFullPaths,
+    RandomNode,
+    ToggleIsolate,
+    NextSearchResult,
+    PrevSearchResult,
+    PresentationMode,
+    Undo,
+    CommandPalette,
+}
+
+impl Action {
+    pub const ALL: [Action; 9] = [
+        Action::FocusSelected,
+        Action::ToggleFullPaths,
+        Action::RandomNode,
+        Action::ToggleIsolate,
+        Action::NextSearchResult,
+        Action::PrevSearchResult,
+        Action::PresentationMode,
+        Action::Undo,
+        Action::CommandPalette,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::FocusSelected => "Focus selected node",
+            Action::ToggleFullPaths => "Toggle full paths",
+            Action::RandomNode => "Open random note",
+            Action::ToggleIsolate => "Isolate / show all",
+            Action::NextSearchResult => "Next search result",
+            Action::PrevSearchResult => "Previous search result",
+            Action::PresentationMode => "Presentation mode",
+            Action::Undo => "Undo last file edit",
+            Action::CommandPalette => "Command palette",
+        }
+    }
+
+    // Function-key and Ctrl actions, which still fire while a text field has focus
+    pub fn works_while_typing(self) -> bool {
+        matches!(
+            self,
+            Action::NextSearchResult
+                | Action::PrevSearchResult
+                | Action::PresentationMode
+                | Action::CommandPalette
+        )
+    }
+
+    fn default_shortcut(self) -> KeyboardShortcut {
+        match self {
+            Action::FocusSelected => KeyboardShortcut::new(Modifiers::NONE, Key::F),
+            Action::ToggleFullPaths => KeyboardShortcut::new(Modifiers::NONE, Key::P),
+            Action::RandomNode => KeyboardShortcut::new(Modifiers::NONE, Key::R),
+            Action::ToggleIsolate => KeyboardShortcut::new(Modifiers::NONE, Key::I),
+            Action::NextSearchResult => KeyboardShortcut::new(Modifiers::NONE, Key::F3),
+            Action::PrevSearchResult => KeyboardShortcut::new(Modifiers::SHIFT, Key::F3),
+            Action::PresentationMode => KeyboardShortcut::new(Modifiers::NONE, Key::F11),
+            Action::Undo => KeyboardShortcut::new(Modifiers::COMMAND, Key::Z),
+            Action::CommandPalette => KeyboardShortcut::new(Modifiers::COMMAND, Key::P),
+        }
+    }
+}
+
+// Action -> shortcut. Actions missing from a saved config fall back to their default.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyBindings {
+    bindings: BTreeMap<Action, KeyboardShortcut>,
+}
+
+impl KeyBindings {
+    pub fn shortcut(&self, action: Action) -> KeyboardShortcut {
+        self.bindings
+            .get(&action)
+            .copied()
+            .unwrap_or_else(|| action.default_shortcut())
+    }
+
+    pub fn rebind(&mut self, action: Action, shortcut: KeyboardShortcut) {
+        if shortcut == action.default_shortcut() {
+            self.bindings.remove(&action);
+        } else {
+            self.bindings.insert(action, shortcut);
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.bindings.clear();
+    }
+
+    // Modifiers must match exactly, so Shift+F3 doesn't also trigger F3
+    pub fn pressed(&self, input: &egui::InputState, action: Action) -> bool {
+        let shortcut = self.shortcut(action);
+        input.key_pressed(shortcut.logical_key) && input.modifiers.matches_exact(shortcut.modifiers)
+    }
+
+    // Other actions already using `shortcut`
+    pub fn conflicts(&self, action: Action, shortcut: KeyboardShortcut) -> Vec<Action> {
+        Action::ALL
+            .into_iter()
+            .filter(|&other| other != action && self.shortcut(other) == shortcut)
+            .collect()
+    }
+}
+
+// First key pressed this frame, with its modifiers, for the rebinding UI
+pub fn captured_shortcut(input: &egui::InputState) -> Option<KeyboardShortcut> {
+    input.events.iter().find_map(|event| match event {
+        egui::Event::Key {
+            key,
+            pressed: true,
+            repeat: false,
+            modifiers,
+            ..
+        } => Some(KeyboardShortcut::new(*modifiers, *key)),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Whether `action` fires on a frame where `modifiers` + `key` is pressed
+    fn fires(bindings: &KeyBindings, action: Action, modifiers: Modifiers, key: Key) -> bool {
+        let input = egui::RawInput {
+            modifiers,
+            events: vec![egui::Event::Key {
+                key,
+                physical_key: None,
+                pressed: true,
+                repeat: false,
+                modifiers,
+            }],
+            ..Default::default()
+        };
+        let ctx = egui::Context::default();
+        let mut fired = false;
+        let _ = ctx.run(input, |ctx| {
+            fired = ctx.input(|i| bindings.pressed(i, action));
+        });
+        fired
+    }
+
+    #[test]
+    fn rebound_key_triggers_and_the_old_one_does_not() {
+        let mut bindings = KeyBindings::default();
+        assert!(fires(
+            &bindings,
+            Action::RandomNode,
+            Modifiers::NONE,
+            Key::R
+        ));
+
+        let ctrl_j = KeyboardShortcut::new(Modifiers::CTRL, Key::J);
+        bindings.rebind(Action::RandomNode, ctrl_j);
+        assert!(fires(
+            &bindings,
+            Action::RandomNode,
+            Modifiers::CTRL,
+            Key::J
+        ));
+        assert!(!fires(
+            &bindings,
+            Action::RandomNode,
+            Modifiers::NONE,
+            Key::R
+        ));
+        // Modifiers have to match exactly
+        assert!(!fires(
+            &bindings,
+            Action::RandomNode,
+            Modifiers::NONE,
+            Key::J
+        ));
+
+        assert_eq!(
+            bindings.conflicts(Action::FocusSelected, ctrl_j),
+            [Action::RandomNode]
+        );
+        bindings.reset();
+        assert!(fires(
+            &bindings,
+            Action::RandomNode,
+            Modifiers::NONE,
+            Key::R
+        ));
+    }
+}
@@ -2,15 +2,47 @@
 use eframe::{NativeOptions, egui};
 use std::path::PathBuf;
 
+mod archive;
+mod code_imports;
+mod command_palette;
+mod file_edits;
 mod file_scan;
 mod graph;
+mod graph_export;
+mod graph_image;
+mod html_links;
+mod keybindings;
+mod pdf_backend;
 mod physics_nodes;
+mod scan_stats;
+mod settings;
 mod ui;
 mod utils;
+mod view_state;
+mod watch;
 
 fn main() -> Result<(), eframe::Error> {
-    let args: Vec<String> = std::env::args().collect();
-    let scan_dir = if args.len() > 1 {
+    let mut args: Vec<String> = std::env::args().collect();
+
+    // `--view <link>` reopens a shared view; it also supplies the directory to scan
+    let mut view_state = None;
+    if let Some(flag_pos) = args.iter().position(|arg| arg == view_state::VIEW_FLAG) {
+        let encoded: Vec<String> = args
+            .drain(flag_pos..(flag_pos + 2).min(args.len()))
+            .collect();
+        match encoded
+            .get(1)
+            .map(|link| view_state::ViewState::decode(link))
+        {
+            Some(Ok(view)) => view_state = Some(view),
+            Some(Err(e)) => eprintln!("{}", e),
+            None => eprintln!("{} expects an encoded view link", view_state::VIEW_FLAG),
+        }
+    }
+
+    let scan_dir = if let Some(view) = &view_state {
+        view.scan_dir.clone()
+    } else if args.len() > 1 {
         PathBuf::from(&args[1])
     } else {
         eprintln!("Usage: {} <path_to_directory_to_scan>", args[0]);
@@ -29,6 +61,14 @@ fn main() -> Result<(), eframe::Error> {
     eframe::run_native(
         app_name,
         options,
-        Box::new(|_cc| Ok(Box::new(ui::FileGraphApp::new(scan_dir)))),
+        Box::new(|cc| {
+            let settings = settings::AppSettings::load(cc.storage);
+            let mut app = ui::FileGraphApp::new(scan_dir, settings);
+            app.restore_scan_snapshots(scan_stats::ScanSnapshots::load(cc.storage));
+            if let Some(view) = view_state {
+                app.apply_view_state(view);
+            }
+            Ok(Box::new(app))
+        }),
     )
 }
@@ -0,0 +1,139 @@
+// src/pdf_backend.rs
+use pdfium_render::prelude::Pdfium;
+use std::path::{Path, PathBuf};
+
+pub const PDFIUM_MISSING_MESSAGE: &str = "PDFium not found; install libpdfium";
+
+// Library file or directory containing it, checked before anything else
+pub const PDFIUM_PATH_ENV: &str = "NEXUSVIEW_PDFIUM_PATH";
+
+// Library paths to try, in order: the env var, then next to the executable.
+// The system library is tried last by `bind_pdfium`.
+pub fn pdfium_library_candidates(env_path: Option<&Path>, exe_dir: Option<&Path>) -> Vec<PathBuf> {
+    let mut candidates = Vec::new();
+    if let Some(path) = env_path {
+        if path.is_dir() {
+            candidates.push(Pdfium::pdfium_platform_library_name_at_path(path));
+        } else {
+            candidates.push(path.to_path_buf());
+        }
+    }
+    if let Some(dir) = exe_dir {
+        candidates.push(Pdfium::pdfium_platform_library_name_at_path(dir));
+    }
+    candidates.retain(|candidate| candidate.is_file());
+    candidates
+}
+
+// Bind to the PDFium library, reporting a missing library instead of panicking
+pub fn bind_pdfium() -> Result<Pdfium, String> {
+    let env_path = std::env::var_os(PDFIUM_PATH_ENV).map(PathBuf::from);
+    let exe_dir = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf));
+
+    for candidate in pdfium_library_candidates(env_path.as_deref(), exe_dir.as_deref()) {
+        match Pdfium::bind_to_library(&candidate) {
+            Ok(bindings) => return Ok(Pdfium::new(bindings)),
+            Err(e) => eprintln!("Failed to bind PDFium at {}: {:?}", candidate.display(), e),
+        }
+    }
+
+    Pdfium::bind_to_system_library()
+        .map(Pdfium::new)
+        .map_err(|e| format!("{} ({:?})", PDFIUM_MISSING_MESSAGE, e))
+}
+
+// Whether PDFium could be bound, checked the first time a PDF is opened
+#[derive(Debug, Default)]
+pub enum PdfiumStatus {
+    #[default]
+    Unchecked,
+    Available,
+    Missing(String),
+}
+
+impl PdfiumStatus {
+    pub fn ensure(&mut self) -> Result<(), String> {
+        self.ensure_with(|| bind_pdfium().map(drop))
+    }
+
+    // `bind` runs once, on the first call; later calls repeat its outcome
+    fn ensure_with(&mut self, bind: impl FnOnce() -> Result<(), String>) -> Result<(), String> {
+        if let PdfiumStatus::Unchecked = self {
+            *self = match bind() {
+                Ok(_) => PdfiumStatus::Available,
+                Err(e) => PdfiumStatus::Missing(e),
+            };
+        }
+        match self {
+            PdfiumStatus::Missing(e) => Err(e.clone()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn library_candidates_go_env_var_then_next_to_the_executable() {
+        let dir = tempfile::tempdir().unwrap();
+        let (env_dir, exe_dir, empty_dir) = (
+            dir.path().join("env"),
+            dir.path().join("exe"),
+            dir.path().join("empty"),
+        );
+        for folder in [&env_dir, &exe_dir, &empty_dir] {
+            std::fs::create_dir_all(folder).unwrap();
+        }
+        let env_library = Pdfium::pdfium_platform_library_name_at_path(&env_dir);
+        let exe_library = Pdfium::pdfium_platform_library_name_at_path(&exe_dir);
+        let named_library = dir.path().join("custom-pdfium.so");
+        for library in [&env_library, &exe_library, &named_library] {
+            std::fs::write(library, "").unwrap();
+        }
+
+        assert_eq!(
+            pdfium_library_candidates(Some(&env_dir), Some(&exe_dir)),
+            [env_library.clone(), exe_library.clone()]
+        );
+        // The env var may name the library file itself
+        assert_eq!(
+            pdfium_library_candidates(Some(&named_library), None),
+            [named_library]
+        );
+        // Absent libraries are skipped, leaving only the system library to try
+        assert_eq!(
+            pdfium_library_candidates(Some(&empty_dir), Some(&exe_dir)),
+            [exe_library]
+        );
+        assert!(
+            pdfium_library_candidates(Some(&dir.path().join("gone.so")), Some(&empty_dir))
+                .is_empty()
+        );
+        assert!(pdfium_library_candidates(None, None).is_empty());
+    }
+
+    #[test]
+    fn failed_binding_is_reported_once_and_remembered() {
+        let mut status = PdfiumStatus::default();
+        let missing = format!("{} (not found)", PDFIUM_MISSING_MESSAGE);
+
+        assert_eq!(
+            status.ensure_with(|| Err(missing.clone())),
+            Err(missing.clone())
+        );
+        assert!(matches!(&status, PdfiumStatus::Missing(e) if *e == missing));
+        // No second attempt
+        assert_eq!(
+            status.ensure_with(|| panic!("bound again")),
+            Err(missing.clone())
+        );
+
+        let mut status = PdfiumStatus::default();
+        assert_eq!(status.ensure_with(|| Ok(())), Ok(()));
+        assert!(matches!(status, PdfiumStatus::Available));
+    }
+}
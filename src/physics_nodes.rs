@@ -3,7 +3,7 @@ use egui::Vec2;
 use petgraph::graph::NodeIndex;
 use petgraph::stable_graph::StableGraph;
 use rayon::prelude::*;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use crate::graph::GraphNode;
 
@@ -22,6 +22,62 @@ impl PhysicsNode {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LayoutPreset {
+    Tight,
+    Balanced,
+    Spread,
+}
+
+impl LayoutPreset {
+    pub const ALL: [LayoutPreset; 3] = [
+        LayoutPreset::Tight,
+        LayoutPreset::Balanced,
+        LayoutPreset::Spread,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LayoutPreset::Tight => "Tight",
+            LayoutPreset::Balanced => "Balanced",
+            LayoutPreset::Spread => "Spread Out",
+        }
+    }
+}
+
+// Turns real elapsed time into whole simulation steps, so layout speed ignores frame rate
+pub struct FixedTimestep {
+    pub steps_per_second: f32,
+    accumulator: f32,
+}
+
+impl FixedTimestep {
+    // Cap after a long stall so the simulation doesn't try to catch up all at once
+    const MAX_STEPS_PER_FRAME: usize = 8;
+
+    pub fn new(steps_per_second: f32) -> Self {
+        Self {
+            steps_per_second,
+            accumulator: 0.0,
+        }
+    }
+
+    pub fn steps(&mut self, elapsed_seconds: f32) -> usize {
+        let step = 1.0 / self.steps_per_second.max(1.0);
+        self.accumulator += elapsed_seconds.max(0.0);
+        let steps = (self.accumulator / step).floor() as usize;
+        if steps > Self::MAX_STEPS_PER_FRAME {
+            self.accumulator = 0.0;
+            return Self::MAX_STEPS_PER_FRAME;
+        }
+        self.accumulator -= steps as f32 * step;
+        steps
+    }
+}
+
+// Mean kinetic energy per node below which the layout counts as settled
+pub const SETTLED_ENERGY: f32 = 0.01;
+
 pub struct PhysicsSimulator {
     pub node_positions: HashMap<NodeIndex, egui::Vec2>,
     pub node_velocities: HashMap<NodeIndex, egui::Vec2>,
@@ -32,6 +88,10 @@ pub struct PhysicsSimulator {
     pub time_step: f32,
     pub friction: f32,
     pub frozen: bool,
+    // Nodes that are moved by forces but exert none on other nodes
+    pub passive_nodes: HashSet<NodeIndex>,
+    // Nodes placed by hand: they push and pull others but stay where they are
+    pub pinned_nodes: HashSet<NodeIndex>,
 }
 
 impl PhysicsSimulator {
@@ -46,15 +106,37 @@ impl PhysicsSimulator {
             time_step: 0.3,
             friction: 0.4,
             frozen: false,
+            passive_nodes: HashSet::new(),
+            pinned_nodes: HashSet::new(),
         }
     }
 
+    // spring, repulsion, edge length, damping, friction, time step
+    pub fn apply_preset(&mut self, preset: LayoutPreset) {
+        let (spring, repulsion, length, damping, friction, time_step) = match preset {
+            LayoutPreset::Tight => (0.4, 6000.0, 80.0, 0.6, 0.5, 0.3),
+            LayoutPreset::Balanced => (0.3, 18000.0, 180.0, 0.55, 0.4, 0.3),
+            LayoutPreset::Spread => (0.15, 40000.0, 280.0, 0.5, 0.3, 0.4),
+        };
+        self.spring_constant = spring;
+        self.repulsion_constant = repulsion;
+        self.ideal_edge_length = length;
+        self.damping = damping;
+        self.friction = friction;
+        self.time_step = time_step;
+    }
+
     pub fn initialize_velocities(&mut self) {
         for node in self.node_positions.keys() {
             self.node_velocities.insert(*node, Vec2::ZERO);
         }
     }
 
+    // Whether `from` pushes or pulls `to`; passive nodes only affect each other
+    fn exerts_force(&self, from: NodeIndex, to: NodeIndex) -> bool {
+        !self.passive_nodes.contains(&from) || self.passive_nodes.contains(&to)
+    }
+
     pub fn update(&mut self, edges: &[(NodeIndex, NodeIndex)]) {
         if self.frozen {
             return;
@@ -84,8 +166,12 @@ impl PhysicsSimulator {
                         let force_magnitude = self.spring_constant * displacement;
                         let spring_force = (delta / distance) * force_magnitude;
 
-                        *spring_forces.entry(node1).or_insert(Vec2::ZERO) += spring_force;
-                        *spring_forces.entry(node2).or_insert(Vec2::ZERO) -= spring_force;
+                        if self.exerts_force(node2, node1) {
+                            *spring_forces.entry(node1).or_insert(Vec2::ZERO) += spring_force;
+                        }
+                        if self.exerts_force(node1, node2) {
+                            *spring_forces.entry(node2).or_insert(Vec2::ZERO) -= spring_force;
+                        }
                     }
                 }
                 spring_forces
@@ -108,8 +194,14 @@ impl PhysicsSimulator {
                             let repulsion_force = (delta / distance)
                                 * (self.repulsion_constant / distance_sq.max(10.0));
 
-                            *repulsion_forces.entry(node1).or_insert(Vec2::ZERO) -= repulsion_force;
-                            *repulsion_forces.entry(node2).or_insert(Vec2::ZERO) += repulsion_force;
+                            if self.exerts_force(node2, node1) {
+                                *repulsion_forces.entry(node1).or_insert(Vec2::ZERO) -=
+                                    repulsion_force;
+                            }
+                            if self.exerts_force(node1, node2) {
+                                *repulsion_forces.entry(node2).or_insert(Vec2::ZERO) +=
+                                    repulsion_force;
+                            }
                         }
                     }
                 }
@@ -127,6 +219,9 @@ impl PhysicsSimulator {
 
         // Update velocities and positions
         for (node_idx, force) in forces {
+            if self.pinned_nodes.contains(&node_idx) {
+                continue;
+            }
             if let (Some(pos), Some(vel)) = (
                 self.node_positions.get_mut(&node_idx),
                 self.node_velocities.get_mut(&node_idx),
@@ -139,6 +234,22 @@ impl PhysicsSimulator {
         }
     }
 
+    pub fn kinetic_energy(&self) -> f32 {
+        if self.node_velocities.is_empty() {
+            return 0.0;
+        }
+        let total: f32 = self
+            .node_velocities
+            .values()
+            .map(|vel| 0.5 * vel.length_sq())
+            .sum();
+        total / self.node_velocities.len() as f32
+    }
+
+    pub fn is_settled(&self) -> bool {
+        !self.node_velocities.is_empty() && self.kinetic_energy() < SETTLED_ENERGY
+    }
+
     pub fn get_node_position(&self, index: NodeIndex) -> Option<&egui::Vec2> {
         self.node_positions.get(&index)
     }
@@ -156,6 +267,7 @@ impl PhysicsSimulator {
 
     pub fn reset_positions(&mut self, initial_layout: &HashMap<NodeIndex, egui::Vec2>) {
         self.node_positions = initial_layout.clone();
+        self.pinned_nodes.clear();
         self.initialize_velocities();
     }
 
@@ -190,3 +302,89 @@ impl PhysicsSimulator {
     ) {
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_timestep_turns_elapsed_time_into_whole_steps() {
+        let mut clock = FixedTimestep::new(60.0);
+        // Fast and slow frames add up to the same number of steps
+        let fast: usize = (0..120).map(|_| clock.steps(1.0 / 120.0)).sum();
+        assert_eq!(fast, 60);
+        let slow: usize = (0..30).map(|_| clock.steps(1.0 / 30.0)).sum();
+        assert_eq!(slow, 60);
+
+        // Leftover time carries into the next frame
+        let mut clock = FixedTimestep::new(10.0);
+        assert_eq!(clock.steps(0.15), 1);
+        assert_eq!(clock.steps(0.06), 1);
+        // A long stall is capped instead of caught up
+        assert_eq!(clock.steps(5.0), FixedTimestep::MAX_STEPS_PER_FRAME);
+        assert_eq!(clock.steps(0.0), 0);
+    }
+
+    #[test]
+    fn passive_image_nodes_are_pushed_but_push_nothing() {
+        let [note, other_note, image] = [0, 1, 2].map(NodeIndex::new);
+        let simulator_with = |passive: &[NodeIndex]| {
+            let mut simulator = PhysicsSimulator::new();
+            simulator.node_positions = HashMap::from([
+                (note, Vec2::new(0.0, 0.0)),
+                (other_note, Vec2::new(300.0, 0.0)),
+                (image, Vec2::new(20.0, 0.0)),
+            ]);
+            simulator.initialize_velocities();
+            simulator.passive_nodes = passive.iter().copied().collect();
+            simulator
+        };
+        let edges = [(note, image)];
+
+        // The notes move exactly as they would with the image gone
+        let mut without_image = simulator_with(&[]);
+        without_image.node_positions.remove(&image);
+        without_image.initialize_velocities();
+        without_image.update(&[]);
+        let mut passive = simulator_with(&[image]);
+        passive.update(&edges);
+        for node in [note, other_note] {
+            assert_eq!(
+                passive.node_positions[&node],
+                without_image.node_positions[&node]
+            );
+        }
+        // The image is still pushed away from the note next to it
+        assert!(passive.node_positions[&image].x > 20.0);
+
+        let mut active = simulator_with(&[]);
+        active.update(&edges);
+        assert_ne!(
+            active.node_positions[&note],
+            without_image.node_positions[&note]
+        );
+    }
+
+    #[test]
+    fn presets_set_all_six_physics_fields() {
+        let fields = |simulator: &PhysicsSimulator| {
+            (
+                simulator.spring_constant,
+                simulator.repulsion_constant,
+                simulator.ideal_edge_length,
+                simulator.damping,
+                simulator.friction,
+                simulator.time_step,
+            )
+        };
+        let mut simulator = PhysicsSimulator::new();
+
+        simulator.apply_preset(LayoutPreset::Spread);
+        assert_eq!(fields(&simulator), (0.15, 40000.0, 280.0, 0.5, 0.3, 0.4));
+        simulator.apply_preset(LayoutPreset::Tight);
+        assert_eq!(fields(&simulator), (0.4, 6000.0, 80.0, 0.6, 0.5, 0.3));
+        // Balanced is where a new simulator starts
+        simulator.apply_preset(LayoutPreset::Balanced);
+        assert_eq!(fields(&simulator), fields(&PhysicsSimulator::new()));
+    }
+}
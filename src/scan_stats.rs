@@ -0,0 +1,205 @@
+// src/scan_stats.rs
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::PathBuf;
+
+use crate::file_scan::FileScanner;
+use crate::graph::{files_by_stem, resolve_by_stem};
+
+const STORAGE_KEY: &str = "nexusview_scan_snapshots";
+
+// What a scan found at one point in time, kept to compare later scans against
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScanSnapshot {
+    // Local time the snapshot was taken, for display
+    pub taken_at: String,
+    pub files: BTreeSet<PathBuf>,
+    // Source -> target, only where the target was scanned too
+    pub links: BTreeSet<(PathBuf, PathBuf)>,
+    // Source -> link text that leads nowhere
+    pub broken_links: BTreeSet<(PathBuf, String)>,
+    pub tag_count: usize,
+    pub orphan_count: usize,
+}
+
+impl ScanSnapshot {
+    pub fn from_scanner(scanner: &FileScanner, taken_at: String) -> Self {
+        let files: BTreeSet<PathBuf> = scanner.files.keys().cloned().collect();
+        // Resolved the way the graph resolves them, so `[[index]]` finds `notes/index.md`
+        let by_stem = files_by_stem(&files);
+        let mut links = BTreeSet::new();
+        let mut broken_links = BTreeSet::new();
+        for (source, targets) in &scanner.files {
+            for target in targets {
+                let resolved = files
+                    .get(target)
+                    .or_else(|| resolve_by_stem(target, source, &by_stem));
+                match resolved {
+                    Some(resolved) => {
+                        links.insert((source.clone(), resolved.clone()));
+                    }
+                    None => {
+                        broken_links.insert((source.clone(), target.display().to_string()));
+                    }
+                }
+            }
+        }
+        for (source, labels) in &scanner.broken_links {
+            for label in labels {
+                broken_links.insert((source.clone(), label.clone()));
+            }
+        }
+        let linked: HashSet<&PathBuf> = links
+            .iter()
+            .flat_map(|(source, target)| [source, target])
+            .collect();
+        let tag_count = scanner
+            .tags
+            .values()
+            .flatten()
+            .collect::<HashSet<_>>()
+            .len();
+        Self {
+            taken_at,
+            orphan_count: files.iter().filter(|file| !linked.contains(file)).count(),
+            files,
+            links,
+            broken_links,
+            tag_count,
+        }
+    }
+
+    // What changed between this snapshot and a later one
+    pub fn diff(&self, later: &ScanSnapshot) -> SnapshotDiff {
+        SnapshotDiff {
+            added_files: later.files.difference(&self.files).cloned().collect(),
+            removed_files: self.files.difference(&later.files).cloned().collect(),
+            new_links: later.links.difference(&self.links).cloned().collect(),
+            removed_links: self.links.difference(&later.links).cloned().collect(),
+            new_broken_links: later
+                .broken_links
+                .difference(&self.broken_links)
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SnapshotDiff {
+    pub added_files: Vec<PathBuf>,
+    pub removed_files: Vec<PathBuf>,
+    pub new_links: Vec<(PathBuf, PathBuf)>,
+    pub removed_links: Vec<(PathBuf, PathBuf)>,
+    pub new_broken_links: Vec<(PathBuf, String)>,
+}
+
+impl SnapshotDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added_files.is_empty()
+            && self.removed_files.is_empty()
+            && self.new_links.is_empty()
+            && self.removed_links.is_empty()
+            && self.new_broken_links.is_empty()
+    }
+}
+
+// The last snapshot of each scan root, stored alongside the settings
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct ScanSnapshots {
+    pub by_root: HashMap<PathBuf, ScanSnapshot>,
+}
+
+impl ScanSnapshots {
+    pub fn load(storage: Option<&dyn eframe::Storage>) -> Self {
+        storage
+            .and_then(|storage| eframe::get_value(storage, STORAGE_KEY))
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, STORAGE_KEY, self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scanner_with(files: &[(&str, &[&str])]) -> FileScanner {
+        let mut scanner = FileScanner::new("/notes");
+        for (path, links) in files {
+            scanner.files.insert(
+                PathBuf::from(path),
+                links.iter().map(PathBuf::from).collect(),
+            );
+        }
+        scanner
+    }
+
+    #[test]
+    fn diff_lists_added_and_removed_files_and_links() {
+        let before = ScanSnapshot::from_scanner(
+            &scanner_with(&[
+                ("/notes/a.md", &["/notes/b.md"]),
+                ("/notes/b.md", &[]),
+                ("/notes/old.md", &["/notes/a.md"]),
+            ]),
+            "before".to_string(),
+        );
+        let after = ScanSnapshot::from_scanner(
+            &scanner_with(&[
+                ("/notes/a.md", &["/notes/b.md", "/notes/new.md"]),
+                ("/notes/b.md", &["/notes/old.md"]),
+                ("/notes/new.md", &[]),
+            ]),
+            "after".to_string(),
+        );
+        let path = |name: &str| PathBuf::from(name);
+
+        let diff = before.diff(&after);
+        assert_eq!(diff.added_files, [path("/notes/new.md")]);
+        assert_eq!(diff.removed_files, [path("/notes/old.md")]);
+        assert_eq!(
+            diff.new_links,
+            [(path("/notes/a.md"), path("/notes/new.md"))]
+        );
+        assert_eq!(
+            diff.removed_links,
+            [(path("/notes/old.md"), path("/notes/a.md"))]
+        );
+        // b.md still links to the deleted note
+        assert_eq!(
+            diff.new_broken_links,
+            [(path("/notes/b.md"), "/notes/old.md".to_string())]
+        );
+
+        assert!(after.diff(&after).is_empty());
+        let reverse = after.diff(&before);
+        assert_eq!(reverse.added_files, diff.removed_files);
+        assert_eq!(reverse.removed_files, diff.added_files);
+    }
+
+    #[test]
+    fn basename_links_across_folders_count_as_resolved() {
+        // `[[plan]]` in journal/today.md, joined to the root as the scan does
+        let snapshot = ScanSnapshot::from_scanner(
+            &scanner_with(&[
+                ("/notes/journal/today.md", &["/notes/plan"]),
+                ("/notes/projects/plan.md", &[]),
+            ]),
+            "now".to_string(),
+        );
+
+        assert_eq!(
+            snapshot.links,
+            BTreeSet::from([(
+                PathBuf::from("/notes/journal/today.md"),
+                PathBuf::from("/notes/projects/plan.md")
+            )])
+        );
+        assert!(snapshot.broken_links.is_empty());
+        assert_eq!(snapshot.orphan_count, 0);
+    }
+}
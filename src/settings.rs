@@ -0,0 +1,119 @@
+// src/settings.rs
+use serde::{Deserialize, Serialize};
+
+use crate::graph::GraphNode;
+use crate::keybindings::KeyBindings;
+use crate::ui::GraphMode;
+
+const STORAGE_KEY: &str = "nexusview_settings";
+
+// UI state remembered between sessions. Window positions are stored by egui itself.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppSettings {
+    pub show_physics_window: bool,
+    pub content_font_size: f32,
+    pub content_line_spacing: f32,
+    pub graph_mode: GraphMode,
+    // Stored by path or tag name, since node indices change between scans
+    pub selected_node: Option<GraphNode>,
+    pub show_content_panel: bool,
+    // Graph building stops here so huge folders stay usable; scan data is kept
+    pub max_graph_nodes: usize,
+    pub keybindings: KeyBindings,
+    // Stop the simulation once the layout first settles after a scan
+    pub freeze_when_settled: bool,
+    // Watch-triggered rescans wait this long so bursts of saves coalesce
+    pub watch_debounce_ms: u64,
+    pub batch_watch_events: bool,
+    // Read-only: no file edits and no opening files in other apps
+    pub safe_mode: bool,
+    // Most recent first
+    pub search_history: Vec<String>,
+    // Pointer travel in pixels that turns a click on a node into a drag
+    pub node_drag_threshold: f32,
+    pub respect_gitignore: bool,
+    // None scans every subfolder
+    pub max_scan_depth: Option<usize>,
+    // Larger files are left out of scans; None reads every file
+    pub max_scan_file_size_mb: Option<u64>,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            show_physics_window: true,
+            content_font_size: 14.0,
+            content_line_spacing: 1.0,
+            graph_mode: GraphMode::Links,
+            selected_node: None,
+            show_content_panel: true,
+            max_graph_nodes: 5000,
+            keybindings: KeyBindings::default(),
+            freeze_when_settled: false,
+            watch_debounce_ms: 1000,
+            batch_watch_events: true,
+            safe_mode: false,
+            search_history: Vec::new(),
+            node_drag_threshold: 6.0,
+            respect_gitignore: false,
+            max_scan_depth: None,
+            max_scan_file_size_mb: None,
+        }
+    }
+}
+
+impl AppSettings {
+    pub fn load(storage: Option<&dyn eframe::Storage>) -> Self {
+        storage
+            .and_then(|storage| eframe::get_value(storage, STORAGE_KEY))
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, STORAGE_KEY, self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eframe::Storage;
+    use std::collections::HashMap;
+
+    #[derive(Default)]
+    struct MemoryStorage(HashMap<String, String>);
+
+    impl Storage for MemoryStorage {
+        fn get_string(&self, key: &str) -> Option<String> {
+            self.0.get(key).cloned()
+        }
+
+        fn set_string(&mut self, key: &str, value: String) {
+            self.0.insert(key.to_string(), value);
+        }
+
+        fn flush(&mut self) {}
+    }
+
+    #[test]
+    fn closed_physics_window_stays_closed_after_a_restart() {
+        let mut storage = MemoryStorage::default();
+        // First run opens it
+        assert!(AppSettings::load(None).show_physics_window);
+        assert!(AppSettings::load(Some(&storage)).show_physics_window);
+
+        let settings = AppSettings {
+            show_physics_window: false,
+            ..AppSettings::default()
+        };
+        settings.save(&mut storage);
+        assert_eq!(AppSettings::load(Some(&storage)), settings);
+
+        // Settings saved before a field existed load with its default
+        storage.set_string(STORAGE_KEY, "(show_physics_window: false)".to_string());
+        let loaded = AppSettings::load(Some(&storage));
+        assert!(!loaded.show_physics_window);
+        assert_eq!(loaded.node_drag_threshold, 6.0);
+    }
+}
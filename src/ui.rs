@@ -12,39 +12,74 @@ use pdf::primitive::PdfString;
 use pdf_extract::content::Operation;
 use pdfium_render::prelude::{
     PdfBitmap, PdfBitmapFormat, PdfDocument, PdfDocumentMetadataTagType, PdfMetadata, PdfPage,
-    PdfRenderConfig, Pdfium, PdfiumError,
+    PdfRenderConfig, PdfiumError,
 };
 use petgraph::stable_graph::{NodeIndex, StableGraph};
 use petgraph::visit::{EdgeRef, IntoEdgeReferences};
 use rand::Rng;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc;
 use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::Instant;
+use std::time::{Duration, Instant, SystemTime};
 use syntect::easy::HighlightLines;
 use syntect::highlighting::{Theme, ThemeSet};
 use syntect::parsing::{SyntaxReference, SyntaxSet};
 use syntect::util::LinesWithEndings;
 
-use crate::file_scan::FileScanner;
-use crate::graph::{FileGraph, GraphNode, TagGraph};
-use crate::physics_nodes::PhysicsSimulator;
+use crate::command_palette::{Command, CommandPalette};
+use crate::file_edits::{self, FileEdit};
+use crate::file_scan::{FileScanner, find_duplicates};
+use crate::graph::{
+    AdjacencyIndex, FileGraph, GraphNode, TagGraph, files_sharing_tags, filter_by_min_degree,
+    isolate, link_cycles, neighborhood, node_metadata, orphan_nodes, pick_random_node,
+    tag_badge_counts, tag_file_index,
+};
+use crate::graph_export::{CooccurrenceMatrix, ExportedGraph};
+use crate::graph_image::{GraphPicture, PictureEdge, PictureNode, default_font_bytes};
+use crate::keybindings::{Action, KeyBindings, captured_shortcut};
+use crate::pdf_backend::{PdfiumStatus, bind_pdfium};
+use crate::physics_nodes::{FixedTimestep, LayoutPreset, PhysicsSimulator};
+use crate::scan_stats::{ScanSnapshot, ScanSnapshots, SnapshotDiff};
+use crate::settings::AppSettings;
 use crate::utils::{
-    is_code_path, is_image_path, is_markdown_path, is_pdf_path, pdf_utils, rotate_vec2,
+    LabelTruncation, MarkdownSegment, RecentWindow, append_tag, apply_tag_completion,
+    bundled_control_point, circle_points, containing_archive, curved_control_point, density_grid,
+    display_path, edge_end_direction, exceeds_drag_threshold, find_match_ranges, flow_phase,
+    follow_offset, is_code_path, is_hidden_path, is_image_path, is_markdown_path, is_pdf_path,
+    is_scan_root, neighbor_centroids, open_with_default_app, path_glob, pdf_utils, point_on_edge,
+    push_history, read_excerpt, relative_to_root, rotate_vec2, screen_to_graph, smooth_grid,
+    split_markdown_code_fences, tag_completions, tag_matches_filter, top_level_folder,
+    truncate_label, wikilink_list, zoom_about,
 };
+use crate::view_state::ViewState;
+use crate::watch::{PollWatcher, RescanDebouncer, spawn_poll_watcher};
 
 // Lazy-loaded syntax set and theme
 static SYNTAX_SET: Lazy<SyntaxSet> = Lazy::new(|| SyntaxSet::load_defaults_newlines());
 static THEME_SET: Lazy<ThemeSet> = Lazy::new(|| ThemeSet::load_defaults());
 static DEFAULT_THEME: Lazy<&'static Theme> = Lazy::new(|| &THEME_SET.themes["base16-ocean.dark"]);
 
-#[derive(PartialEq)]
-enum GraphMode {
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum GraphMode {
     Links,
     Tags,
+    // Links and tags in one graph
+    Unified,
+}
+
+impl GraphMode {
+    pub fn label(self) -> &'static str {
+        match self {
+            GraphMode::Links => "Links",
+            GraphMode::Tags => "Tags",
+            GraphMode::Unified => "Unified",
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -53,6 +88,378 @@ struct DirectoryNode {
     children: Vec<DirectoryNode>,
     expanded: bool,
     selected: bool,
+    populated: bool,
+    // Last `matches_filter` answer, with the query and hidden-files setting it was for
+    filter_match: Option<(String, bool, bool)>,
+}
+
+// Append `text`, which starts at byte `offset` of the document, with find matches marked
+fn append_with_matches(
+    job: &mut LayoutJob,
+    text: &str,
+    offset: usize,
+    format: TextFormat,
+    matches: &[std::ops::Range<usize>],
+    current: Option<usize>,
+) {
+    let end = offset + text.len();
+    let mut pos = offset;
+    for (i, found) in matches.iter().enumerate() {
+        if found.end <= pos || found.start >= end {
+            continue;
+        }
+        let start = found.start.max(pos);
+        let stop = found.end.min(end);
+        if start > pos {
+            job.append(&text[pos - offset..start - offset], 0.0, format.clone());
+        }
+        let mut marked = format.clone();
+        marked.background = if Some(i) == current {
+            Color32::from_rgb(255, 140, 0)
+        } else {
+            Color32::from_rgb(120, 100, 0)
+        };
+        job.append(&text[start - offset..stop - offset], 0.0, marked);
+        pos = stop;
+    }
+    if pos < end {
+        job.append(&text[pos - offset..], 0.0, format);
+    }
+}
+
+const ROOT_FOLDER_COLOR: Color32 = Color32::from_gray(160);
+
+// Evenly spaced hues for the sorted folder names, so colours stay put between frames
+fn folder_palette(folders: &[String]) -> Vec<(String, Color32)> {
+    let mut sorted = folders.to_vec();
+    sorted.sort();
+    sorted.dedup();
+    let count = sorted.len().max(1) as f32;
+    sorted
+        .into_iter()
+        .enumerate()
+        .map(|(i, folder)| {
+            let color = egui::ecolor::Hsva::new(i as f32 / count, 0.6, 0.9, 1.0);
+            (folder, color.into())
+        })
+        .collect()
+}
+
+// Text styles for the content panel at the reader's chosen size and spacing
+fn content_style(base: &egui::Style, font_size: f32, line_spacing: f32) -> egui::Style {
+    let mut style = base.clone();
+    for (text_style, font_id) in style.text_styles.iter_mut() {
+        font_id.size = match text_style {
+            egui::TextStyle::Heading => font_size * 1.45,
+            egui::TextStyle::Small => font_size * 0.75,
+            egui::TextStyle::Body | egui::TextStyle::Monospace => font_size,
+            _ => font_id.size,
+        };
+    }
+    // Markdown paragraphs are laid out by egui_commonmark, so the spacing there is
+    // applied between them; other content text sets its line height directly
+    style.spacing.item_spacing.y = base.spacing.item_spacing.y * line_spacing;
+    style
+}
+
+// Content panel text with lines `line_spacing` times the font size apart
+fn content_text_format(font_id: egui::FontId, color: Color32, line_spacing: f32) -> TextFormat {
+    TextFormat {
+        line_height: Some(font_id.size * line_spacing),
+        font_id,
+        color,
+        ..Default::default()
+    }
+}
+
+const MIN_LABEL_FONT_SIZE: f32 = 8.0;
+const MAX_LABEL_FONT_SIZE: f32 = 32.0;
+
+// Label size for the current zoom, kept readable at both extremes
+fn label_font_size(base_size: f32, label_scale: f32, zoom: f32, scale_with_zoom: bool) -> f32 {
+    let zoom_factor = if scale_with_zoom { zoom } else { 1.0 };
+    (base_size * label_scale * zoom_factor).clamp(MIN_LABEL_FONT_SIZE, MAX_LABEL_FONT_SIZE)
+}
+
+// Node radius multiplier for a file of `bytes`: grows with the log of the size,
+// clamped so one huge file can't dwarf the rest
+fn file_size_radius_scale(bytes: u64) -> f32 {
+    (0.6 + 0.1 * (1.0 + bytes as f32 / 1024.0).log2()).clamp(0.6, 2.5)
+}
+
+// Node radius multiplier for a node with `degree` incident edges, also logarithmic and
+// clamped so a hub stays readable next to its neighbours
+fn degree_radius_scale(degree: usize) -> f32 {
+    (0.8 + 0.3 * (1.0 + degree as f32).log2()).clamp(0.8, 2.5)
+}
+
+// Screen size of one heatmap cell, in points
+const HEATMAP_CELL_SIZE: f32 = 24.0;
+const MAX_FLOW_EDGES: usize = 500;
+// Sideways bend of curved edges, as a fraction of their length
+const EDGE_CURVATURE: f32 = 0.15;
+const FLOW_DOTS_PER_EDGE: usize = 2;
+// Edge lengths travelled per second by the flow dots
+const FLOW_SPEED: f32 = 0.4;
+const SAFE_MODE_HINT: &str = "Disabled in Safe Mode";
+// Files sharing a tag with the selected file
+const SHARED_TAG_COLOR: Color32 = Color32::from_rgb(0, 220, 220);
+const CYCLE_COLOR: Color32 = Color32::from_rgb(255, 80, 160);
+const DIRECTORY_NODE_COLOR: Color32 = Color32::from_rgb(200, 170, 110);
+// How much of its colour an unlinked node keeps
+const ORPHAN_NODE_FADE: f32 = 0.45;
+
+// Cool blue for sparse areas up to a warm orange for dense ones, always translucent
+fn heatmap_color(t: f32) -> Color32 {
+    let t = t.clamp(0.0, 1.0);
+    let lerp = |a: f32, b: f32| (a + (b - a) * t) as u8;
+    Color32::from_rgba_unmultiplied(
+        lerp(40.0, 255.0),
+        lerp(70.0, 110.0),
+        lerp(200.0, 30.0),
+        lerp(10.0, 90.0),
+    )
+}
+
+// Undo history length for file edits made from the graph
+const MAX_EDIT_HISTORY: usize = 50;
+const SEARCH_HISTORY_LEN: usize = 10;
+
+const MAX_DUPLICATE_HASH_SIZE: u64 = 64 * 1024 * 1024;
+const HOVER_PREVIEW_DELAY: f64 = 0.5;
+const HOVER_PREVIEW_LINES: usize = 12;
+const HOVER_PREVIEW_LINE_CHARS: usize = 120;
+// Depth offered when a scan depth limit is first switched on
+const DEFAULT_MAX_SCAN_DEPTH: usize = 3;
+// Size offered when a file size limit is first switched on
+const DEFAULT_MAX_SCAN_FILE_SIZE_MB: u64 = 10;
+// How often the watcher checks the folder for changes
+const WATCH_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+// What the hover popup shows for a file, cached per path and modification time
+#[derive(Debug, Clone, PartialEq)]
+enum HoverPreview {
+    // Being read on a worker thread
+    Loading,
+    Text(String),
+    Image { width: u32, height: u32 },
+    Pdf { pages: Option<usize> },
+    Unavailable,
+}
+
+impl HoverPreview {
+    fn load(path: &Path) -> Self {
+        if is_image_path(path) {
+            image::image_dimensions(path)
+                .map(|(width, height)| HoverPreview::Image { width, height })
+                .unwrap_or(HoverPreview::Unavailable)
+        } else if is_pdf_path(path) {
+            let pages = pdf::file::FileOptions::cached()
+                .open(path)
+                .ok()
+                .map(|file| file.pages().count());
+            HoverPreview::Pdf { pages }
+        } else {
+            read_excerpt(path, HOVER_PREVIEW_LINES, HOVER_PREVIEW_LINE_CHARS)
+                .map(HoverPreview::Text)
+                .unwrap_or(HoverPreview::Unavailable)
+        }
+    }
+
+    fn show(&self, ui: &mut egui::Ui) {
+        match self {
+            HoverPreview::Text(excerpt) if !excerpt.trim().is_empty() => {
+                ui.separator();
+                ui.label(egui::RichText::new(excerpt).monospace().small());
+            }
+            HoverPreview::Image { width, height } => {
+                ui.label(format!("Image: {} × {} px", width, height));
+            }
+            HoverPreview::Pdf { pages: Some(pages) } => {
+                ui.label(format!("PDF: {} pages", pages));
+            }
+            HoverPreview::Loading => {
+                ui.spinner();
+            }
+            _ => {}
+        }
+    }
+}
+
+// Which panels and windows are open, saved while presentation mode hides them
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ChromeVisibility {
+    directory_panel: bool,
+    content_panel: bool,
+    physics_window: bool,
+    display_window: bool,
+    settings_window: bool,
+    duplicates_window: bool,
+}
+
+impl ChromeVisibility {
+    const HIDDEN: Self = Self {
+        directory_panel: false,
+        content_panel: false,
+        physics_window: false,
+        display_window: false,
+        settings_window: false,
+        duplicates_window: false,
+    };
+}
+
+// What the central panel shows
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CentralView {
+    Welcome,
+    Graph,
+    EmptyResult,
+}
+
+impl CentralView {
+    fn choose(has_scanned: bool, is_scanning: bool, node_count: usize) -> Self {
+        if node_count > 0 || is_scanning {
+            CentralView::Graph
+        } else if has_scanned {
+            CentralView::EmptyResult
+        } else {
+            CentralView::Welcome
+        }
+    }
+}
+
+// How the content panel shows a text file
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TextView {
+    // Search works on the markdown source
+    MarkdownSource,
+    Markdown,
+    Code,
+    SearchableText,
+    // Read-only but selectable, so parts can be copied
+    SelectableText,
+}
+
+impl TextView {
+    fn choose(is_markdown: bool, is_code: bool, searching: bool) -> Self {
+        match (is_markdown, is_code, searching) {
+            (true, _, true) => TextView::MarkdownSource,
+            (true, _, false) => TextView::Markdown,
+            (false, true, _) => TextView::Code,
+            (false, false, true) => TextView::SearchableText,
+            (false, false, false) => TextView::SelectableText,
+        }
+    }
+}
+
+// Contents of the bottom status bar. Counts are the previous frame's draw set, since
+// the bar is laid out before the graph is drawn.
+#[derive(Debug, Clone, PartialEq)]
+struct StatusBar {
+    visible_nodes: usize,
+    visible_edges: usize,
+    graph_mode: GraphMode,
+    zoom: f32,
+    // Name and full path, or tag / URL twice
+    hovered: Option<(String, String)>,
+}
+
+impl StatusBar {
+    fn show(&self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label(format!(
+                "{} nodes · {} edges",
+                self.visible_nodes, self.visible_edges
+            ));
+            ui.separator();
+            ui.label(format!("Mode: {}", self.graph_mode.label()));
+            ui.separator();
+            ui.label(format!("Zoom: {:.0}%", self.zoom * 100.0));
+            if let Some((name, path)) = &self.hovered {
+                ui.separator();
+                ui.strong(name);
+                if path != name {
+                    ui.weak(path);
+                }
+            }
+        });
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SettingsTab {
+    Scan,
+    Display,
+    Physics,
+}
+
+#[derive(Debug, PartialEq)]
+enum DropAction {
+    ScanDirectories(Vec<PathBuf>),
+    PreviewFile(PathBuf),
+    Ignore,
+}
+
+impl DropAction {
+    // Any dropped folder or zip archive means "scan", otherwise the first dropped file is previewed
+    fn classify(paths: &[PathBuf]) -> Self {
+        let directories: Vec<PathBuf> = paths.iter().filter(|p| is_scan_root(p)).cloned().collect();
+        if !directories.is_empty() {
+            DropAction::ScanDirectories(directories)
+        } else if let Some(file) = paths.iter().find(|p| p.is_file()) {
+            DropAction::PreviewFile(file.clone())
+        } else {
+            DropAction::Ignore
+        }
+    }
+}
+
+// The three graphs, built together off the UI thread by build_graphs
+struct BuiltGraphs {
+    file_graph: FileGraph,
+    tag_graph: TagGraph,
+    unified_graph: FileGraph,
+}
+
+enum GraphBuildMessage {
+    Progress(f32, String),
+    Done(Result<Box<BuiltGraphs>, String>),
+}
+
+// Builds every graph from scratch. `progress` gets the overall fraction done, from
+// 0 to 1, and what is being built.
+// None once `cancel` is set; it's checked between graphs
+fn build_all_graphs(
+    scanner: &FileScanner,
+    directory_grouping: Option<HashSet<PathBuf>>,
+    show_untagged_files: bool,
+    max_nodes: usize,
+    cancel: &AtomicBool,
+    progress: &mut dyn FnMut(f32, &str),
+) -> Option<BuiltGraphs> {
+    let cancelled = || cancel.load(Ordering::Relaxed);
+    let mut file_graph = FileGraph::new();
+    file_graph.directory_grouping = directory_grouping;
+    file_graph.build_from_scanner(scanner, max_nodes, &mut |fraction| {
+        progress(0.5 * fraction, "Building file graph...")
+    });
+    if cancelled() {
+        return None;
+    }
+    let mut tag_graph = TagGraph::new();
+    tag_graph.build_from_tags(scanner, show_untagged_files, max_nodes, &mut |fraction| {
+        progress(0.5 + 0.5 * fraction, "Building tag graph...")
+    });
+    if cancelled() {
+        return None;
+    }
+    let mut unified_graph = FileGraph::new();
+    unified_graph.update_unified(scanner, max_nodes);
+    (!cancelled()).then_some(BuiltGraphs {
+        file_graph,
+        tag_graph,
+        unified_graph,
+    })
 }
 
 #[derive(Debug, PartialEq)]
@@ -121,6 +528,114 @@ impl PdfViewerState {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ImageViewState {
+    zoom: f32,
+    offset: egui::Vec2,
+    fit_to_panel: bool,
+}
+
+impl Default for ImageViewState {
+    fn default() -> Self {
+        Self {
+            zoom: 1.0,
+            offset: egui::Vec2::ZERO,
+            fit_to_panel: true,
+        }
+    }
+}
+
+impl ImageViewState {
+    // Largest scale at which the whole image fits inside the viewport
+    fn fit_scale(image_size: egui::Vec2, viewport: egui::Vec2) -> f32 {
+        if image_size.x <= 0.0 || image_size.y <= 0.0 {
+            return 1.0;
+        }
+        (viewport.x / image_size.x)
+            .min(viewport.y / image_size.y)
+            .max(0.01)
+    }
+}
+
+// Frames stay decoded in memory; only the one on screen is uploaded as a texture
+struct GifAnimation {
+    frames: Vec<egui::ColorImage>,
+    delays: Vec<f32>,
+    started_at: f64,
+    texture: egui::TextureHandle,
+    shown_frame: usize,
+}
+
+impl GifAnimation {
+    fn load(path: &Path, ctx: &egui::Context) -> Option<Self> {
+        use image::AnimationDecoder;
+
+        let file = fs::File::open(path).ok()?;
+        let decoder = image::codecs::gif::GifDecoder::new(std::io::BufReader::new(file)).ok()?;
+        let frames = decoder.into_frames().collect_frames().ok()?;
+        if frames.len() <= 1 {
+            return None; // Static GIFs go through the regular image path
+        }
+
+        let mut images = Vec::with_capacity(frames.len());
+        let mut delays = Vec::with_capacity(frames.len());
+        for frame in frames {
+            let (numer, denom) = frame.delay().numer_denom_ms();
+            let delay_secs = numer as f32 / denom.max(1) as f32 / 1000.0;
+            // Browsers treat near-zero delays as 100ms, do the same
+            delays.push(if delay_secs < 0.02 { 0.1 } else { delay_secs });
+
+            let buffer = frame.into_buffer();
+            let size = [buffer.width() as usize, buffer.height() as usize];
+            images.push(egui::ColorImage::from_rgba_unmultiplied(
+                size,
+                buffer.as_raw(),
+            ));
+        }
+
+        let texture = ctx.load_texture(
+            path.display().to_string(),
+            images[0].clone(),
+            Default::default(),
+        );
+        Some(Self {
+            frames: images,
+            delays,
+            started_at: ctx.input(|i| i.time),
+            texture,
+            shown_frame: 0,
+        })
+    }
+
+    // Frame showing `elapsed` seconds into the looping animation, and the seconds left
+    // until the next one
+    fn frame_timing(delays: &[f32], elapsed: f64) -> (usize, f64) {
+        let total: f64 = delays.iter().map(|&d| d as f64).sum();
+        if delays.is_empty() || total <= 0.0 {
+            return (0, f64::INFINITY);
+        }
+        let mut remaining = elapsed.rem_euclid(total);
+        for (i, &delay) in delays.iter().enumerate() {
+            if remaining < delay as f64 {
+                return (i, delay as f64 - remaining);
+            }
+            remaining -= delay as f64;
+        }
+        (delays.len() - 1, 0.0)
+    }
+
+    // The texture, switched to the frame due at `now`, and when the next one is due
+    fn show_frame(&mut self, now: f64) -> (&egui::TextureHandle, f64) {
+        let (frame, until_next) = Self::frame_timing(&self.delays, now - self.started_at);
+        if frame != self.shown_frame {
+            self.texture
+                .set(self.frames[frame].clone(), Default::default());
+            self.shown_frame = frame;
+        }
+        (&self.texture, until_next)
+    }
+}
+
 #[derive(Clone)]
 struct TextLayout {
     text: String,
@@ -143,6 +658,70 @@ impl Default for RenderQuality {
     }
 }
 
+// Graph drawing detail. Low skips glow rings and shadows, which are several extra
+// shapes per edge and node and add up on large graphs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum GraphQuality {
+    Low,
+    High,
+}
+
+impl GraphQuality {
+    fn draws_effects(self) -> bool {
+        self == GraphQuality::High
+    }
+
+    // How many of `full` decorative passes to draw
+    fn effect_passes(self, full: usize) -> usize {
+        if self.draws_effects() { full } else { 0 }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct EdgeStyle {
+    color: Color32,
+    opacity: f32,
+    width: f32,
+    glow: bool,
+}
+
+impl Default for EdgeStyle {
+    fn default() -> Self {
+        Self {
+            color: Color32::from_rgb(100, 100, 255),
+            opacity: 150.0 / 255.0,
+            width: 1.5,
+            glow: true,
+        }
+    }
+}
+
+impl EdgeStyle {
+    fn color_with_alpha(&self, fraction: f32) -> Color32 {
+        let alpha = (self.opacity * fraction * 255.0).round().clamp(0.0, 255.0) as u8;
+        Color32::from_rgba_premultiplied(self.color.r(), self.color.g(), self.color.b(), alpha)
+    }
+
+    fn stroke(&self, zoom: f32) -> Stroke {
+        Stroke::new(self.width * zoom, self.color_with_alpha(1.0))
+    }
+
+    // Fading strokes drawn under the main edge, empty when glow is off or at low quality
+    fn glow_strokes(&self, zoom: f32, quality: GraphQuality) -> Vec<Stroke> {
+        if !self.glow || !quality.draws_effects() {
+            return Vec::new();
+        }
+        (0..3)
+            .map(|i| {
+                Stroke::new(
+                    self.width * zoom - i as f32 * 0.5,
+                    self.color_with_alpha((3 - i) as f32 / 3.0),
+                )
+            })
+            .collect()
+    }
+}
+
 impl DirectoryNode {
     fn new(path: PathBuf) -> Self {
         Self {
@@ -150,12 +729,15 @@ impl DirectoryNode {
             children: Vec::new(),
             expanded: false,
             selected: false,
+            populated: false,
+            filter_match: None,
         }
     }
 
+    // Only the root level is read up front, deeper levels load on first expand
     fn build_tree(root_path: &Path) -> Self {
         let mut root_node = DirectoryNode::new(root_path.to_path_buf());
-        Self::populate_node(&mut root_node);
+        root_node.populate();
         root_node
     }
 
@@ -183,24 +765,80 @@ impl DirectoryNode {
         None
     }
 
-    fn populate_node(node: &mut DirectoryNode) {
-        if let Ok(entries) = std::fs::read_dir(&node.path) {
-            for entry in entries.filter_map(|e| e.ok()) {
-                let path = entry.path();
-                if path.is_dir() {
-                    let mut child_node = DirectoryNode::new(path);
-                    Self::populate_node(&mut child_node);
-                    node.children.push(child_node);
-                }
-            }
+    // A node stays visible when it or any shown descendant matches the (lowercase)
+    // query. Folders not loaded yet are read as the query looks into them, except
+    // linked ones, so a link loop can't make the search endless. The answer is kept
+    // until the query or hidden-files setting changes, so the tree isn't walked
+    // every frame.
+    fn matches_filter(&mut self, query_lower: &str, show_hidden: bool) -> bool {
+        if query_lower.is_empty() {
+            return true;
+        }
+        if let Some((query, hidden, matched)) = &self.filter_match
+            && query == query_lower
+            && *hidden == show_hidden
+        {
+            return *matched;
+        }
+        let matched = self.find_filter_match(query_lower, show_hidden);
+        self.filter_match = Some((query_lower.to_string(), show_hidden, matched));
+        matched
+    }
+
+    fn find_filter_match(&mut self, query_lower: &str, show_hidden: bool) -> bool {
+        let name_matches = self
+            .path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().to_lowercase().contains(query_lower));
+        if name_matches {
+            return true;
+        }
+        if !self.populated && self.path.is_symlink() {
+            return false;
+        }
+        self.populate();
+        self.shown_children(show_hidden)
+            .any(|child| child.matches_filter(query_lower, show_hidden))
+    }
+
+    // Forget cached filter answers, e.g. once a folder expanded and loaded new children
+    fn clear_filter_matches(&mut self) {
+        self.filter_match = None;
+        for child in &mut self.children {
+            child.clear_filter_matches();
         }
     }
+
+    // The subdirectories the tree draws, without dot-folders unless hidden files are shown
+    fn shown_children(&mut self, show_hidden: bool) -> impl Iterator<Item = &mut DirectoryNode> {
+        self.children
+            .iter_mut()
+            .filter(move |child| show_hidden || !is_hidden_path(&child.path))
+    }
+
+    // Reads the immediate subdirectories once, later calls reuse the cached children
+    fn populate(&mut self) {
+        if self.populated {
+            return;
+        }
+        if let Ok(entries) = std::fs::read_dir(&self.path) {
+            let mut child_paths: Vec<PathBuf> = entries
+                .filter_map(|e| e.ok())
+                .map(|entry| entry.path())
+                .filter(|path| path.is_dir())
+                .collect();
+            child_paths.sort();
+            self.children = child_paths.into_iter().map(DirectoryNode::new).collect();
+        }
+        self.populated = true;
+    }
 }
 
 pub struct FileGraphApp<'a> {
     scan_dir: PathBuf,
     show_directory_panel: bool,
     directory_tree: DirectoryNode,
+    directory_filter: String,
     selected_directory: Option<PathBuf>,
     scanner: Arc<Mutex<FileScanner>>,
     file_graph: FileGraph,
@@ -211,48 +849,168 @@ pub struct FileGraphApp<'a> {
     search_text: String,
     filter_tags: String,
     tag_graph: TagGraph,
+    // Only kept up to date while Unified mode is shown
+    unified_graph: FileGraph,
     current_graph_mode: GraphMode,
     current_scan_dir: PathBuf,
     show_full_paths: bool,
+    // Labels longer than this are shortened; the tooltip keeps the full name
+    max_label_chars: usize,
+    // Highlight files sharing a tag with the selected file
+    highlight_shared_tags: bool,
+    safe_mode: bool,
+    // Globs (relative to the scan root) skipped by the next scans
+    exclude_globs: Vec<String>,
+    exclude_glob_input: String,
+    shared_tag_edges: bool,
+    // Selected node and the nodes sharing a tag with it, recomputed on selection
+    shared_tag_nodes: Option<(NodeIndex, HashSet<NodeIndex>)>,
+    label_truncation: LabelTruncation,
+    show_tag_badges: bool,
+    focus_on_hover: bool,
+    color_by_folder: bool,
+    size_by_file_size: bool,
+    // Scale nodes by their number of links in the active graph
+    size_by_degree: bool,
+    orphans_only: bool,
+    show_density_heatmap: bool,
+    bundle_edges: bool,
+    curved_edges: bool,
+    // Click a node to pick it up, click again to drop and pin it
+    placement_mode: bool,
+    // Node following the pointer, with where it was picked up for Esc
+    held_node: Option<(NodeIndex, egui::Vec2)>,
+    graph_quality: GraphQuality,
+    show_edge_flow: bool,
+    follow_selected: bool,
+    // The node being followed; selecting another one stops following
+    followed_node: Option<NodeIndex>,
+    bundling_strength: f32,
+    orphan_count: usize,
+    hovered_node: Option<NodeIndex>,
+    // Nodes and edges drawn last frame, for the status bar
+    visible_counts: (usize, usize),
+    hover_started: Option<(NodeIndex, f64)>,
+    hover_previews: HashMap<PathBuf, (SystemTime, HoverPreview)>,
+    hover_preview_sender: mpsc::Sender<(PathBuf, SystemTime, HoverPreview)>,
+    hover_preview_receiver: mpsc::Receiver<(PathBuf, SystemTime, HoverPreview)>,
     physics_simulator: PhysicsSimulator,
+    physics_clock: FixedTimestep,
     // show_physics_menu: bool,
     show_physics_window: bool,
+    show_display_window: bool,
+    show_settings_window: bool,
+    settings_tab: SettingsTab,
+    saved_chrome: Option<ChromeVisibility>,
+    confirm_reset_settings: bool,
+    reset_clears_search_history: bool,
+    edge_style: EdgeStyle,
+    snapshot_scale: f32,
+    // The graph as last drawn, for copying it as an image
+    last_picture: GraphPicture,
+    label_scale: f32,
+    scale_labels_with_zoom: bool,
+    content_font_size: f32,
+    content_line_spacing: f32,
+    content_find_query: String,
+    content_find_index: usize,
+    content_find_scroll: bool,
     is_scanning: bool,
+    has_scanned: bool,
     scan_error: Option<String>,
     selected_node: Option<petgraph::graph::NodeIndex>,
+    pending_selection: Option<GraphNode>,
+    previewed_file: Option<PathBuf>,
     selected_file_content: Option<String>,
     selected_image: Option<egui::TextureHandle>,
+    selected_animation: Option<GifAnimation>,
+    image_view_states: HashMap<PathBuf, ImageViewState>,
     show_content_panel: bool,
     tag_filter_input: String,
+    tag_completion_index: usize,
+    min_connections: usize,
+    isolated_node: Option<NodeIndex>,
+    show_tag_cooccurrence: bool,
+    show_untagged_files: bool,
+    duplicate_groups: Vec<Vec<PathBuf>>,
+    duplicate_receiver: Option<mpsc::Receiver<Vec<Vec<PathBuf>>>>,
+    show_duplicates_window: bool,
+    show_cycles_window: bool,
+    scan_snapshots: ScanSnapshots,
+    show_scan_stats_window: bool,
+    // The current scan's stats and its diff against the saved snapshot, until the next scan
+    scan_stats: Option<(ScanSnapshot, Option<SnapshotDiff>)>,
+    // Nodes of the cycle picked in the cycles window, drawn highlighted
+    highlighted_cycle: Option<Vec<NodeIndex>>,
+    show_scan_errors_window: bool,
+    keybindings: KeyBindings,
+    show_keybindings_window: bool,
+    command_palette: CommandPalette,
+    // Only files modified inside this window are drawn
+    recent_window: RecentWindow,
+    // Graph exports carry each node's layout position
+    export_positions: bool,
+    // Set while showing an imported graph; the scanner then leaves the links graph alone
+    imported_graph: Option<PathBuf>,
+    freeze_when_settled: bool,
+    // A scan started and the layout hasn't settled since
+    awaiting_settle: bool,
+    watch_for_changes: bool,
+    // Watched directory and its change events
+    watcher: Option<PollWatcher>,
+    rescan_debouncer: RescanDebouncer,
+    // Action waiting for its new key in the keybindings window
+    rebinding_action: Option<Action>,
+    max_graph_nodes: usize,
+    cooccurrence_threshold: usize,
+    isolate_depth: usize,
     initial_node_layout: HashMap<petgraph::graph::NodeIndex, egui::Vec2>,
     graph_center_offset: egui::Vec2,
     graph_zoom_factor: f32,
     dragged_node: Option<petgraph::graph::NodeIndex>,
+    // Screen pixels the pointer must travel before a press on a node becomes a drag
+    node_drag_threshold: f32,
     last_drag_pos: Option<egui::Pos2>,
     current_directory_label: String,
     show_images: bool,
+    images_affect_layout: bool,
     show_hidden_files: bool,
+    parse_code_imports: bool,
+    // None scans every subfolder
+    max_scan_depth: Option<usize>,
+    // None reads files of any size
+    max_scan_file_size_mb: Option<u64>,
+    // Links graph draws one node per folder until a folder node is clicked open
+    collapse_directories: bool,
+    respect_gitignore: bool,
     markdown_cache: egui_commonmark::CommonMarkCache,
     scan_progress: f32,
     scan_status: String,
     graph_rect: egui::Rect,
     graph_build_progress: f32,
     graph_build_status: String,
+    graph_build_receiver: Option<mpsc::Receiver<GraphBuildMessage>>,
+    // Set to stop the running build and drop its result
+    graph_build_cancel: Arc<AtomicBool>,
     scan_sender: Option<std::sync::mpsc::Sender<(f32, String)>>,
     scan_progress_receiver: Option<std::sync::mpsc::Receiver<(f32, String)>>,
     search_query: String,
+    search_history: Vec<String>,
     search_results: Vec<NodeIndex>,
     current_search_result: usize,
     open_menu_on_node: Option<NodeIndex>,
     right_click_menu_pos: Option<egui::Pos2>,
     menu_open: bool,
+    new_tag_input: String,
+    rename_input: String,
+    edit_history: Vec<FileEdit>,
     syntax_cache: HashMap<String, SyntaxReference>,
     markdown_syntax: Option<SyntaxReference>,
     scan_thread_handle: Option<thread::JoinHandle<()>>,
     cancel_sender: Option<std::sync::mpsc::Sender<()>>,
     state: AppState,
-    // pdfium_instance: Arc<Pdfium>,
     pdf_viewer_state: PdfViewerState,
+    pdfium_status: PdfiumStatus,
     pdf_file_data: HashMap<PathBuf, FileData<'a>>,
     show_pdf_text: bool,
     selected_text: Option<String>,
@@ -265,7 +1023,14 @@ pub struct FileData<'a> {
 }
 
 impl<'a> App for FileGraphApp<'a> {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        self.current_settings().save(storage);
+        self.scan_snapshots.save(storage);
+    }
+
     fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        // Keep egui's click detection in line with the node drag threshold
+        ctx.options_mut(|options| options.input_options.max_click_dist = self.node_drag_threshold);
         self.update_ui_state(ctx);
         match self.state {
             AppState::Ready => {
@@ -286,6 +1051,39 @@ impl<'a> App for FileGraphApp<'a> {
             _ => {}
         }
 
+        // Drag-and-drop: folders are scanned, a single file is previewed
+        let dropped_paths: Vec<PathBuf> = ctx.input(|i| {
+            i.raw
+                .dropped_files
+                .iter()
+                .filter_map(|file| file.path.clone())
+                .collect()
+        });
+        match DropAction::classify(&dropped_paths) {
+            DropAction::ScanDirectories(directories) => self.open_directories(directories),
+            DropAction::PreviewFile(path) => self.preview_file(path, ctx),
+            DropAction::Ignore => {}
+        }
+
+        // Previews read by hover worker threads
+        while let Ok((path, modified, preview)) = self.hover_preview_receiver.try_recv() {
+            if let Some(entry) = self.hover_previews.get_mut(&path)
+                && entry.0 == modified
+            {
+                entry.1 = preview;
+            }
+        }
+
+        // Duplicate search results from the hashing thread
+        if let Some(receiver) = &self.duplicate_receiver {
+            if let Ok(groups) = receiver.try_recv() {
+                self.duplicate_groups = groups;
+                self.duplicate_receiver = None;
+            } else {
+                ctx.request_repaint();
+            }
+        }
+
         // Check for scan progress updates
         if let Some(receiver) = self.scan_progress_receiver.take() {
             while let Ok((progress, status)) = receiver.try_recv() {
@@ -293,6 +1091,9 @@ impl<'a> App for FileGraphApp<'a> {
                 self.scan_status = status;
                 if progress >= 1.0 {
                     self.is_scanning = false;
+                    self.shared_tag_nodes = None;
+                    self.scan_stats = None;
+                    self.has_scanned = true;
                 }
                 ctx.request_repaint();
             }
@@ -301,248 +1102,494 @@ impl<'a> App for FileGraphApp<'a> {
             }
         }
 
-        // Update graph building progress
-        {
+        self.poll_watcher(ctx);
+
+        // Keep the graphs in line with the scanner, unless a full build is replacing them
+        if self.graph_build_receiver.is_none() {
             let scanner_locked = self.scanner.lock().unwrap();
 
-            self.graph_build_progress = 0.0;
-            self.graph_build_status = "Building file graph...".to_string();
-            ctx.request_repaint();
+            // Update in place so nodes keep their layout while a scan streams in files
+            if self.imported_graph.is_none() {
+                self.file_graph
+                    .update_from_scanner(&scanner_locked, self.max_graph_nodes);
+            }
 
-            // Clear old graphs before rebuilding
-            self.file_graph.graph.clear();
-            self.file_graph.node_indices.clear();
-            self.file_graph.build_from_scanner(&scanner_locked);
+            self.tag_graph.update_from_tags(
+                &scanner_locked,
+                self.show_untagged_files,
+                self.max_graph_nodes,
+            );
+            if self.current_graph_mode == GraphMode::Unified {
+                self.unified_graph
+                    .update_unified(&scanner_locked, self.max_graph_nodes);
+            }
+        }
 
-            self.graph_build_progress = 0.5;
-            self.graph_build_status = "Building tag graph...".to_string();
-            ctx.request_repaint();
+        // Reselect the node remembered from the last session once a scan has finished
+        if self.has_scanned
+            && !self.is_scanning
+            && let Some(saved) = self.pending_selection.take()
+        {
+            self.restore_selection(&saved, ctx);
+        }
 
-            // Clear old tag graph before rebuilding
-            self.tag_graph.graph.clear();
-            self.tag_graph.file_node_indices.clear();
-            self.tag_graph.tag_node_indices.clear();
-            self.tag_graph.image_node_indices.clear();
-            self.tag_graph.build_from_tags(&scanner_locked);
+        // Ctrl+Z undoes the last file edit; text fields keep their own undo
+        let undo_shortcut = self.keybindings.shortcut(Action::Undo);
+        if self.rebinding_action.is_none()
+            && !self.safe_mode
+            && !ctx.wants_keyboard_input()
+            && ctx.input_mut(|i| i.consume_shortcut(&undo_shortcut))
+        {
+            self.undo_last_edit(ctx);
+        }
 
-            self.graph_build_progress = 1.0;
-            self.graph_build_status = "Graph ready".to_string();
+        // F11 toggles presentation mode: only the graph, fullscreen
+        if self.action_pressed(ctx, Action::PresentationMode) {
+            self.toggle_presentation_mode(ctx);
         }
 
-        egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
-            ui.horizontal(|ui| {
-                ui.label("Scanning directory:");
-                ui.monospace(&self.current_directory_label);
-            });
-            ui.separator();
+        if self.action_pressed(ctx, Action::CommandPalette) {
+            self.command_palette.toggle();
+        }
+        if let Some(command) = self.command_palette.show(ctx, &self.keybindings) {
+            self.run_command(command, ctx);
+        }
 
-            ui.horizontal(|ui| {
-                // Directory panel toggle button
-                if ui.button("📁").clicked() {
-                    self.show_directory_panel = !self.show_directory_panel;
-                }
-                // Content panel toggle button
-                if ui.button("📄").clicked() {
-                    self.show_content_panel = !self.show_content_panel;
-                }
-                // Physics menu toggle button
-                // if ui.button("⚙️").clicked() {
-                //     self.show_physics_menu = !self.show_physics_menu;
-                // }
-                if ui.button("⚙️ Physics").clicked() {
-                    self.show_physics_window = !self.show_physics_window;
-                }
-                // Exit button
-                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                    if ui
-                        .add(egui::Button::new("✕ Exit").fill(Color32::from_rgb(200, 80, 80)))
-                        .clicked()
-                    {
-                        ctx.send_viewport_cmd(egui::ViewportCommand::Close);
-                    }
+        egui::TopBottomPanel::top("top_panel").show_animated(
+            ctx,
+            self.saved_chrome.is_none(),
+            |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Scanning directory:");
+                    ui.monospace(&self.current_directory_label);
                 });
-            });
-
-            // Main controls row
-            ui.horizontal(|ui| {
-                ui.label("Graph Mode:");
-                if ui
-                    .radio_value(&mut self.current_graph_mode, GraphMode::Links, "Links")
-                    .clicked()
-                {
-                    self.selected_node = None;
-                    self.physics_simulator
-                        .reset_positions(&self.initial_node_layout);
-                }
-                if ui
-                    .radio_value(&mut self.current_graph_mode, GraphMode::Tags, "Tags")
-                    .clicked()
-                {
-                    self.selected_node = None;
-                    self.physics_simulator
-                        .reset_positions(&self.initial_node_layout);
-                }
-
-                ui.checkbox(&mut self.show_full_paths, "Show Full Paths");
-                ui.checkbox(&mut self.show_images, "Show Images");
+                ui.separator();
 
-                if ui
-                    .checkbox(&mut self.show_hidden_files, "Show Hidden Files")
-                    .changed()
-                {
-                    if let Ok(mut scanner_guard) = self.scanner.lock() {
-                        scanner_guard.set_show_hidden(self.show_hidden_files);
-                    } else {
-                        eprintln!("Failed to lock scanner mutex when setting show_hidden.");
-                        return;
+                ui.horizontal(|ui| {
+                    // Directory panel toggle button
+                    if ui.button("📁").clicked() {
+                        self.show_directory_panel = !self.show_directory_panel;
                     }
-
-                    // Trigger a rescan of the currently selected directory
-                    if !self.is_scanning {
-                        let scan_dir = self
-                            .selected_directory
-                            .clone()
-                            .unwrap_or_else(|| self.scan_dir.clone());
-                        self.current_scan_dir = scan_dir.clone();
-                        self.trigger_scan(scan_dir, ctx);
+                    // Content panel toggle button
+                    if ui.button("📄").clicked() {
+                        self.show_content_panel = !self.show_content_panel;
                     }
-                }
-
-                ui.separator();
-
-                ui.label("Filter Tags:");
-                ui.text_edit_singleline(&mut self.tag_filter_input);
-
-                if ui.button("Rescan Directory").clicked() && !self.is_scanning {
-                    self.scan_error = None;
-                    self.is_scanning = true;
-                    self.scan_progress = 0.0;
-                    self.scan_status = "Starting scan...".to_string();
-
-                    let scan_dir = self
-                        .selected_directory
-                        .clone()
-                        .unwrap_or_else(|| self.scan_dir.clone());
-                    self.current_scan_dir = scan_dir.clone();
-
-                    let scanner_arc_clone = self.scanner.clone();
-                    let (progress_sender, progress_receiver) = std::sync::mpsc::channel();
-
-                    thread::spawn(move || {
-                        let mut scanner = scanner_arc_clone.lock().unwrap();
-                        if let Err(e) =
-                            scanner.scan_directory_with_progress(&scan_dir, progress_sender)
+                    if ui.button("⚙ Settings").clicked() {
+                        self.show_settings_window = !self.show_settings_window;
+                    }
+                    if ui.button("⚙️ Physics").clicked() {
+                        self.show_physics_window = !self.show_physics_window;
+                    }
+                    if ui.button("🎨 Display").clicked() {
+                        self.show_display_window = !self.show_display_window;
+                    }
+                    ui.checkbox(&mut self.safe_mode, "🔒 Safe Mode")
+                        .on_hover_text("Read-only: no file edits or opening files in other apps");
+                    if ui.button("⌨ Keys").clicked() {
+                        self.show_keybindings_window = !self.show_keybindings_window;
+                    }
+                    if ui.button("🔁 Duplicates").clicked() {
+                        self.find_duplicate_files();
+                    }
+                    if ui.button("⟲ Cycles").clicked() {
+                        self.show_cycles_window = !self.show_cycles_window;
+                    }
+                    if ui.button("📊 Stats").clicked() {
+                        self.show_scan_stats_window = !self.show_scan_stats_window;
+                    }
+                    if ui.button("📋 Copy Graph").clicked() {
+                        self.copy_picture(
+                            &self.last_picture.clone(),
+                            self.graph_rect,
+                            ctx.pixels_per_point() * self.snapshot_scale,
+                        );
+                    }
+                    if ui
+                        .button("🔗 Copy View Link")
+                        .on_hover_text("Reopen this view with: NexusView --view <link>")
+                        .clicked()
+                    {
+                        ctx.copy_text(self.view_state().encode());
+                    }
+                    if ui
+                        .button("🎲 Random")
+                        .on_hover_text(format!(
+                            "Open a random note ({})",
+                            ctx.format_shortcut(&self.keybindings.shortcut(Action::RandomNode))
+                        ))
+                        .clicked()
+                    {
+                        self.jump_to_random_node(ctx);
+                    }
+                    if ui
+                        .button("📂 Import Graph")
+                        .on_hover_text("Open a graph exported as JSON, without scanning")
+                        .clicked()
+                    {
+                        self.import_graph();
+                    }
+                    ui.menu_button("💾 Export Graph", |ui| {
+                        ui.checkbox(&mut self.export_positions, "Include Positions");
+                        if ui.button("JSON").clicked() {
+                            self.export_graph(false);
+                            ui.close_menu();
+                        }
+                        if ui.button("GraphML").clicked() {
+                            self.export_graph(true);
+                            ui.close_menu();
+                        }
+                        ui.separator();
+                        ui.label("Tag Co-occurrence");
+                        if ui.button("CSV").clicked() {
+                            self.export_cooccurrence(true);
+                            ui.close_menu();
+                        }
+                        if ui.button("JSON").clicked() {
+                            self.export_cooccurrence(false);
+                            ui.close_menu();
+                        }
+                    });
+                    // Exit button
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if ui
+                            .add(egui::Button::new("✕ Exit").fill(Color32::from_rgb(200, 80, 80)))
+                            .clicked()
                         {
-                            eprintln!("Error during scan: {}", e);
+                            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
                         }
                     });
+                });
 
-                    self.scan_progress_receiver = Some(progress_receiver);
-                }
+                // Main controls row
+                ui.horizontal(|ui| {
+                    ui.label("Graph Mode:");
+                    if ui
+                        .radio(self.current_graph_mode == GraphMode::Links, "Links")
+                        .clicked()
+                    {
+                        self.switch_graph_mode(GraphMode::Links);
+                    }
+                    if ui
+                        .radio(self.current_graph_mode == GraphMode::Tags, "Tags")
+                        .clicked()
+                    {
+                        self.switch_graph_mode(GraphMode::Tags);
+                    }
+                    if ui
+                        .radio(self.current_graph_mode == GraphMode::Unified, "Unified")
+                        .on_hover_text("Links and tags together")
+                        .clicked()
+                    {
+                        self.switch_graph_mode(GraphMode::Unified);
+                    }
 
-                if self.is_scanning {
-                    ui.horizontal(|ui| {
-                        ui.spinner();
-                        ui.add(egui::ProgressBar::new(self.scan_progress).show_percentage());
-                        ui.label(&self.scan_status);
-                    });
-                }
+                    ui.checkbox(&mut self.show_full_paths, "Show Full Paths")
+                        .on_hover_text(format!(
+                            "Toggle with {}",
+                            ctx.format_shortcut(
+                                &self.keybindings.shortcut(Action::ToggleFullPaths)
+                            )
+                        ));
+                    ui.checkbox(&mut self.show_images, "Show Images");
+                    ui.add_enabled(
+                        self.show_images,
+                        egui::Checkbox::new(&mut self.images_affect_layout, "Images Affect Layout"),
+                    );
+                    ui.checkbox(&mut self.show_tag_badges, "Tag Badges");
+                    ui.checkbox(&mut self.focus_on_hover, "Focus on Hover");
+                    if ui
+                        .add_enabled(
+                            self.selected_node.is_some(),
+                            egui::Checkbox::new(&mut self.follow_selected, "Follow Selected"),
+                        )
+                        .on_hover_text("Keep the selected node centred while the layout moves")
+                        .changed()
+                    {
+                        self.followed_node = self.selected_node;
+                    }
+                    if ui
+                        .checkbox(&mut self.placement_mode, "Place Nodes")
+                        .on_hover_text(
+                            "Click a node to pick it up and click again to pin it; Esc cancels",
+                        )
+                        .changed()
+                        && !self.placement_mode
+                    {
+                        self.cancel_held_node();
+                    }
+                    if !self.physics_simulator.pinned_nodes.is_empty()
+                        && ui.button("Unpin All").clicked()
+                    {
+                        self.physics_simulator.pinned_nodes.clear();
+                    }
+                    ui.checkbox(&mut self.color_by_folder, "Color by Folder");
+                    ui.checkbox(&mut self.size_by_file_size, "Size by File Size");
+                    ui.checkbox(&mut self.size_by_degree, "Size by Connections");
+                    ui.checkbox(&mut self.show_density_heatmap, "Density Heatmap");
+                    ui.label("Modified:");
+                    for window in [
+                        RecentWindow::All,
+                        RecentWindow::Today,
+                        RecentWindow::Days(7),
+                        RecentWindow::Days(30),
+                    ] {
+                        ui.radio_value(&mut self.recent_window, window, window.label());
+                    }
+                    if self.current_graph_mode == GraphMode::Links {
+                        if ui
+                            .checkbox(&mut self.collapse_directories, "Collapse Folders")
+                            .on_hover_text("One node per folder; click a folder node to expand it")
+                            .changed()
+                        {
+                            self.file_graph.directory_grouping =
+                                self.collapse_directories.then(HashSet::new);
+                        }
+                        if self
+                            .file_graph
+                            .directory_grouping
+                            .as_ref()
+                            .is_some_and(|expanded| !expanded.is_empty())
+                            && ui.button("Collapse All").clicked()
+                        {
+                            self.file_graph.directory_grouping = Some(HashSet::new());
+                        }
+                        ui.checkbox(&mut self.orphans_only, "Orphans Only");
+                        if self.orphans_only {
+                            ui.label(format!("{} orphans", self.orphan_count));
+                        }
+                    }
+                    if self.current_graph_mode == GraphMode::Tags {
+                        ui.menu_button("Export Tags", |ui| {
+                            if ui.button("File → Tags (JSON)").clicked() {
+                                self.export_tags(false);
+                                ui.close_menu();
+                            }
+                            if ui.button("Tag → Files (JSON)").clicked() {
+                                self.export_tags(true);
+                                ui.close_menu();
+                            }
+                        });
+                        ui.checkbox(&mut self.show_untagged_files, "Show Untagged");
+                        ui.checkbox(&mut self.show_tag_cooccurrence, "Tag Co-occurrence");
+                        if self.show_tag_cooccurrence {
+                            ui.add(
+                                egui::Slider::new(&mut self.cooccurrence_threshold, 1..=10)
+                                    .text("Min shared files"),
+                            );
+                        }
+                    }
 
-                if let Some(ref err) = self.scan_error {
-                    ui.colored_label(egui::Color32::RED, format!("Error: {}", err));
-                }
-            });
+                    if ui
+                        .checkbox(&mut self.show_hidden_files, "Show Hidden Files")
+                        .changed()
+                    {
+                        self.apply_scan_options(ctx);
+                    }
+                    if ui
+                        .checkbox(&mut self.respect_gitignore, "Respect .gitignore")
+                        .on_hover_text(
+                            "Skip files ignored by .gitignore files in the scanned folders",
+                        )
+                        .changed()
+                    {
+                        self.apply_scan_options(ctx);
+                    }
+                    if self.max_depth_field(ui) {
+                        self.apply_scan_options(ctx);
+                    }
 
-            // Physics controls section
-            // if self.show_physics_menu {
-            //     ui.separator();
-            //     ui.horizontal(|ui| {
-            //         ui.group(|ui| {
-            //             ui.label("Physics Controls:");
-            //             ui.horizontal(|ui| {
-            //                 ui.vertical(|ui| {
-            //                     ui.add(
-            //                         egui::Slider::new(
-            //                             &mut self.physics_simulator.spring_constant,
-            //                             0.001..=0.5,
-            //                         )
-            //                         .text("Spring K"),
-            //                     );
-            //                     ui.add(
-            //                         egui::Slider::new(
-            //                             &mut self.physics_simulator.damping,
-            //                             0.0..=0.9,
-            //                         )
-            //                         .text("Damping"),
-            //                     );
-            //                     ui.add(
-            //                         egui::Slider::new(
-            //                             &mut self.physics_simulator.time_step,
-            //                             0.1..=1.0,
-            //                         )
-            //                         .text("Time Step"),
-            //                     );
-            //                 });
-            //                 ui.vertical(|ui| {
-            //                     ui.add(
-            //                         egui::Slider::new(
-            //                             &mut self.physics_simulator.repulsion_constant,
-            //                             100.0..=50000.0,
-            //                         )
-            //                         .text("Repulsion K"),
-            //                     );
-            //                     ui.add(
-            //                         egui::Slider::new(
-            //                             &mut self.physics_simulator.ideal_edge_length,
-            //                             10.0..=300.0,
-            //                         )
-            //                         .text("Ideal Length"),
-            //                     );
-            //                     ui.add(
-            //                         egui::Slider::new(
-            //                             &mut self.physics_simulator.friction,
-            //                             0.0..=0.9,
-            //                         )
-            //                         .text("Friction"),
-            //                     );
-            //                 });
-            //             });
-            //
-            //             if ui.button("Reset Node Positions").clicked() {
-            //                 self.physics_simulator
-            //                     .reset_positions(&self.initial_node_layout);
-            //             }
-            //
-            //             if ui.button("Center Graph").clicked() {
-            //                 self.center_graph();
-            //             }
-            //         });
-            //     });
-            // }
-
-            // Graph Search section
-            ui.separator();
-            ui.horizontal(|ui| {
-                ui.label("Search:");
-                if ui.text_edit_singleline(&mut self.search_query).changed() {
-                    self.perform_search();
-                }
+                    ui.separator();
 
-                if !self.search_results.is_empty() {
-                    ui.label(format!(
-                        "{} of {}",
-                        self.current_search_result + 1,
-                        self.search_results.len()
-                    ));
-                    if ui.button("◀").clicked() {
-                        self.focus_prev_search_result();
+                    ui.label("Filter Tags:");
+                    self.tag_filter_field(ui);
+
+                    ui.add(
+                        egui::Slider::new(&mut self.min_connections, 0..=10)
+                            .text("Min connections"),
+                    );
+
+                    if self.isolated_node.is_some() {
+                        ui.add(
+                            egui::Slider::new(&mut self.isolate_depth, 1..=5).text("Isolate depth"),
+                        );
+                        if ui.button("Show All").clicked() {
+                            self.isolated_node = None;
+                        }
                     }
-                    if ui.button("▶").clicked() {
-                        self.focus_next_search_result();
+
+                    if ui.button("Rescan Directory").clicked() && !self.is_scanning {
+                        self.rescan_in_place();
                     }
-                }
-            });
-        });
+
+                    if self.rescan_debouncer.is_pending() {
+                        ui.weak("Changes detected, updating…");
+                    }
+
+                    if self.is_scanning {
+                        ui.horizontal(|ui| {
+                            ui.spinner();
+                            ui.add(egui::ProgressBar::new(self.scan_progress).show_percentage());
+                            ui.label(&self.scan_status);
+                        });
+                    }
+
+                    if let Some(ref err) = self.scan_error {
+                        ui.colored_label(egui::Color32::RED, format!("Error: {}", err));
+                    }
+
+                    // Files that failed to read don't stop a scan; list them once it is done
+                    let unreadable = if self.is_scanning {
+                        0
+                    } else {
+                        self.scanner
+                            .try_lock()
+                            .map_or(0, |scanner| scanner.scan_errors.len())
+                    };
+                    if unreadable > 0
+                        && ui
+                            .link(
+                                egui::RichText::new(format!("⚠ {} could not be read", unreadable))
+                                    .color(Color32::YELLOW),
+                            )
+                            .clicked()
+                    {
+                        self.show_scan_errors_window = true;
+                    }
+                });
+
+                // Physics controls section
+                // if self.show_physics_menu {
+                //     ui.separator();
+                //     ui.horizontal(|ui| {
+                //         ui.group(|ui| {
+                //             ui.label("Physics Controls:");
+                //             ui.horizontal(|ui| {
+                //                 ui.vertical(|ui| {
+                //                     ui.add(
+                //                         egui::Slider::new(
+                //                             &mut self.physics_simulator.spring_constant,
+                //                             0.001..=0.5,
+                //                         )
+                //                         .text("Spring K"),
+                //                     );
+                //                     ui.add(
+                //                         egui::Slider::new(
+                //                             &mut self.physics_simulator.damping,
+                //                             0.0..=0.9,
+                //                         )
+                //                         .text("Damping"),
+                //                     );
+                //                     ui.add(
+                //                         egui::Slider::new(
+                //                             &mut self.physics_simulator.time_step,
+                //                             0.1..=1.0,
+                //                         )
+                //                         .text("Time Step"),
+                //                     );
+                //                 });
+                //                 ui.vertical(|ui| {
+                //                     ui.add(
+                //                         egui::Slider::new(
+                //                             &mut self.physics_simulator.repulsion_constant,
+                //                             100.0..=50000.0,
+                //                         )
+                //                         .text("Repulsion K"),
+                //                     );
+                //                     ui.add(
+                //                         egui::Slider::new(
+                //                             &mut self.physics_simulator.ideal_edge_length,
+                //                             10.0..=300.0,
+                //                         )
+                //                         .text("Ideal Length"),
+                //                     );
+                //                     ui.add(
+                //                         egui::Slider::new(
+                //                             &mut self.physics_simulator.friction,
+                //                             0.0..=0.9,
+                //                         )
+                //                         .text("Friction"),
+                //                     );
+                //                 });
+                //             });
+                //
+                //             if ui.button("Reset Node Positions").clicked() {
+                //                 self.physics_simulator
+                //                     .reset_positions(&self.initial_node_layout);
+                //             }
+                //
+                //             if ui.button("Center Graph").clicked() {
+                //                 self.center_graph();
+                //             }
+                //         });
+                //     });
+                // }
+
+                // Graph Search section
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label("Search:");
+                    let search_response = ui.text_edit_singleline(&mut self.search_query);
+                    if search_response.changed() {
+                        self.perform_search();
+                    }
+                    // Searching runs on every keystroke, so only finished queries are remembered
+                    if search_response.lost_focus() {
+                        push_history(
+                            &mut self.search_history,
+                            &self.search_query,
+                            SEARCH_HISTORY_LEN,
+                        );
+                    }
+                    let mut rerun = None;
+                    let mut clear_history = false;
+                    ui.add_enabled_ui(!self.search_history.is_empty(), |ui| {
+                        ui.menu_button("🕘", |ui| {
+                            for query in &self.search_history {
+                                if ui.button(query).clicked() {
+                                    rerun = Some(query.clone());
+                                    ui.close_menu();
+                                }
+                            }
+                            ui.separator();
+                            if ui.button("Clear History").clicked() {
+                                clear_history = true;
+                                ui.close_menu();
+                            }
+                        })
+                        .response
+                        .on_hover_text("Recent searches");
+                    });
+                    if clear_history {
+                        self.search_history.clear();
+                    }
+                    if let Some(query) = rerun {
+                        push_history(&mut self.search_history, &query, SEARCH_HISTORY_LEN);
+                        self.search_query = query;
+                        self.perform_search();
+                    }
+
+                    if !self.search_results.is_empty() {
+                        ui.label(format!(
+                            "{} of {}",
+                            self.current_search_result + 1,
+                            self.search_results.len()
+                        ));
+                        if ui.button("◀").clicked() {
+                            self.focus_prev_search_result();
+                        }
+                        if ui.button("▶").clicked() {
+                            self.focus_next_search_result();
+                        }
+                    }
+                });
+            },
+        );
+
+        egui::TopBottomPanel::bottom("status_bar").show_animated(
+            ctx,
+            self.saved_chrome.is_none(),
+            |ui| self.status_bar().show(ui),
+        );
 
         // Left directory panel
         let panel_width = 200.0;
@@ -562,12 +1609,42 @@ impl<'a> App for FileGraphApp<'a> {
                     self.scan_selected_directories(ctx);
                 }
 
+                ui.horizontal(|ui| {
+                    ui.label("🔍");
+                    ui.text_edit_singleline(&mut self.directory_filter);
+                    if !self.directory_filter.is_empty() && ui.button("✕").clicked() {
+                        self.directory_filter.clear();
+                    }
+                });
+
                 ui.separator();
 
+                let files_by_dir = self.scanned_files_by_dir();
+                let selected_file = self.selected_file_path();
+                let mut clicked_file = None;
                 egui::ScrollArea::vertical().show(ui, |ui| {
                     let tree = &mut self.directory_tree;
-                    FileGraphApp::render_directory_tree_node(ui, tree);
+                    let filter = self.directory_filter.to_lowercase();
+                    // Mirrors the scanner's show_hidden flag, kept in sync by the checkbox
+                    let show_hidden = self.show_hidden_files;
+                    let changed = FileGraphApp::render_directory_tree_node(
+                        ui,
+                        tree,
+                        &filter,
+                        show_hidden,
+                        &files_by_dir,
+                        selected_file.as_deref(),
+                        &mut clicked_file,
+                    );
+                    // An expanded folder may have loaded children the answers didn't see
+                    if changed {
+                        tree.clear_filter_matches();
+                    }
                 });
+                // Files outside the current graph (filtered or hidden) are still previewed
+                if let Some(path) = clicked_file {
+                    self.preview_file(path, ctx);
+                }
             });
 
         // Central panel
@@ -585,6 +1662,20 @@ impl<'a> App for FileGraphApp<'a> {
                     };
                     ui.set_width(available_width);
 
+                    let node_count = match self.current_graph_mode {
+                        GraphMode::Links => self.file_graph.graph.node_count(),
+                        GraphMode::Tags => self.tag_graph.graph.node_count(),
+                        GraphMode::Unified => self.unified_graph.graph.node_count(),
+                    };
+                    let central_view =
+                        CentralView::choose(self.has_scanned, self.is_scanning, node_count);
+                    if central_view != CentralView::Graph {
+                        self.graph_rect = ui.max_rect();
+                        self.visible_counts = (0, 0);
+                        self.show_empty_state(ui, ctx, central_view);
+                        return;
+                    }
+
                     let (response, painter) = ui.allocate_painter(
                         ui.available_size(),
                         egui::Sense::hover() | egui::Sense::drag() | egui::Sense::click(),
@@ -613,55 +1704,87 @@ impl<'a> App for FileGraphApp<'a> {
                         );
                     }
 
+                    // Zoom about the pointer so the point under it stays put
                     if response.hovered() {
-                        self.graph_zoom_factor *= ctx.input(|i| i.zoom_delta());
-                        self.graph_zoom_factor = self.graph_zoom_factor.clamp(0.1, 10.0);
+                        let old_zoom = self.graph_zoom_factor;
+                        self.graph_zoom_factor =
+                            (old_zoom * ctx.input(|i| i.zoom_delta())).clamp(0.1, 10.0);
+                        if self.graph_zoom_factor != old_zoom
+                            && let Some(pointer) = response.hover_pos()
+                        {
+                            self.graph_center_offset = zoom_about(
+                                self.graph_center_offset,
+                                to_screen.inverse().transform_pos(pointer),
+                                old_zoom,
+                                self.graph_zoom_factor,
+                            );
+                        }
                     }
 
                     if response.dragged_by(egui::PointerButton::Middle) {
                         self.graph_center_offset += response.drag_delta() / self.graph_zoom_factor;
                     }
 
-                    if ctx.input(|i| i.key_pressed(egui::Key::F)) {
-                        if let Some(node_idx) = self.selected_node {
-                            self.focus_on_node(node_idx);
+                    for action in [
+                        Action::FocusSelected,
+                        Action::ToggleFullPaths,
+                        Action::RandomNode,
+                        Action::ToggleIsolate,
+                        Action::NextSearchResult,
+                        Action::PrevSearchResult,
+                    ] {
+                        if self.action_pressed(ctx, action) {
+                            self.run_action(action, ctx);
                         }
                     }
 
-                    if ctx.input(|i| i.key_pressed(egui::Key::F3)) {
-                        self.focus_next_search_result();
-                    }
-
-                    if ctx.input(|i| i.key_pressed(egui::Key::F3) && i.modifiers.shift) {
-                        self.focus_prev_search_result();
-                    }
-
-                    {
-                        let scanner_locked = self.scanner.lock().unwrap();
-                        self.file_graph.build_from_scanner(&scanner_locked);
-                        self.tag_graph.build_from_tags(&scanner_locked);
-                    }
-
                     // node filtering logic:
                     let (nodes_to_draw, edges_to_draw) = {
                         let scanner_locked = self.scanner.lock().unwrap();
+                        let tag_filter = self.tag_filter_input.to_lowercase();
 
                         match self.current_graph_mode {
-                            GraphMode::Links => {
+                            GraphMode::Links | GraphMode::Unified => {
+                                let file_graph = self.links_graph();
                                 let mut nodes = Vec::new();
                                 let mut edges = Vec::new();
 
+                                // Narrow to tagged files when a tag filter is set
+                                let tag_filter_active = !tag_filter.is_empty();
+
                                 // Add all files
-                                for (path, node_idx) in &self.file_graph.node_indices {
+                                for (path, node_idx) in &file_graph.node_indices {
                                     let is_image = is_image_path(path);
+                                    if tag_filter_active
+                                        && !scanner_locked
+                                            .has_tag_matching(path, &tag_filter)
+                                    {
+                                        continue;
+                                    }
 
                                     if self.show_images || !is_image {
                                         nodes.push(*node_idx);
                                     }
                                 }
 
+                                // Add external URL nodes
+                                if !tag_filter_active {
+                                    nodes.extend(file_graph.external_node_indices.values());
+                                }
+
+                                // Unified mode: tag nodes, narrowed by the tag filter
+                                nodes.extend(
+                                    file_graph
+                                        .tag_node_indices
+                                        .iter()
+                                        .filter(|(tag_name, _)| {
+                                            tag_matches_filter(tag_name, &tag_filter)
+                                        })
+                                        .map(|(_, &node_idx)| node_idx),
+                                );
+
                                 // Add all edges between visible nodes
-                                for edge in self.file_graph.graph.edge_references() {
+                                for edge in file_graph.graph.edge_references() {
                                     if nodes.contains(&edge.source())
                                         && nodes.contains(&edge.target())
                                     {
@@ -677,8 +1800,7 @@ impl<'a> App for FileGraphApp<'a> {
                                     .tag_node_indices
                                     .iter()
                                     .filter(|(tag_name, _)| {
-                                        self.tag_filter_input.is_empty()
-                                            || tag_name.contains(&self.tag_filter_input)
+                                        tag_matches_filter(tag_name, &tag_filter)
                                     })
                                     .map(|(tag_name, &node_idx)| (tag_name.clone(), node_idx))
                                     .collect();
@@ -701,11 +1823,75 @@ impl<'a> App for FileGraphApp<'a> {
                                         edges.push((edge_ref.source(), edge_ref.target()));
                                     }
                                 }
+
+                                // Tag-to-tag edges for tags sharing enough files
+                                if self.show_tag_cooccurrence {
+                                    for (&(tag_a, tag_b), &count) in &self.tag_graph.cooccurrence {
+                                        if count >= self.cooccurrence_threshold
+                                            && nodes.contains(&tag_a)
+                                            && nodes.contains(&tag_b)
+                                        {
+                                            edges.push((tag_a, tag_b));
+                                        }
+                                    }
+                                }
                                 (nodes, edges)
                             }
                         }
                     };
 
+                    // Recent files: drop files modified before the chosen window
+                    let (nodes_to_draw, edges_to_draw) = match self
+                        .recent_window
+                        .cutoff(chrono::Local::now())
+                    {
+                        Some(cutoff) => {
+                            let graph = match self.current_graph_mode {
+                                GraphMode::Links => &self.file_graph.graph,
+                                GraphMode::Tags => &self.tag_graph.graph,
+                                GraphMode::Unified => &self.unified_graph.graph,
+                            };
+                            let scanner = self.scanner.lock().unwrap();
+                            let nodes: Vec<NodeIndex> = nodes_to_draw
+                                .into_iter()
+                                .filter(|&node_idx| match &graph[node_idx] {
+                                    GraphNode::File(path) => scanner
+                                        .file_metadata
+                                        .get(Path::new(path))
+                                        .is_some_and(|&(_, modified)| modified >= cutoff),
+                                    _ => true,
+                                })
+                                .collect();
+                            let edges = edges_to_draw
+                                .into_iter()
+                                .filter(|(source, target)| {
+                                    nodes.contains(source) && nodes.contains(target)
+                                })
+                                .collect();
+                            (nodes, edges)
+                        }
+                        None => (nodes_to_draw, edges_to_draw),
+                    };
+
+                    // Orphans only (Links mode): unlinked files, for clean-up passes
+                    let orphans_only =
+                        self.orphans_only && self.current_graph_mode == GraphMode::Links;
+                    let (nodes_to_draw, edges_to_draw) = if orphans_only {
+                        let orphans = orphan_nodes(&nodes_to_draw, &edges_to_draw);
+                        self.orphan_count = orphans.len();
+                        (orphans, Vec::new())
+                    } else {
+                        filter_by_min_degree(&nodes_to_draw, &edges_to_draw, self.min_connections)
+                    };
+
+                    // Isolate: keep only the chosen node's neighbourhood until "Show All"
+                    let (nodes_to_draw, edges_to_draw) = isolate(
+                        nodes_to_draw,
+                        edges_to_draw,
+                        self.isolated_node,
+                        self.isolate_depth,
+                    );
+
                     // Clear any old nodes from physics simulator that aren't in current graph
                     self.physics_simulator
                         .node_positions
@@ -742,21 +1928,159 @@ impl<'a> App for FileGraphApp<'a> {
                     self.initial_node_layout
                         .retain(|node_idx, _| nodes_to_draw.contains(node_idx));
 
-                    if self.dragged_node.is_none() {
-                        self.physics_simulator.update(&edges_to_draw);
+                    // Images stay visible but can be kept from pushing the note layout around
+                    self.physics_simulator.passive_nodes = if self.images_affect_layout {
+                        HashSet::new()
+                    } else {
+                        match self.current_graph_mode {
+                            GraphMode::Links | GraphMode::Unified => self
+                                .links_graph()
+                                .node_indices
+                                .iter()
+                                .filter(|(path, _)| is_image_path(path))
+                                .map(|(_, &node_idx)| node_idx)
+                                .collect(),
+                            GraphMode::Tags => self
+                                .tag_graph
+                                .image_node_indices
+                                .values()
+                                .copied()
+                                .collect(),
+                        }
+                    };
+
+                    // Step at a fixed real-time rate, independent of the display refresh rate.
+                    // A frozen layout skips stepping; dragged nodes are still placed directly.
+                    let physics_steps = self.physics_clock.steps(ctx.input(|i| i.stable_dt));
+                    let physics_steps = if self.physics_simulator.frozen {
+                        0
                     } else {
-                        let original_time_step = self.physics_simulator.time_step;
-                        self.physics_simulator.time_step = original_time_step * 0.4;
-                        self.physics_simulator.update(&edges_to_draw);
-                        self.physics_simulator.time_step = original_time_step;
+                        physics_steps
+                    };
+                    for _ in 0..physics_steps {
+                        if self.dragged_node.is_none() {
+                            self.physics_simulator.update(&edges_to_draw);
+                        } else {
+                            let original_time_step = self.physics_simulator.time_step;
+                            self.physics_simulator.time_step = original_time_step * 0.4;
+                            self.physics_simulator.update(&edges_to_draw);
+                            self.physics_simulator.time_step = original_time_step;
+                        }
+                    }
+
+                    if self.follow_selected && self.selected_node != self.followed_node {
+                        self.follow_selected = false;
+                    }
+                    // Panning by hand wins over following for as long as the drag lasts
+                    if self.follow_selected
+                        && !response.dragged_by(egui::PointerButton::Middle)
+                        && let Some(node_idx) = self.followed_node
+                        && let Some(&node_pos) = self.physics_simulator.get_node_position(node_idx)
+                    {
+                        self.graph_center_offset = follow_offset(node_pos, self.graph_zoom_factor);
+                    }
+
+                    // A held node follows the pointer until the next click drops it
+                    if self.placement_mode && ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+                        if self.held_node.is_some() {
+                            self.cancel_held_node();
+                        } else {
+                            self.placement_mode = false;
+                        }
+                    }
+                    if self.held_node.is_some() {
+                        let pointer = response.hover_pos().map(|pointer| {
+                            screen_to_graph(
+                                to_screen.inverse().transform_pos(pointer),
+                                self.graph_center_offset,
+                                self.graph_zoom_factor,
+                            )
+                        });
+                        self.carry_held_node(
+                            pointer,
+                            response.clicked_by(egui::PointerButton::Primary),
+                        );
                     }
 
+                    self.freeze_if_settled();
+
                     // Animation effects
                     let time = ctx.input(|i| i.time) as f32;
                     let global_pulse = (time * 2.0).sin() * 0.02 + 1.0;
 
+                    let mut picture = GraphPicture {
+                        background: ui.visuals().panel_fill,
+                        ..GraphPicture::default()
+                    };
+                    self.visible_counts = (nodes_to_draw.len(), edges_to_draw.len());
+
+                    let visible_adjacency = AdjacencyIndex::from_edges(&edges_to_draw);
+                    let hovered_neighborhood = self
+                        .hovered_node
+                        .filter(|node_idx| nodes_to_draw.contains(node_idx))
+                        .map(|node_idx| neighborhood(node_idx, &visible_adjacency));
+
+                    // Focus mode: keep the hovered node and its neighbours bright
+                    let focus = if self.focus_on_hover {
+                        hovered_neighborhood.clone()
+                    } else {
+                        None
+                    };
+
+                    // Density heatmap behind everything: warmer where nodes and edges cluster
+                    if self.show_density_heatmap {
+                        let to_screen_pos = |pos: egui::Vec2| {
+                            to_screen.transform_pos(pos2(
+                                pos.x * self.graph_zoom_factor + self.graph_center_offset.x,
+                                pos.y * self.graph_zoom_factor + self.graph_center_offset.y,
+                            ))
+                        };
+                        let positions = &self.physics_simulator.node_positions;
+                        let node_points = nodes_to_draw
+                            .iter()
+                            .filter_map(|node_idx| positions.get(node_idx).copied());
+                        let edge_midpoints = edges_to_draw.iter().filter_map(|(start, end)| {
+                            Some((*positions.get(start)? + *positions.get(end)?) * 0.5)
+                        });
+                        let points: Vec<egui::Pos2> =
+                            node_points.chain(edge_midpoints).map(to_screen_pos).collect();
+                        let cols = (graph_rect.width() / HEATMAP_CELL_SIZE).ceil() as usize;
+                        let rows = (graph_rect.height() / HEATMAP_CELL_SIZE).ceil() as usize;
+                        let counts = density_grid(&points, graph_rect, cols, rows);
+                        let grid = smooth_grid(&smooth_grid(&counts, cols, rows), cols, rows);
+                        let max_density = grid.iter().copied().fold(0.0, f32::max);
+                        if max_density > 0.0 {
+                            let cell = egui::vec2(
+                                graph_rect.width() / cols as f32,
+                                graph_rect.height() / rows as f32,
+                            );
+                            for (i, &density) in grid.iter().enumerate() {
+                                let t = density / max_density;
+                                if t < 0.02 {
+                                    continue;
+                                }
+                                let min = graph_rect.min
+                                    + vec2((i % cols) as f32 * cell.x, (i / cols) as f32 * cell.y);
+                                painter.rect_filled(
+                                    egui::Rect::from_min_size(min, cell),
+                                    0.0,
+                                    heatmap_color(t),
+                                );
+                            }
+                        }
+                    }
+
+                    // Bundling follows the current layout, so it is recomputed every frame
+                    let bundle_centroids = if self.bundle_edges {
+                        neighbor_centroids(&edges_to_draw, &self.physics_simulator.node_positions)
+                    } else {
+                        HashMap::new()
+                    };
+
                     // Draw edges with enhanced styling
-                    for (start_node_idx, end_node_idx) in &edges_to_draw {
+                    for (edge_number, (start_node_idx, end_node_idx)) in
+                        edges_to_draw.iter().enumerate()
+                    {
                         if let (Some(&start_pos), Some(&end_pos)) = (
                             self.physics_simulator.get_node_position(*start_node_idx),
                             self.physics_simulator.get_node_position(*end_node_idx),
@@ -770,83 +2094,294 @@ impl<'a> App for FileGraphApp<'a> {
                                 end_pos.y * self.graph_zoom_factor + self.graph_center_offset.y,
                             ));
 
-                            let vec_between = end_screen_pos - start_screen_pos;
-                            let dir = vec_between.normalized();
+                            // Work in screen space so the curve scales with zoom
+                            let control_point: Option<egui::Pos2> = match (
+                                bundle_centroids.get(start_node_idx),
+                                bundle_centroids.get(end_node_idx),
+                            ) {
+                                (Some(&start_centroid), Some(&end_centroid)) => {
+                                    let to_screen_pos = |pos: egui::Vec2| {
+                                        to_screen.transform_pos(pos2(
+                                            pos.x * self.graph_zoom_factor
+                                                + self.graph_center_offset.x,
+                                            pos.y * self.graph_zoom_factor
+                                                + self.graph_center_offset.y,
+                                        ))
+                                    };
+                                    Some(bundled_control_point(
+                                        start_screen_pos,
+                                        end_screen_pos,
+                                        to_screen_pos(start_centroid),
+                                        to_screen_pos(end_centroid),
+                                        self.bundling_strength,
+                                    ))
+                                }
+                                _ if self.curved_edges => Some(curved_control_point(
+                                    start_screen_pos,
+                                    end_screen_pos,
+                                    EDGE_CURVATURE,
+                                )),
+                                _ => None,
+                            };
+                            let draw_edge = |stroke: Stroke| match control_point {
+                                Some(control) => {
+                                    painter.add(egui::epaint::QuadraticBezierShape::from_points_stroke(
+                                        [start_screen_pos, control, end_screen_pos],
+                                        false,
+                                        Color32::TRANSPARENT,
+                                        stroke,
+                                    ));
+                                }
+                                None => {
+                                    painter.line_segment([start_screen_pos, end_screen_pos], stroke);
+                                }
+                            };
 
-                            // Enhanced edge drawing with glow effect
-                            let edge_stroke = Stroke::new(
-                                1.5 * self.graph_zoom_factor,
-                                Color32::from_rgba_premultiplied(100, 100, 255, 150),
+                            // Arrows follow the curve's tangent where it meets the target
+                            let dir = edge_end_direction(
+                                start_screen_pos,
+                                control_point,
+                                end_screen_pos,
                             );
 
+                            let edge_dim = match &focus {
+                                Some((_, focus_edges))
+                                    if !focus_edges.contains(&(*start_node_idx, *end_node_idx)) =>
+                                {
+                                    0.15
+                                }
+                                _ => 1.0,
+                            };
+
+                            // Enhanced edge drawing with glow effect
+                            let mut edge_stroke = self.edge_style.stroke(self.graph_zoom_factor);
+                            edge_stroke.color = edge_stroke.color.gamma_multiply(edge_dim);
+
+                            // Edges touching the hovered node stand out
+                            if hovered_neighborhood
+                                .as_ref()
+                                .is_some_and(|(_, hovered_edges)| {
+                                    hovered_edges.contains(&(*start_node_idx, *end_node_idx))
+                                })
+                            {
+                                edge_stroke.width *= 2.0;
+                                edge_stroke.color =
+                                    edge_stroke.color.lerp_to_gamma(Color32::WHITE, 0.5);
+                            }
+                            if self.highlighted_cycle.as_ref().is_some_and(|cycle| {
+                                cycle.contains(start_node_idx) && cycle.contains(end_node_idx)
+                            }) {
+                                edge_stroke.width *= 2.0;
+                                edge_stroke.color = CYCLE_COLOR;
+                            }
+                            if self.current_graph_mode == GraphMode::Tags
+                                && let Some(&count) = self
+                                    .tag_graph
+                                    .cooccurrence
+                                    .get(&(*start_node_idx, *end_node_idx))
+                            {
+                                edge_stroke.width *= (count as f32).sqrt();
+                            }
+                            let glow_strokes: Vec<Stroke> = self
+                                .edge_style
+                                .glow_strokes(self.graph_zoom_factor, self.graph_quality)
+                                .into_iter()
+                                .map(|mut glow_stroke| {
+                                    glow_stroke.color = glow_stroke.color.gamma_multiply(edge_dim);
+                                    glow_stroke
+                                })
+                                .collect();
+
                             // Draw the edge with glow effect
-                            for i in 0..3 {
-                                let width = edge_stroke.width - i as f32 * 0.5;
-                                let alpha = (150 - i * 50) as f32;
-                                let glow_stroke = Stroke::new(
-                                    width,
-                                    Color32::from_rgba_premultiplied(100, 100, 255, alpha as u8),
-                                );
-                                painter
-                                    .line_segment([start_screen_pos, end_screen_pos], glow_stroke);
+                            for &glow_stroke in &glow_strokes {
+                                draw_edge(glow_stroke);
                             }
 
                             // Draw the main edge
-                            painter.line_segment([start_screen_pos, end_screen_pos], edge_stroke);
+                            draw_edge(edge_stroke);
 
                             // Arrow with glow
                             let arrow_size = 10.0 * self.graph_zoom_factor;
                             let arrow_tip1 = end_screen_pos - rotate_vec2(dir, 0.5) * arrow_size;
                             let arrow_tip2 = end_screen_pos - rotate_vec2(dir, -0.5) * arrow_size;
 
-                            for i in 0..3 {
-                                let width = edge_stroke.width - i as f32 * 0.5;
-                                let alpha = (150 - i * 50) as f32;
-                                let glow_stroke = Stroke::new(
-                                    width,
-                                    Color32::from_rgba_premultiplied(100, 100, 255, alpha as u8),
-                                );
+                            for &glow_stroke in &glow_strokes {
                                 painter.line_segment([end_screen_pos, arrow_tip1], glow_stroke);
                                 painter.line_segment([end_screen_pos, arrow_tip2], glow_stroke);
                             }
 
                             painter.line_segment([end_screen_pos, arrow_tip1], edge_stroke);
                             painter.line_segment([end_screen_pos, arrow_tip2], edge_stroke);
+                            picture.edges.push(PictureEdge {
+                                source: *start_node_idx,
+                                target: *end_node_idx,
+                                from: start_screen_pos,
+                                to: end_screen_pos,
+                                control: control_point,
+                                arrow: [arrow_tip1, arrow_tip2],
+                                stroke: edge_stroke,
+                            });
+
+                            // Only the first edges animate, so huge graphs stay responsive
+                            if self.show_edge_flow && edge_number < MAX_FLOW_EDGES {
+                                let dot_color =
+                                    edge_stroke.color.lerp_to_gamma(Color32::WHITE, 0.4);
+                                for dot in 0..FLOW_DOTS_PER_EDGE {
+                                    let t = flow_phase(time, FLOW_SPEED, dot, FLOW_DOTS_PER_EDGE);
+                                    painter.circle_filled(
+                                        point_on_edge(
+                                            start_screen_pos,
+                                            control_point,
+                                            end_screen_pos,
+                                            t,
+                                        ),
+                                        2.0 * self.graph_zoom_factor.sqrt(),
+                                        dot_color,
+                                    );
+                                }
+                            }
                         }
                     }
 
-                    // Draw nodes with enhanced styling
-                    for &node_idx in &nodes_to_draw {
-                        if let Some(node_pos_vec2) =
-                            self.physics_simulator.get_node_position(node_idx).cloned()
-                        {
-                            let screen_pos = to_screen.transform_pos(pos2(
-                                node_pos_vec2.x * self.graph_zoom_factor
-                                    + self.graph_center_offset.x,
-                                node_pos_vec2.y * self.graph_zoom_factor
-                                    + self.graph_center_offset.y,
-                            ));
+                    // Tag counts for the per-node badges in Links mode
+                    let tag_badge_counts: HashMap<NodeIndex, usize> =
+                        if self.show_tag_badges && self.current_graph_mode == GraphMode::Links {
+                            tag_badge_counts(&self.file_graph, &self.scanner.lock().unwrap())
+                        } else {
+                            HashMap::new()
+                        };
 
-                            let node_name = match self.current_graph_mode {
-                                GraphMode::Links => match &self.file_graph.graph[node_idx] {
-                                    GraphNode::File(s) => s.clone(),
-                                    GraphNode::Tag(s) => s.clone(),
-                                },
+                    let (folder_colors, folder_legend) = if self.color_by_folder {
+                        self.folder_colors(&nodes_to_draw)
+                    } else {
+                        (HashMap::new(), Vec::new())
+                    };
+
+                    // Nodes whose file has an identical copy elsewhere
+                    let duplicate_nodes: HashSet<NodeIndex> = self
+                        .duplicate_groups
+                        .iter()
+                        .flatten()
+                        .filter_map(|path| self.node_for_path(path))
+                        .collect();
+
+                    // Edges at each drawn node across the whole active graph, for sizing
+                    let node_degrees: HashMap<NodeIndex, usize> = if self.size_by_degree {
+                        let adjacency = match self.current_graph_mode {
+                            GraphMode::Links | GraphMode::Unified => &self.links_graph().adjacency,
+                            GraphMode::Tags => &self.tag_graph.adjacency,
+                        };
+                        nodes_to_draw
+                            .iter()
+                            .map(|&node_idx| {
+                                let degree = adjacency.outgoing(node_idx).len()
+                                    + adjacency.incoming(node_idx).len();
+                                (node_idx, degree)
+                            })
+                            .collect()
+                    } else {
+                        HashMap::new()
+                    };
+
+                    // Notes nothing links to and that link to nothing, drawn muted and dashed
+                    let unlinked_nodes = match self.current_graph_mode {
+                        GraphMode::Links | GraphMode::Unified => self.links_graph().orphans(),
+                        GraphMode::Tags => HashSet::new(),
+                    };
+
+                    // Size and modification time of the drawn files, from the scan
+                    let node_metadata = {
+                        let graph = match self.current_graph_mode {
+                            GraphMode::Links => &self.file_graph.graph,
+                            GraphMode::Tags => &self.tag_graph.graph,
+                            GraphMode::Unified => &self.unified_graph.graph,
+                        };
+                        node_metadata(graph, &self.scanner.lock().unwrap(), &nodes_to_draw)
+                    };
+
+                    // Files sharing a tag with the selected file
+                    let shared_tag_nodes = self.update_shared_tag_nodes();
+                    if self.shared_tag_edges
+                        && let Some(selected) = self.selected_node
+                        && let Some(&selected_pos) =
+                            self.physics_simulator.get_node_position(selected)
+                    {
+                        let to_graph_screen = |pos: egui::Vec2| {
+                            to_screen.transform_pos(pos2(
+                                pos.x * self.graph_zoom_factor + self.graph_center_offset.x,
+                                pos.y * self.graph_zoom_factor + self.graph_center_offset.y,
+                            ))
+                        };
+                        let stroke = Stroke::new(1.0, SHARED_TAG_COLOR.gamma_multiply(0.35));
+                        for &node_idx in &shared_tag_nodes {
+                            if nodes_to_draw.contains(&node_idx)
+                                && let Some(&pos) = self.physics_simulator.get_node_position(node_idx)
+                            {
+                                painter.add(egui::Shape::dashed_line(
+                                    &[to_graph_screen(selected_pos), to_graph_screen(pos)],
+                                    stroke,
+                                    6.0,
+                                    4.0,
+                                ));
+                            }
+                        }
+                    }
+
+                    // Draw nodes with enhanced styling
+                    let mut hovered_this_frame = None;
+                    for &node_idx in &nodes_to_draw {
+                        if let Some(node_pos_vec2) =
+                            self.physics_simulator.get_node_position(node_idx).cloned()
+                        {
+                            let screen_pos = to_screen.transform_pos(pos2(
+                                node_pos_vec2.x * self.graph_zoom_factor
+                                    + self.graph_center_offset.x,
+                                node_pos_vec2.y * self.graph_zoom_factor
+                                    + self.graph_center_offset.y,
+                            ));
+
+                            let node_name = match self.current_graph_mode {
+                                GraphMode::Links | GraphMode::Unified => match &self.links_graph().graph[node_idx] {
+                                    GraphNode::File(s) | GraphNode::Directory(s) => {
+                                        s.display().to_string()
+                                    }
+                                    GraphNode::Tag(s) => s.clone(),
+                                    GraphNode::External(s) => s.clone(),
+                                },
                                 GraphMode::Tags => match &self.tag_graph.graph[node_idx] {
-                                    GraphNode::File(s) => s.clone(),
+                                    GraphNode::File(s) | GraphNode::Directory(s) => {
+                                        s.display().to_string()
+                                    }
                                     GraphNode::Tag(s) => s.clone(),
+                                    GraphNode::External(s) => s.clone(),
                                 },
                             };
 
+                            let size_scale = match node_metadata.get(&node_idx) {
+                                Some(&(size, _)) if self.size_by_file_size => {
+                                    file_size_radius_scale(size)
+                                }
+                                _ => 1.0,
+                            };
+                            let size_scale = match node_degrees.get(&node_idx) {
+                                Some(&degree) => size_scale * degree_radius_scale(degree),
+                                None => size_scale,
+                            };
+
                             // Enhanced node styling parameters
-                            let node_radius = 15.0 * self.graph_zoom_factor * global_pulse;
+                            let node_radius =
+                                15.0 * size_scale * self.graph_zoom_factor * global_pulse;
                             let node_color = if Some(node_idx) == self.selected_node {
                                 Color32::from_rgb(255, 100, 100)
                             } else if self.search_results.contains(&node_idx) {
                                 Color32::from_rgb(100, 255, 100)
+                            } else if shared_tag_nodes.contains(&node_idx) {
+                                SHARED_TAG_COLOR
+                            } else if let Some(&folder_color) = folder_colors.get(&node_idx) {
+                                folder_color
                             } else {
                                 match self.current_graph_mode {
-                                    GraphMode::Links => match &self.file_graph.graph[node_idx] {
+                                    GraphMode::Links | GraphMode::Unified => match &self.links_graph().graph[node_idx] {
                                         GraphNode::File(path) => {
                                             let path = Path::new(path);
                                             let is_image = is_image_path(path);
@@ -864,6 +2399,8 @@ impl<'a> App for FileGraphApp<'a> {
                                             }
                                         }
                                         GraphNode::Tag(_) => Color32::from_rgb(255, 100, 150), // Pink for tags
+                                        GraphNode::External(_) => Color32::from_rgb(255, 215, 80), // Gold for URLs
+                                        GraphNode::Directory(_) => DIRECTORY_NODE_COLOR,
                                     },
                                     GraphMode::Tags => match &self.tag_graph.graph[node_idx] {
                                         GraphNode::File(path) => {
@@ -882,10 +2419,24 @@ impl<'a> App for FileGraphApp<'a> {
                                             }
                                         }
                                         GraphNode::Tag(_) => Color32::from_rgb(255, 100, 150), // Pink for tags
+                                        GraphNode::External(_) => Color32::from_rgb(255, 215, 80), // Gold for URLs
+                                        GraphNode::Directory(_) => DIRECTORY_NODE_COLOR,
                                     },
                                 }
                             };
 
+                            let is_dimmed = focus
+                                .as_ref()
+                                .is_some_and(|(focus_nodes, _)| !focus_nodes.contains(&node_idx));
+                            let is_orphan = unlinked_nodes.contains(&node_idx);
+                            let node_color = if is_dimmed {
+                                node_color.gamma_multiply(0.2)
+                            } else if is_orphan && Some(node_idx) != self.selected_node {
+                                node_color.gamma_multiply(ORPHAN_NODE_FADE)
+                            } else {
+                                node_color
+                            };
+
                             // Custom node styling parameters
                             let node_glow_radius = 10.0 * self.graph_zoom_factor;
                             let node_shadow_offset = vec2(2.0, 2.0) * self.graph_zoom_factor;
@@ -900,7 +2451,7 @@ impl<'a> App for FileGraphApp<'a> {
                             // Draw the node with effects
                             if Some(node_idx) == self.selected_node {
                                 // Glow effect for selected node
-                                for i in 0..5 {
+                                for i in 0..self.graph_quality.effect_passes(5) {
                                     let radius = node_radius * pulse + i as f32 * 2.0;
                                     let alpha = (50 - i * 10) as f32 / 255.0;
                                     let glow_color = Color32::from_rgba_premultiplied(
@@ -917,15 +2468,11 @@ impl<'a> App for FileGraphApp<'a> {
                                 }
                             }
 
-                            // Node shadow
-                            painter.circle_filled(
-                                screen_pos + node_shadow_offset,
-                                node_radius,
-                                Color32::from_black_alpha(50),
-                            );
-
-                            // Main node circle
-                            painter.circle_filled(screen_pos, node_radius, node_color);
+                            let is_external = self.current_graph_mode != GraphMode::Tags
+                                && matches!(
+                                    self.links_graph().graph.node_weight(node_idx),
+                                    Some(GraphNode::External(_))
+                                );
 
                             // Node border
                             let border_color = if Some(node_idx) == self.selected_node {
@@ -933,24 +2480,126 @@ impl<'a> App for FileGraphApp<'a> {
                             } else {
                                 Color32::from_gray(100)
                             };
-                            painter.circle_stroke(
-                                screen_pos,
-                                node_radius,
-                                Stroke::new(1.5, border_color),
-                            );
+
+                            if is_external {
+                                // External URLs are drawn as rounded squares
+                                let node_square = egui::Rect::from_center_size(
+                                    screen_pos,
+                                    vec2(node_radius * 1.8, node_radius * 1.8),
+                                );
+                                if self.graph_quality.draws_effects() {
+                                    painter.rect_filled(
+                                        node_square.translate(node_shadow_offset),
+                                        4.0,
+                                        Color32::from_black_alpha(50),
+                                    );
+                                }
+                                painter.rect_filled(node_square, 4.0, node_color);
+                                painter.rect_stroke(
+                                    node_square,
+                                    4.0,
+                                    Stroke::new(1.5, border_color),
+                                    egui::StrokeKind::Middle,
+                                );
+                            } else {
+                                // Node shadow
+                                if self.graph_quality.draws_effects() {
+                                    painter.circle_filled(
+                                        screen_pos + node_shadow_offset,
+                                        node_radius,
+                                        Color32::from_black_alpha(50),
+                                    );
+                                }
+
+                                // Main node circle
+                                painter.circle_filled(screen_pos, node_radius, node_color);
+
+                                if is_orphan {
+                                    painter.add(egui::Shape::dashed_line(
+                                        &circle_points(screen_pos, node_radius, 32),
+                                        Stroke::new(1.5, border_color),
+                                        4.0,
+                                        3.0,
+                                    ));
+                                } else {
+                                    painter.circle_stroke(
+                                        screen_pos,
+                                        node_radius,
+                                        Stroke::new(1.5, border_color),
+                                    );
+                                }
+                            }
+
+                            if self
+                                .highlighted_cycle
+                                .as_ref()
+                                .is_some_and(|cycle| cycle.contains(&node_idx))
+                            {
+                                painter.circle_stroke(
+                                    screen_pos,
+                                    node_radius + 7.0,
+                                    Stroke::new(2.0, CYCLE_COLOR),
+                                );
+                            }
+
+                            if duplicate_nodes.contains(&node_idx) {
+                                painter.circle_stroke(
+                                    screen_pos,
+                                    node_radius + 4.0,
+                                    Stroke::new(2.0, Color32::from_rgb(255, 165, 0)),
+                                );
+                            }
+
+                            // Tag count badge, kept legible at any zoom
+                            if let Some(&tag_count) = tag_badge_counts.get(&node_idx) {
+                                let badge_radius = (6.0 * self.graph_zoom_factor).clamp(7.0, 14.0);
+                                let badge_pos = screen_pos + vec2(node_radius, -node_radius) * 0.75;
+                                painter.circle_filled(
+                                    badge_pos,
+                                    badge_radius,
+                                    Color32::from_rgb(255, 100, 150),
+                                );
+                                painter.circle_stroke(
+                                    badge_pos,
+                                    badge_radius,
+                                    Stroke::new(1.0, Color32::WHITE),
+                                );
+                                painter.text(
+                                    badge_pos,
+                                    egui::Align2::CENTER_CENTER,
+                                    tag_count.to_string(),
+                                    egui::FontId::proportional(badge_radius * 1.2),
+                                    Color32::WHITE,
+                                );
+                            }
 
                             // Node label with improved styling
                             let display_name = if self.show_full_paths {
                                 node_name.clone()
+                            } else if is_external {
+                                truncate_label(
+                                    node_name
+                                        .trim_start_matches("https://")
+                                        .trim_start_matches("http://")
+                                        .trim_end_matches('/'),
+                                    self.max_label_chars,
+                                    self.label_truncation,
+                                )
                             } else {
-                                PathBuf::from(&node_name)
-                                    .file_name()
-                                    .and_then(|os_str| os_str.to_str())
-                                    .map(|s| s.to_string())
-                                    .unwrap_or_else(|| node_name.clone())
+                                truncate_label(
+                                    &display_path(Path::new(&node_name), false),
+                                    self.max_label_chars,
+                                    self.label_truncation,
+                                )
                             };
 
-                            let font_id = egui::TextStyle::Body.resolve(ui.style());
+                            let mut font_id = egui::TextStyle::Body.resolve(ui.style());
+                            font_id.size = label_font_size(
+                                font_id.size,
+                                self.label_scale,
+                                self.graph_zoom_factor,
+                                self.scale_labels_with_zoom,
+                            );
                             let text_color = {
                                 let r = node_color.r() as f32 / 255.0;
                                 let g = node_color.g() as f32 / 255.0;
@@ -963,8 +2612,17 @@ impl<'a> App for FileGraphApp<'a> {
                                 }
                             };
 
-                            let text_galley = ui
-                                .fonts(|f| f.layout_no_wrap(display_name, font_id, Color32::WHITE));
+                            let label_color = if is_dimmed {
+                                Color32::WHITE.gamma_multiply(0.2)
+                            } else {
+                                Color32::WHITE
+                            };
+                            let label_background =
+                                Color32::from_black_alpha(if is_dimmed { 30 } else { 120 });
+                            let label_size = font_id.size;
+                            let text_galley = ui.fonts(|f| {
+                                f.layout_no_wrap(display_name.clone(), font_id, label_color)
+                            });
 
                             let text_size = text_galley.size();
 
@@ -973,10 +2631,24 @@ impl<'a> App for FileGraphApp<'a> {
                                 text_pos - vec2(4.0, 0.0),
                                 text_size + vec2(8.0, 0.0), // padding
                             );
+                            picture.nodes.push(PictureNode {
+                                node: node_idx,
+                                center: screen_pos,
+                                radius: node_radius,
+                                color: node_color,
+                                border: border_color,
+                                dashed: is_orphan && !is_external,
+                                square: is_external,
+                                label: display_name,
+                                label_size,
+                                label_rect: text_bg_rect,
+                                label_color,
+                                label_background,
+                            });
                             painter.rect_filled(
                                 text_bg_rect,
-                                2.0,                            // corner radius
-                                Color32::from_black_alpha(120), // transparency
+                                2.0, // corner radius
+                                label_background, // transparency
                             );
                             painter.galley(text_pos, text_galley, Color32::WHITE);
 
@@ -1001,8 +2673,24 @@ impl<'a> App for FileGraphApp<'a> {
                                 Sense::click_and_drag(),
                             );
 
-                            if node_response.dragged_by(egui::PointerButton::Primary) {
-                                let delta = node_response.drag_delta() / self.graph_zoom_factor;
+                            // Small wobbles during a click don't move the node; once the
+                            // threshold is passed the node catches up with the pointer
+                            let already_dragging = self.dragged_node == Some(node_idx);
+                            let pointer_travel = ui.input(|i| {
+                                Some(i.pointer.interact_pos()? - i.pointer.press_origin()?)
+                            });
+                            let past_threshold = pointer_travel.is_some_and(|travel| {
+                                exceeds_drag_threshold(travel, self.node_drag_threshold)
+                            });
+                            if node_response.dragged_by(egui::PointerButton::Primary)
+                                && (already_dragging || past_threshold)
+                            {
+                                let screen_delta = if already_dragging {
+                                    node_response.drag_delta()
+                                } else {
+                                    pointer_travel.unwrap_or_default()
+                                };
+                                let delta = screen_delta / self.graph_zoom_factor;
                                 self.physics_simulator
                                     .set_node_position(node_idx, node_pos_vec2 + delta);
                                 self.dragged_node = Some(node_idx);
@@ -1014,8 +2702,10 @@ impl<'a> App for FileGraphApp<'a> {
 
                             // Enhanced hover effects
                             if node_response.hovered() {
+                                hovered_this_frame = Some(node_idx);
+
                                 // Glow effect on hover
-                                for i in 0..3 {
+                                for i in 0..self.graph_quality.effect_passes(3) {
                                     let radius = node_radius + i as f32 * 3.0;
                                     let alpha = (100 - i * 30) as f32;
                                     let hover_color = Color32::from_rgba_premultiplied(
@@ -1033,20 +2723,69 @@ impl<'a> App for FileGraphApp<'a> {
 
                                 // Show tooltip with additional information
                                 let full_name = match self.current_graph_mode {
-                                    GraphMode::Links => match &self.file_graph.graph[node_idx] {
-                                        GraphNode::File(file_path_str) => file_path_str.clone(),
+                                    GraphMode::Links | GraphMode::Unified => match &self.links_graph().graph[node_idx] {
+                                        GraphNode::File(file_path_str) => display_path(
+                                            Path::new(file_path_str),
+                                            self.show_full_paths,
+                                        ),
                                         GraphNode::Tag(tag_name) => format!("#{}", tag_name),
+                                        GraphNode::External(url) => url.clone(),
+                                        GraphNode::Directory(dir) => format!(
+                                            "📁 {} (click to expand)",
+                                            display_path(dir, self.show_full_paths)
+                                        ),
                                     },
                                     GraphMode::Tags => match &self.tag_graph.graph[node_idx] {
-                                        GraphNode::File(file_path_str) => file_path_str.clone(),
+                                        GraphNode::File(file_path_str) => display_path(
+                                            Path::new(file_path_str),
+                                            self.show_full_paths,
+                                        ),
                                         GraphNode::Tag(tag_name) => format!("#{}", tag_name),
+                                        GraphNode::External(url) => url.clone(),
+                                        GraphNode::Directory(dir) => format!(
+                                            "📁 {} (click to expand)",
+                                            display_path(dir, self.show_full_paths)
+                                        ),
                                     },
                                 };
 
+                                // Excerpt appears once the pointer has rested on the node
+                                let now = ctx.input(|i| i.time);
+                                let hover_since = match self.hover_started {
+                                    Some((idx, since)) if idx == node_idx => since,
+                                    _ => {
+                                        self.hover_started = Some((node_idx, now));
+                                        now
+                                    }
+                                };
+                                let hovered_file = match self.current_graph_mode {
+                                    GraphMode::Links => &self.file_graph.graph[node_idx],
+                                    GraphMode::Tags => &self.tag_graph.graph[node_idx],
+                                    GraphMode::Unified => &self.unified_graph.graph[node_idx],
+                                };
+                                let hover_preview = match hovered_file {
+                                    GraphNode::File(path) => {
+                                        if now - hover_since >= HOVER_PREVIEW_DELAY {
+                                            let modified = node_metadata
+                                                .get(&node_idx)
+                                                .map_or(SystemTime::UNIX_EPOCH, |m| m.1);
+                                            Some(self.hover_preview(&path.clone(), modified, ctx))
+                                        } else {
+                                            ctx.request_repaint_after(
+                                                std::time::Duration::from_secs_f64(
+                                                    HOVER_PREVIEW_DELAY,
+                                                ),
+                                            );
+                                            None
+                                        }
+                                    }
+                                    _ => None,
+                                };
+
                                 let tooltip_content = match self.current_graph_mode {
-                                    GraphMode::Links => {
+                                    GraphMode::Links | GraphMode::Unified => {
                                         if let GraphNode::File(path) =
-                                            &self.file_graph.graph[node_idx]
+                                            &self.links_graph().graph[node_idx]
                                         {
                                             let file_type = if is_image_path(Path::new(path)) {
                                                 "Image"
@@ -1058,6 +2797,10 @@ impl<'a> App for FileGraphApp<'a> {
                                                 "File"
                                             };
                                             format!("{}: {}", file_type, full_name)
+                                        } else if let GraphNode::External(_) =
+                                            &self.links_graph().graph[node_idx]
+                                        {
+                                            format!("Link: {}", full_name)
                                         } else {
                                             full_name
                                         }
@@ -1075,36 +2818,44 @@ impl<'a> App for FileGraphApp<'a> {
                                     node_response.hover_pos().unwrap(),
                                     |ui| {
                                         ui.label(egui::RichText::new(tooltip_content).strong());
-                                        if let GraphNode::File(path) =
-                                            &self.file_graph.graph[node_idx]
+                                        if let Some((size, modified)) =
+                                            node_metadata.get(&node_idx)
                                         {
-                                            if let Ok(metadata) = std::fs::metadata(path) {
-                                                let modified =
-                                                    metadata.modified().unwrap_or_else(|_| {
-                                                        std::time::SystemTime::UNIX_EPOCH
-                                                    });
-                                                let size = metadata.len();
-                                                ui.label(format!("Size: {} bytes", size));
-                                                ui.label(format!("Modified: {:?}", modified));
-                                            }
+                                            ui.label(format!("Size: {} bytes", size));
+                                            ui.label(format!("Modified: {:?}", modified));
+                                        }
+                                        if let Some(preview) = &hover_preview {
+                                            preview.show(ui);
                                         }
                                     },
                                 );
                             }
 
+                            if self.placement_mode
+                                && node_response.clicked_by(egui::PointerButton::Primary)
+                            {
+                                self.pick_up_or_drop(node_idx, node_pos_vec2);
+                            }
+
                             if node_response.clicked_by(egui::PointerButton::Primary) {
                                 self.selected_node = Some(node_idx);
+                                self.previewed_file = None;
                                 self.selected_file_content = None; // Clear previous content
                                 self.selected_image = None; // Clear previous image
 
                                 match self.current_graph_mode {
-                                    GraphMode::Links => {
-                                        if let GraphNode::File(file_path_str) =
-                                            &self.file_graph.graph[node_idx]
-                                        {
+                                    GraphMode::Links | GraphMode::Unified => match &self.links_graph().graph[node_idx] {
+                                        GraphNode::File(file_path_str) => {
                                             self.try_load_file_content(file_path_str.into(), ctx);
                                         }
-                                    }
+                                        GraphNode::External(url) if !self.safe_mode => {
+                                            ctx.open_url(egui::OpenUrl::new_tab(url));
+                                        }
+                                        GraphNode::Directory(dir) => {
+                                            self.expand_directory(dir.clone());
+                                        }
+                                        GraphNode::External(_) | GraphNode::Tag(_) => {}
+                                    },
                                     GraphMode::Tags => {
                                         if let GraphNode::File(file_path_str) =
                                             &self.tag_graph.graph[node_idx]
@@ -1124,6 +2875,51 @@ impl<'a> App for FileGraphApp<'a> {
                         }
                     }
 
+                    self.hovered_node = hovered_this_frame;
+                    self.last_picture = picture;
+
+                    // Legend for Color by Folder
+                    if !folder_legend.is_empty() {
+                        let mut legend_pos = graph_rect.left_top() + vec2(12.0, 12.0);
+                        let root_entry = ("(root)".to_string(), ROOT_FOLDER_COLOR);
+                        for (folder, color) in
+                            folder_legend.iter().chain(std::iter::once(&root_entry))
+                        {
+                            painter.circle_filled(legend_pos + vec2(5.0, 7.0), 5.0, *color);
+                            painter.text(
+                                legend_pos + vec2(16.0, 0.0),
+                                egui::Align2::LEFT_TOP,
+                                folder,
+                                egui::FontId::proportional(13.0),
+                                Color32::WHITE,
+                            );
+                            legend_pos.y += 18.0;
+                        }
+                    }
+
+                    // The node cap was hit: say how much is missing and how to see it
+                    let omitted_nodes = match self.current_graph_mode {
+                        GraphMode::Links => self.file_graph.omitted_nodes,
+                        GraphMode::Tags => self.tag_graph.omitted_nodes,
+                        GraphMode::Unified => self.unified_graph.omitted_nodes,
+                    };
+                    if omitted_nodes > 0 {
+                        painter.text(
+                            graph_rect.center_top() + vec2(0.0, 12.0),
+                            egui::Align2::CENTER_TOP,
+                            format!(
+                                "⚠ Showing {} of {} nodes. Filter the graph or raise the node limit in Settings.",
+                                node_count,
+                                node_count + omitted_nodes
+                            ),
+                            egui::FontId::proportional(14.0),
+                            Color32::YELLOW,
+                        );
+                    }
+                    if hovered_this_frame.is_none() {
+                        self.hover_started = None;
+                    }
+
                     // Render the custom right-click menu as an egui::Window
                     if let Some(menu_node_idx) = self.open_menu_on_node {
                         if let Some(menu_pos) = self.right_click_menu_pos {
@@ -1138,67 +2934,111 @@ impl<'a> App for FileGraphApp<'a> {
                                 .default_width(200.0)
                                 .show(ctx, |ui| {
                                     let full_name_for_menu = match self.current_graph_mode {
-                                        GraphMode::Links => match &self.file_graph.graph
+                                        GraphMode::Links | GraphMode::Unified => match &self.links_graph().graph
                                             [menu_node_idx]
                                         {
-                                            GraphNode::File(file_path_str) => file_path_str.clone(),
+                                            GraphNode::File(file_path)
+                                            | GraphNode::Directory(file_path) => {
+                                                file_path.display().to_string()
+                                            }
                                             GraphNode::Tag(tag_name) => {
                                                 format!("Tag: #{}", tag_name)
                                             }
+                                            GraphNode::External(url) => url.clone(),
                                         },
                                         GraphMode::Tags => match &self.tag_graph.graph
                                             [menu_node_idx]
                                         {
-                                            GraphNode::File(file_path_str) => file_path_str.clone(),
+                                            GraphNode::File(file_path)
+                                            | GraphNode::Directory(file_path) => {
+                                                file_path.display().to_string()
+                                            }
                                             GraphNode::Tag(tag_name) => {
                                                 format!("Tag: #{}", tag_name)
                                             }
+                                            GraphNode::External(url) => url.clone(),
                                         },
                                     };
                                     ui.label(full_name_for_menu);
                                     ui.separator();
 
+                                    if self.isolated_node == Some(menu_node_idx) {
+                                        if ui.button("Show All").clicked() {
+                                            self.isolated_node = None;
+                                            should_close_menu = true;
+                                        }
+                                    } else if ui.button("Isolate").clicked() {
+                                        self.isolated_node = Some(menu_node_idx);
+                                        should_close_menu = true;
+                                    }
+                                    if ui.button("Copy Neighborhood Image").clicked() {
+                                        let picture = self.last_picture.neighborhood(menu_node_idx);
+                                        self.copy_picture(
+                                            &picture,
+                                            picture.bounds().expand(12.0),
+                                            ctx.pixels_per_point() * self.snapshot_scale,
+                                        );
+                                        should_close_menu = true;
+                                    }
+
                                     let path_buf_option = match self.current_graph_mode {
-                                        GraphMode::Links => {
-                                            match &self.file_graph.graph[menu_node_idx] {
-                                                GraphNode::File(s) => Some(PathBuf::from(s)),
-                                                GraphNode::Tag(_) => None,
+                                        GraphMode::Links | GraphMode::Unified => {
+                                            match &self.links_graph().graph[menu_node_idx] {
+                                                GraphNode::File(path) => Some(path.clone()),
+                                                GraphNode::Tag(_)
+                                                | GraphNode::External(_)
+                                                | GraphNode::Directory(_) => None,
                                             }
                                         }
                                         GraphMode::Tags => {
                                             match &self.tag_graph.graph[menu_node_idx] {
-                                                GraphNode::File(s) => Some(PathBuf::from(s)),
-                                                GraphNode::Tag(_) => None,
+                                                GraphNode::File(path) => Some(path.clone()),
+                                                GraphNode::Tag(_)
+                                                | GraphNode::External(_)
+                                                | GraphNode::Directory(_) => None,
                                             }
                                         }
                                     };
 
+                                    let external_url = match self.current_graph_mode {
+                                        GraphMode::Links | GraphMode::Unified => {
+                                            match &self.links_graph().graph[menu_node_idx] {
+                                                GraphNode::External(url) => Some(url.clone()),
+                                                _ => None,
+                                            }
+                                        }
+                                        GraphMode::Tags => None,
+                                    };
+
+                                    if let Some(url) = external_url {
+                                        if ui
+                                            .add_enabled(
+                                                !self.safe_mode,
+                                                egui::Button::new("Open Link"),
+                                            )
+                                            .on_disabled_hover_text(SAFE_MODE_HINT)
+                                            .clicked()
+                                        {
+                                            ctx.open_url(egui::OpenUrl::new_tab(&url));
+                                            should_close_menu = true;
+                                        }
+                                        if ui.button("Copy URL").clicked() {
+                                            ctx.copy_text(url);
+                                            should_close_menu = true;
+                                        }
+                                    }
+
                                     if let Some(path_buf) = path_buf_option {
                                         if path_buf.is_file() {
-                                            if ui.button("Open File").clicked() {
-                                                #[cfg(target_os = "linux")]
-                                                {
-                                                    std::process::Command::new("xdg-open")
-                                                        .arg(&path_buf)
-                                                        .spawn()
-                                                        .expect("Failed to open file");
-                                                }
-                                                #[cfg(target_os = "macos")]
-                                                {
-                                                    std::process::Command::new("open")
-                                                        .arg(&path_buf)
-                                                        .spawn()
-                                                        .expect("Failed to open file");
-                                                }
-                                                #[cfg(target_os = "windows")]
-                                                {
-                                                    std::process::Command::new("cmd")
-                                                        .arg("/C")
-                                                        .arg("start")
-                                                        .arg(&path_buf)
-                                                        .spawn()
-                                                        .expect("Failed to open file");
-                                                }
+                                            if ui
+                                                .add_enabled(
+                                                    !self.safe_mode,
+                                                    egui::Button::new("Open File"),
+                                                )
+                                                .on_disabled_hover_text(SAFE_MODE_HINT)
+                                                .clicked()
+                                            {
+                                                self.open_file_externally(&path_buf);
                                                 should_close_menu = true;
                                             }
                                             if ui.button("Copy Path").clicked() {
@@ -1207,6 +3047,88 @@ impl<'a> App for FileGraphApp<'a> {
                                                 );
                                                 should_close_menu = true;
                                             }
+                                            if ui.button("Copy Relative Path").clicked() {
+                                                let root = self
+                                                    .scanner
+                                                    .lock()
+                                                    .map(|scanner| scanner.root_path().clone())
+                                                    .unwrap_or_default();
+                                                ctx.copy_text(
+                                                    relative_to_root(&path_buf, &root)
+                                                        .to_string_lossy()
+                                                        .to_string(),
+                                                );
+                                                should_close_menu = true;
+                                            }
+                                            if ui.button("Copy Backlinks").clicked() {
+                                                let backlinks =
+                                                    self.file_graph.backlinks(&path_buf);
+                                                ctx.copy_text(wikilink_list(&backlinks));
+                                                should_close_menu = true;
+                                            }
+                                            if !is_image_path(&path_buf) && !is_pdf_path(&path_buf)
+                                            {
+                                                ui.separator();
+                                                let safe_mode = self.safe_mode;
+                                                ui.add_enabled_ui(!safe_mode, |ui| {
+                                                    ui.horizontal(|ui| {
+                                                    let input = ui.add(
+                                                        egui::TextEdit::singleline(
+                                                            &mut self.new_tag_input,
+                                                        )
+                                                        .hint_text("tag")
+                                                        .desired_width(100.0),
+                                                    );
+                                                    let submitted = input.lost_focus()
+                                                        && ui.input(|i| {
+                                                            i.key_pressed(egui::Key::Enter)
+                                                        });
+                                                    if ui.button("Add Tag").clicked() || submitted {
+                                                        self.add_tag_to_file(&path_buf, ctx);
+                                                        should_close_menu = true;
+                                                    }
+                                                    });
+                                                })
+                                                .response
+                                                .on_disabled_hover_text(SAFE_MODE_HINT);
+                                            }
+
+                                            ui.separator();
+                                            let can_edit = !self.safe_mode && !self.is_scanning;
+                                            ui.add_enabled_ui(can_edit, |ui| {
+                                                ui.horizontal(|ui| {
+                                                    let current_name = path_buf
+                                                        .file_name()
+                                                        .unwrap_or_default()
+                                                        .to_string_lossy()
+                                                        .to_string();
+                                                    let input = ui.add(
+                                                        egui::TextEdit::singleline(
+                                                            &mut self.rename_input,
+                                                        )
+                                                        .hint_text(current_name)
+                                                        .desired_width(100.0),
+                                                    );
+                                                    let submitted = input.lost_focus()
+                                                        && ui.input(|i| {
+                                                            i.key_pressed(egui::Key::Enter)
+                                                        });
+                                                    if ui.button("Rename").clicked() || submitted {
+                                                        self.rename_file(&path_buf);
+                                                        should_close_menu = true;
+                                                    }
+                                                });
+                                                if ui.button("New Note Here").clicked() {
+                                                    self.create_note_beside(&path_buf);
+                                                    should_close_menu = true;
+                                                }
+                                                if ui.button("Move to Trash").clicked() {
+                                                    self.trash_file(&path_buf);
+                                                    should_close_menu = true;
+                                                }
+                                            })
+                                            .response
+                                            .on_disabled_hover_text(SAFE_MODE_HINT);
                                         }
                                     }
                                 });
@@ -1239,46 +3161,7 @@ impl<'a> App for FileGraphApp<'a> {
                 .resizable(true)
                 .default_width(300.0)
                 .show(ctx, |ui| {
-                    ui.horizontal(|ui| {
-                        ui.vertical(|ui| {
-                            ui.add(
-                                egui::Slider::new(
-                                    &mut self.physics_simulator.spring_constant,
-                                    0.001..=0.5,
-                                )
-                                .text("Spring K"),
-                            );
-                            ui.add(
-                                egui::Slider::new(&mut self.physics_simulator.damping, 0.0..=0.9)
-                                    .text("Damping"),
-                            );
-                            ui.add(
-                                egui::Slider::new(&mut self.physics_simulator.time_step, 0.1..=1.0)
-                                    .text("Time Step"),
-                            );
-                        });
-                        ui.vertical(|ui| {
-                            ui.add(
-                                egui::Slider::new(
-                                    &mut self.physics_simulator.repulsion_constant,
-                                    100.0..=50000.0,
-                                )
-                                .text("Repulsion K"),
-                            );
-                            ui.add(
-                                egui::Slider::new(
-                                    &mut self.physics_simulator.ideal_edge_length,
-                                    10.0..=300.0,
-                                )
-                                .text("Ideal Length"),
-                            );
-                            ui.add(
-                                egui::Slider::new(&mut self.physics_simulator.friction, 0.0..=0.9)
-                                    .text("Friction"),
-                            );
-                        });
-                    });
-
+                    self.physics_sliders_ui(ui);
                     ui.separator();
 
                     ui.horizontal(|ui| {
@@ -1308,78 +3191,629 @@ impl<'a> App for FileGraphApp<'a> {
             }
         }
 
-        // Right panel section
-        egui::SidePanel::right("file_content_panel")
-            .min_width(200.0)
-            .show_animated(ctx, self.show_content_panel, |ui| {
-                ui.horizontal(|ui| {
-                    ui.heading("File Content");
-                    if ui.button("▶").clicked() {
-                        self.show_content_panel = !self.show_content_panel;
-                    }
+        // Display settings floating window
+        {
+            let mut show_display_window = self.show_display_window;
+            egui::Window::new("Display Settings")
+                .open(&mut show_display_window)
+                .collapsible(true)
+                .resizable(false)
+                .default_width(250.0)
+                .show(ctx, |ui| {
+                    self.display_settings_ui(ui);
                 });
-                ui.separator();
-
-                // Display file name
-                if let Some(node_idx) = self.selected_node {
-                    let file_name = match self.current_graph_mode {
-                        GraphMode::Links => match &self.file_graph.graph[node_idx] {
-                            GraphNode::File(s) => PathBuf::from(s).file_name().map_or_else(
-                                || s.clone(),
-                                |os_str| os_str.to_string_lossy().into_owned(),
-                            ),
-                            GraphNode::Tag(s) => format!("#{}", s),
-                        },
-                        GraphMode::Tags => match &self.tag_graph.graph[node_idx] {
-                            GraphNode::File(s) => PathBuf::from(s).file_name().map_or_else(
-                                || s.clone(),
-                                |os_str| os_str.to_string_lossy().into_owned(),
-                            ),
-                            GraphNode::Tag(s) => format!("#{}", s),
-                        },
-                    };
+            self.show_display_window = show_display_window;
+        }
 
-                    ui.label(egui::RichText::new(file_name).strong());
+        // Settings window grouping every option by area
+        {
+            let mut show_settings_window = self.show_settings_window;
+            egui::Window::new("Settings")
+                .open(&mut show_settings_window)
+                .collapsible(true)
+                .resizable(true)
+                .default_width(320.0)
+                .show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.selectable_value(&mut self.settings_tab, SettingsTab::Scan, "Scan");
+                        ui.selectable_value(
+                            &mut self.settings_tab,
+                            SettingsTab::Display,
+                            "Display",
+                        );
+                        ui.selectable_value(
+                            &mut self.settings_tab,
+                            SettingsTab::Physics,
+                            "Physics",
+                        );
+                    });
                     ui.separator();
 
-                    let path = match self.current_graph_mode {
-                        GraphMode::Links => match &self.file_graph.graph[node_idx] {
-                            GraphNode::File(s) => PathBuf::from(s),
-                            GraphNode::Tag(_) => {
-                                ui.label("Tag node selected");
-                                return;
+                    match self.settings_tab {
+                        SettingsTab::Scan => {
+                            if ui
+                                .checkbox(&mut self.show_hidden_files, "Show Hidden Files")
+                                .changed()
+                            {
+                                self.apply_scan_options(ctx);
                             }
-                        },
-                        GraphMode::Tags => match &self.tag_graph.graph[node_idx] {
-                            GraphNode::File(s) => PathBuf::from(s),
-                            GraphNode::Tag(_) => {
-                                ui.label("Tag node selected");
-                                return;
+                            if ui
+                                .checkbox(&mut self.parse_code_imports, "Code Imports as Links")
+                                .on_hover_text("Rust mod/use, Python import and C #include")
+                                .changed()
+                            {
+                                self.apply_scan_options(ctx);
                             }
-                        },
-                    };
-
-                    if is_pdf_path(&path) {
-                        // Check for rendered page updates
-                        if let Some(receiver) = &mut self.pdf_viewer_state.page_render_receiver {
-                            while let Ok((path, page_idx, texture, total_pages)) =
-                                receiver.try_recv()
+                            if ui
+                                .checkbox(&mut self.respect_gitignore, "Respect .gitignore")
+                                .on_hover_text(
+                                    "Skip files ignored by .gitignore files in the scanned folders",
+                                )
+                                .changed()
                             {
-                                if Some(&path) == self.pdf_viewer_state.current_pdf_path.as_ref() {
-                                    self.pdf_viewer_state.rendered_page_texture = Some(texture);
-                                    self.pdf_viewer_state.total_pages = total_pages;
-                                    self.pdf_viewer_state.loading = false;
-                                    self.pdf_viewer_state.current_page_number = page_idx;
+                                self.apply_scan_options(ctx);
+                            }
+                            let mut limits_changed = false;
+                            ui.horizontal(|ui| limits_changed |= self.max_depth_field(ui));
+                            ui.horizontal(|ui| limits_changed |= self.max_file_size_field(ui));
+                            if limits_changed {
+                                self.apply_scan_options(ctx);
+                            }
+                            ui.label("Exclude (globs relative to the scan root):");
+                            let mut removed = None;
+                            for (i, glob) in self.exclude_globs.iter().enumerate() {
+                                ui.horizontal(|ui| {
+                                    ui.monospace(glob);
+                                    if ui.small_button("✖").clicked() {
+                                        removed = Some(i);
+                                    }
+                                });
+                            }
+                            let mut globs_changed = false;
+                            if let Some(i) = removed {
+                                self.exclude_globs.remove(i);
+                                globs_changed = true;
+                            }
+                            ui.horizontal(|ui| {
+                                let input = ui.add(
+                                    egui::TextEdit::singleline(&mut self.exclude_glob_input)
+                                        .hint_text("**/archive/**")
+                                        .desired_width(160.0),
+                                );
+                                let submitted = input.lost_focus()
+                                    && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                                let glob = self.exclude_glob_input.trim().to_string();
+                                if (ui.button("Add").clicked() || submitted)
+                                    && !glob.is_empty()
+                                    && !self.exclude_globs.contains(&glob)
+                                {
+                                    match path_glob(&glob) {
+                                        Ok(_) => {
+                                            self.exclude_globs.push(glob);
+                                            self.exclude_glob_input.clear();
+                                            globs_changed = true;
+                                        }
+                                        Err(e) => {
+                                            self.scan_error =
+                                                Some(format!("Invalid glob {:?}: {}", glob, e));
+                                        }
+                                    }
                                 }
+                            });
+                            if globs_changed {
+                                self.apply_scan_options(ctx);
                             }
+                            ui.checkbox(&mut self.watch_for_changes, "Watch for Changes")
+                                .on_hover_text("Rescan when files in the folder change");
+                            ui.add_enabled_ui(self.watch_for_changes, |ui| {
+                                let mut debounce_ms =
+                                    self.rescan_debouncer.window.as_millis() as u64;
+                                if ui
+                                    .add(
+                                        egui::Slider::new(&mut debounce_ms, 100..=10_000)
+                                            .text("Debounce (ms)"),
+                                    )
+                                    .changed()
+                                {
+                                    self.rescan_debouncer.window =
+                                        Duration::from_millis(debounce_ms);
+                                }
+                                ui.checkbox(
+                                    &mut self.rescan_debouncer.batch_events,
+                                    "Batch Events",
+                                )
+                                .on_hover_text(
+                                    "Wait until changes stop before rescanning, \
+                                         instead of a fixed time after the first one",
+                                );
+                            });
+                            ui.horizontal(|ui| {
+                                ui.label("Node limit:");
+                                ui.add(
+                                    egui::DragValue::new(&mut self.max_graph_nodes)
+                                        .range(100..=100_000)
+                                        .speed(50),
+                                );
+                            });
+                            ui.checkbox(&mut self.show_images, "Show Images");
+                            ui.add_enabled(
+                                self.show_images,
+                                egui::Checkbox::new(
+                                    &mut self.images_affect_layout,
+                                    "Images Affect Layout",
+                                ),
+                            );
+                            ui.checkbox(&mut self.show_untagged_files, "Show Untagged (Tags mode)");
                         }
-
-                        if self.pdf_viewer_state.loading {
+                        SettingsTab::Display => {
+                            ui.checkbox(&mut self.show_full_paths, "Show Full Paths");
+                            ui.checkbox(&mut self.show_tag_badges, "Tag Badges");
+                            ui.checkbox(&mut self.focus_on_hover, "Focus on Hover");
+                            ui.checkbox(&mut self.color_by_folder, "Color by Folder");
+                            ui.checkbox(&mut self.size_by_file_size, "Size by File Size");
+                            ui.checkbox(&mut self.size_by_degree, "Size by Connections");
+                            ui.checkbox(&mut self.show_density_heatmap, "Density Heatmap");
+                            ui.checkbox(&mut self.highlight_shared_tags, "Highlight Shared Tags")
+                                .on_hover_text(
+                                    "Highlight files sharing a tag with the selected file",
+                                );
+                            ui.add_enabled(
+                                self.highlight_shared_tags,
+                                egui::Checkbox::new(&mut self.shared_tag_edges, "Shared Tag Edges"),
+                            );
                             ui.horizontal(|ui| {
-                                ui.spinner();
-                                ui.label("Loading PDF page...");
+                                ui.label("Filter Tags:");
+                                self.tag_filter_field(ui);
                             });
-                        } else if let Some(error) = &self.pdf_viewer_state.error {
+                            ui.add(
+                                egui::Slider::new(&mut self.min_connections, 0..=10)
+                                    .text("Min connections"),
+                            );
+                            ui.add(
+                                egui::Slider::new(&mut self.node_drag_threshold, 0.0..=30.0)
+                                    .text("Click/drag threshold (px)"),
+                            )
+                            .on_hover_text(
+                                "How far the pointer can move during a click before it drags the node",
+                            );
+                            ui.separator();
+                            self.display_settings_ui(ui);
+                        }
+                        SettingsTab::Physics => {
+                            self.physics_sliders_ui(ui);
+                        }
+                    }
+                    ui.separator();
+                    if ui.button("↺ Reset to Defaults").clicked() {
+                        self.confirm_reset_settings = true;
+                    }
+                });
+            self.show_settings_window = show_settings_window;
+        }
+
+        if self.confirm_reset_settings {
+            egui::Window::new("Reset Settings")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                .show(ctx, |ui| {
+                    ui.label("Restore every saved setting, including keybindings, to its default?");
+                    ui.label("Saved views and the current selection are kept.");
+                    ui.checkbox(
+                        &mut self.reset_clears_search_history,
+                        "Also clear search history",
+                    );
+                    ui.horizontal(|ui| {
+                        if ui.button("Reset").clicked() {
+                            if self.reset_settings(self.reset_clears_search_history) {
+                                self.apply_scan_options(ctx);
+                            }
+                            self.confirm_reset_settings = false;
+                        }
+                        if ui.button("Cancel").clicked() {
+                            self.confirm_reset_settings = false;
+                        }
+                    });
+                });
+        }
+
+        // Files and folders the last scan could not read
+        if self.show_scan_errors_window {
+            let scan_errors = self
+                .scanner
+                .lock()
+                .map(|scanner| scanner.scan_errors.clone())
+                .unwrap_or_default();
+            egui::Window::new("Scan Errors")
+                .open(&mut self.show_scan_errors_window)
+                .collapsible(true)
+                .resizable(true)
+                .default_width(400.0)
+                .show(ctx, |ui| {
+                    if scan_errors.is_empty() {
+                        ui.label("Every file was read successfully.");
+                    }
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        for (path, error) in &scan_errors {
+                            ui.label(egui::RichText::new(path.display().to_string()).strong());
+                            ui.colored_label(Color32::LIGHT_RED, error);
+                            ui.separator();
+                        }
+                    });
+                });
+        }
+
+        // Keybindings window: click an action, then press its new key
+        if self.show_keybindings_window {
+            if let Some(action) = self.rebinding_action
+                && let Some(shortcut) = ctx.input(captured_shortcut)
+            {
+                if shortcut.logical_key != egui::Key::Escape {
+                    self.keybindings.rebind(action, shortcut);
+                }
+                self.rebinding_action = None;
+            }
+            let mut show_keybindings_window = self.show_keybindings_window;
+            egui::Window::new("Keybindings")
+                .open(&mut show_keybindings_window)
+                .collapsible(true)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    egui::Grid::new("keybindings_grid")
+                        .num_columns(2)
+                        .striped(true)
+                        .show(ui, |ui| {
+                            for action in Action::ALL {
+                                ui.label(action.label());
+                                let shortcut = self.keybindings.shortcut(action);
+                                let text = if self.rebinding_action == Some(action) {
+                                    "Press a key (Esc cancels)".to_string()
+                                } else {
+                                    ctx.format_shortcut(&shortcut)
+                                };
+                                let conflicts = self.keybindings.conflicts(action, shortcut);
+                                let button = ui.button(text);
+                                let button = if conflicts.is_empty() {
+                                    button
+                                } else {
+                                    button.on_hover_text(format!(
+                                        "Also bound to: {}",
+                                        conflicts
+                                            .iter()
+                                            .map(|a| a.label())
+                                            .collect::<Vec<_>>()
+                                            .join(", ")
+                                    ))
+                                };
+                                if button.clicked() {
+                                    self.rebinding_action = Some(action);
+                                }
+                                ui.end_row();
+                            }
+                        });
+                    if ui.button("Reset to Defaults").clicked() {
+                        self.keybindings.reset();
+                        self.rebinding_action = None;
+                    }
+                });
+            self.show_keybindings_window = show_keybindings_window;
+            if !self.show_keybindings_window {
+                self.rebinding_action = None;
+            }
+        }
+
+        // Scan statistics and what changed since the snapshot saved for this folder
+        if self.show_scan_stats_window {
+            let root = self.current_scan_dir.clone();
+            let (current, diff) = self.scan_stats.take().unwrap_or_else(|| {
+                let taken_at = chrono::Local::now().format("%Y-%m-%d %H:%M").to_string();
+                let current = ScanSnapshot::from_scanner(&self.scanner.lock().unwrap(), taken_at);
+                let diff = self
+                    .scan_snapshots
+                    .by_root
+                    .get(&root)
+                    .map(|saved| saved.diff(&current));
+                (current, diff)
+            });
+            let mut save_snapshot = false;
+            let mut show_scan_stats_window = self.show_scan_stats_window;
+            egui::Window::new("Scan Statistics")
+                .open(&mut show_scan_stats_window)
+                .collapsible(true)
+                .resizable(true)
+                .default_width(400.0)
+                .show(ctx, |ui| {
+                    let summary = |snapshot: &ScanSnapshot| {
+                        format!(
+                            "{} files, {} links, {} tags, {} orphans, {} broken links",
+                            snapshot.files.len(),
+                            snapshot.links.len(),
+                            snapshot.tag_count,
+                            snapshot.orphan_count,
+                            snapshot.broken_links.len()
+                        )
+                    };
+                    ui.label(egui::RichText::new("Now").strong());
+                    ui.label(summary(&current));
+                    ui.separator();
+                    match self.scan_snapshots.by_root.get(&root) {
+                        Some(saved) => {
+                            ui.label(
+                                egui::RichText::new(format!("Snapshot of {}", saved.taken_at))
+                                    .strong(),
+                            );
+                            ui.label(summary(saved));
+                        }
+                        None => {
+                            ui.label("No snapshot saved for this folder yet.");
+                        }
+                    }
+                    if ui.button("📸 Save Snapshot").clicked() {
+                        save_snapshot = true;
+                    }
+                    let Some(diff) = &diff else {
+                        return;
+                    };
+                    ui.separator();
+                    if diff.is_empty() {
+                        ui.label("No changes since the snapshot.");
+                        return;
+                    }
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        let path_list = |ui: &mut egui::Ui, title: &str, paths: &[PathBuf]| {
+                            ui.collapsing(format!("{} ({})", title, paths.len()), |ui| {
+                                for path in paths {
+                                    ui.label(relative_to_root(path, &root).display().to_string());
+                                }
+                            });
+                        };
+                        let link_list =
+                            |ui: &mut egui::Ui, title: &str, links: &[(PathBuf, PathBuf)]| {
+                                ui.collapsing(format!("{} ({})", title, links.len()), |ui| {
+                                    for (source, target) in links {
+                                        ui.label(format!(
+                                            "{} → {}",
+                                            relative_to_root(source, &root).display(),
+                                            relative_to_root(target, &root).display()
+                                        ));
+                                    }
+                                });
+                            };
+                        path_list(ui, "Added files", &diff.added_files);
+                        path_list(ui, "Removed files", &diff.removed_files);
+                        link_list(ui, "New links", &diff.new_links);
+                        link_list(ui, "Removed links", &diff.removed_links);
+                        ui.collapsing(
+                            format!("New broken links ({})", diff.new_broken_links.len()),
+                            |ui| {
+                                for (source, link) in &diff.new_broken_links {
+                                    ui.label(format!(
+                                        "{} → {}",
+                                        relative_to_root(source, &root).display(),
+                                        link
+                                    ));
+                                }
+                            },
+                        );
+                    });
+                });
+            self.show_scan_stats_window = show_scan_stats_window;
+            if save_snapshot {
+                self.scan_snapshots.by_root.insert(root, current);
+            } else {
+                self.scan_stats = Some((current, diff));
+            }
+        }
+
+        // Link cycles, recomputed while the window is open so they follow rescans
+        if self.show_cycles_window {
+            let mut show_cycles_window = self.show_cycles_window;
+            let cycles = match self.current_graph_mode {
+                GraphMode::Links | GraphMode::Unified => link_cycles(&self.links_graph().graph),
+                GraphMode::Tags => Vec::new(),
+            };
+            let mut file_to_preview = None;
+            egui::Window::new("Link Cycles")
+                .open(&mut show_cycles_window)
+                .collapsible(true)
+                .resizable(true)
+                .default_width(350.0)
+                .show(ctx, |ui| {
+                    if self.current_graph_mode == GraphMode::Tags {
+                        ui.label("Cycles are found in the Links graph.");
+                    } else if cycles.is_empty() {
+                        ui.label("No circular links found.");
+                    } else {
+                        egui::ScrollArea::vertical().show(ui, |ui| {
+                            let graph = match self.current_graph_mode {
+                                GraphMode::Unified => &self.unified_graph.graph,
+                                GraphMode::Links | GraphMode::Tags => &self.file_graph.graph,
+                            };
+                            for (i, cycle) in cycles.iter().enumerate() {
+                                let is_highlighted = self.highlighted_cycle.as_ref() == Some(cycle);
+                                let title = if cycle.len() == 1 {
+                                    format!("Cycle {} (links to itself)", i + 1)
+                                } else {
+                                    format!("Cycle {} ({} files)", i + 1, cycle.len())
+                                };
+                                if ui.selectable_label(is_highlighted, title).clicked() {
+                                    self.highlighted_cycle =
+                                        (!is_highlighted).then(|| cycle.clone());
+                                }
+                                for &node_idx in cycle {
+                                    if let Some(GraphNode::File(path)) = graph.node_weight(node_idx)
+                                        && ui
+                                            .link(display_path(path, self.show_full_paths))
+                                            .clicked()
+                                    {
+                                        file_to_preview = Some(path.clone());
+                                    }
+                                }
+                                ui.separator();
+                            }
+                        });
+                    }
+                });
+            self.show_cycles_window = show_cycles_window;
+            if !show_cycles_window
+                || self
+                    .highlighted_cycle
+                    .as_ref()
+                    .is_some_and(|highlighted| !cycles.contains(highlighted))
+            {
+                self.highlighted_cycle = None;
+            }
+            if let Some(path) = file_to_preview {
+                self.preview_file(path, ctx);
+            }
+        }
+
+        // Duplicate files window
+        if self.show_duplicates_window {
+            let mut show_duplicates_window = self.show_duplicates_window;
+            let mut file_to_preview = None;
+            egui::Window::new("Duplicate Files")
+                .open(&mut show_duplicates_window)
+                .collapsible(true)
+                .resizable(true)
+                .default_width(350.0)
+                .show(ctx, |ui| {
+                    if self.duplicate_receiver.is_some() {
+                        ui.horizontal(|ui| {
+                            ui.spinner();
+                            ui.label("Hashing files...");
+                        });
+                    } else if self.duplicate_groups.is_empty() {
+                        ui.label("No duplicate files found.");
+                    } else {
+                        egui::ScrollArea::vertical().show(ui, |ui| {
+                            for (i, group) in self.duplicate_groups.iter().enumerate() {
+                                ui.label(
+                                    egui::RichText::new(format!(
+                                        "Group {} ({} files)",
+                                        i + 1,
+                                        group.len()
+                                    ))
+                                    .strong(),
+                                );
+                                for path in group {
+                                    if ui.link(path.display().to_string()).clicked() {
+                                        file_to_preview = Some(path.clone());
+                                    }
+                                }
+                                ui.separator();
+                            }
+                        });
+                    }
+                });
+            self.show_duplicates_window = show_duplicates_window;
+            if let Some(path) = file_to_preview {
+                self.preview_file(path, ctx);
+            }
+        }
+
+        // Right panel section
+        egui::SidePanel::right("file_content_panel")
+            .min_width(200.0)
+            .show_animated(ctx, self.show_content_panel, |ui| {
+                ui.horizontal(|ui| {
+                    ui.heading("File Content");
+                    if ui.button("▶").clicked() {
+                        self.show_content_panel = !self.show_content_panel;
+                    }
+                    ui.menu_button("Aa", |ui| {
+                        ui.add(
+                            egui::Slider::new(&mut self.content_font_size, 10.0..=28.0)
+                                .text("Font size"),
+                        );
+                        ui.add(
+                            egui::Slider::new(&mut self.content_line_spacing, 0.5..=3.0)
+                                .text("Line spacing"),
+                        );
+                        if ui.button("Reset").clicked() {
+                            let defaults = AppSettings::default();
+                            self.content_font_size = defaults.content_font_size;
+                            self.content_line_spacing = defaults.content_line_spacing;
+                        }
+                    });
+                });
+                ui.set_style(content_style(
+                    ui.style(),
+                    self.content_font_size,
+                    self.content_line_spacing,
+                ));
+                ui.separator();
+
+                // Resolve the shown file: the selected node, or a file previewed outside the graph
+                let selected_path = match self.selected_graph_node().cloned() {
+                    Some(GraphNode::File(path)) => Some(path),
+                    Some(GraphNode::Tag(s)) => {
+                        ui.label(egui::RichText::new(format!("#{}", s)).strong());
+                        ui.separator();
+                        ui.label("Tag node selected");
+                        return;
+                    }
+                    Some(GraphNode::External(url)) => {
+                        ui.label(egui::RichText::new(&url).strong());
+                        ui.separator();
+                        ui.hyperlink(url);
+                        return;
+                    }
+                    Some(GraphNode::Directory(dir)) => {
+                        ui.label(
+                            egui::RichText::new(display_path(&dir, self.show_full_paths)).strong(),
+                        );
+                        ui.separator();
+                        ui.label("Collapsed folder; click its node to expand it");
+                        return;
+                    }
+                    None => self.previewed_file.clone(),
+                };
+
+                // Display file name
+                if let Some(path) = selected_path {
+                    let file_name = display_path(&path, self.show_full_paths);
+
+                    ui.label(egui::RichText::new(file_name).strong());
+                    let broken_links = self
+                        .scanner
+                        .lock()
+                        .ok()
+                        .and_then(|scanner| scanner.broken_links.get(&path).cloned());
+                    if let Some(broken_links) = broken_links {
+                        ui.collapsing(
+                            egui::RichText::new(format!("⚠ {} broken links", broken_links.len()))
+                                .color(Color32::YELLOW),
+                            |ui| {
+                                for label in &broken_links {
+                                    ui.label(format!("[{}] has no definition", label));
+                                }
+                            },
+                        );
+                    }
+                    ui.separator();
+
+                    if is_pdf_path(&path) {
+                        // Check for rendered page updates
+                        if let Some(receiver) = &mut self.pdf_viewer_state.page_render_receiver {
+                            while let Ok((path, page_idx, texture, total_pages)) =
+                                receiver.try_recv()
+                            {
+                                if Some(&path) == self.pdf_viewer_state.current_pdf_path.as_ref() {
+                                    self.pdf_viewer_state.rendered_page_texture = Some(texture);
+                                    self.pdf_viewer_state.total_pages = total_pages;
+                                    self.pdf_viewer_state.loading = false;
+                                    self.pdf_viewer_state.current_page_number = page_idx;
+                                }
+                            }
+                        }
+
+                        if self.pdf_viewer_state.loading {
+                            ui.horizontal(|ui| {
+                                ui.spinner();
+                                ui.label("Loading PDF page...");
+                            });
+                        } else if let Some(error) = &self.pdf_viewer_state.error {
                             ui.colored_label(Color32::RED, error);
                         } else {
                             // PDF controls
@@ -1460,54 +3894,178 @@ impl<'a> App for FileGraphApp<'a> {
                             }
                         }
                     } else if is_image_path(&path) {
-                        if let Some(image) = &self.selected_image {
-                            egui::ScrollArea::vertical().show(ui, |ui| {
-                                // Show image dimensions
-                                let size = image.size_vec2();
-                                ui.label(format!("Dimensions: {} × {} px", size.x, size.y));
-                                ui.add_space(10.0);
-                                ui.add(egui::Image::new(image).max_size(size));
+                        let now = ctx.input(|i| i.time);
+                        let displayed_image = match self.selected_animation.as_mut() {
+                            Some(animation) => {
+                                let (texture, until_next) = animation.show_frame(now);
+                                ctx.request_repaint_after(Duration::from_secs_f64(until_next));
+                                Some(texture.clone())
+                            }
+                            None => self.selected_image.clone(),
+                        };
+                        if let Some(image) = &displayed_image {
+                            // Show image dimensions
+                            let size = image.size_vec2();
+                            ui.label(format!("Dimensions: {} × {} px", size.x, size.y));
+
+                            let view = self.image_view_states.entry(path.clone()).or_default();
+                            ui.horizontal(|ui| {
+                                if ui.button("-").clicked() {
+                                    view.zoom = (view.zoom / 1.25).max(0.05);
+                                    view.fit_to_panel = false;
+                                }
+                                ui.label(format!("{:.0}%", view.zoom * 100.0));
+                                if ui.button("+").clicked() {
+                                    view.zoom = (view.zoom * 1.25).min(20.0);
+                                    view.fit_to_panel = false;
+                                }
+                                if ui.button("Fit").clicked() {
+                                    view.fit_to_panel = true;
+                                    view.offset = egui::Vec2::ZERO;
+                                }
+                                if ui.button("1:1").clicked() {
+                                    view.zoom = 1.0;
+                                    view.fit_to_panel = false;
+                                    view.offset = egui::Vec2::ZERO;
+                                }
                             });
+                            ui.add_space(10.0);
+
+                            // Drag to pan, Ctrl+scroll to zoom
+                            let (viewport, response) =
+                                ui.allocate_exact_size(ui.available_size(), Sense::drag());
+                            if view.fit_to_panel {
+                                view.zoom = ImageViewState::fit_scale(size, viewport.size());
+                            }
+                            if response.hovered() {
+                                let zoom_delta = ctx.input(|i| i.zoom_delta());
+                                if zoom_delta != 1.0 {
+                                    view.zoom = (view.zoom * zoom_delta).clamp(0.05, 20.0);
+                                    view.fit_to_panel = false;
+                                }
+                            }
+                            if response.dragged() {
+                                view.offset += response.drag_delta();
+                            }
+
+                            let image_rect = egui::Rect::from_center_size(
+                                viewport.center() + view.offset,
+                                size * view.zoom,
+                            );
+                            ui.painter_at(viewport).image(
+                                image.id(),
+                                image_rect,
+                                egui::Rect::from_min_max(pos2(0.0, 0.0), pos2(1.0, 1.0)),
+                                Color32::WHITE,
+                            );
                         }
-                    } else if let Some(content) = &self.selected_file_content {
-                        if self.is_markdown_file() {
-                            egui::ScrollArea::vertical().show(ui, |ui| {
-                                CommonMarkViewer::new().show(ui, &mut self.markdown_cache, content);
-                            });
-                        } else if self.is_code_file() {
-                            let content_clone = content.clone();
-                            self.render_code_with_syntax_highlighting(ui, &content_clone);
-                        } else {
-                            egui::ScrollArea::vertical().show(ui, |ui| {
-                                ui.label(content);
-                            });
+                    } else if let Some(content) = self.selected_file_content.clone() {
+                        self.find_in_file_bar(ui, &content);
+                        let searching = !self.content_find_query.is_empty();
+
+                        match TextView::choose(
+                            self.is_markdown_file(),
+                            self.is_code_file(),
+                            searching,
+                        ) {
+                            TextView::MarkdownSource => {
+                                egui::ScrollArea::vertical().show(ui, |ui| {
+                                    let syntax = SYNTAX_SET.find_syntax_by_extension("md");
+                                    self.show_searchable_text(
+                                        ui,
+                                        &content,
+                                        syntax,
+                                        egui::TextStyle::Monospace,
+                                    );
+                                });
+                            }
+                            TextView::Markdown => {
+                                let segments = split_markdown_code_fences(&content);
+                                egui::ScrollArea::vertical().show(ui, |ui| {
+                                    for segment in segments {
+                                        match segment {
+                                            MarkdownSegment::Text(text) => {
+                                                CommonMarkViewer::new().show(
+                                                    ui,
+                                                    &mut self.markdown_cache,
+                                                    &text,
+                                                );
+                                            }
+                                            MarkdownSegment::Code { language, code } => {
+                                                let syntax =
+                                                    self.get_syntax_for_language(&language);
+                                                self.render_code_block(ui, &code, syntax);
+                                            }
+                                        }
+                                    }
+                                });
+                            }
+                            TextView::Code => {
+                                self.render_code_with_syntax_highlighting(ui, &content);
+                            }
+                            TextView::SearchableText => {
+                                egui::ScrollArea::vertical().show(ui, |ui| {
+                                    self.show_searchable_text(
+                                        ui,
+                                        &content,
+                                        None,
+                                        egui::TextStyle::Body,
+                                    );
+                                });
+                            }
+                            TextView::SelectableText => {
+                                egui::ScrollArea::vertical().show(ui, |ui| {
+                                    ui.add(
+                                        egui::TextEdit::multiline(&mut content.as_str())
+                                            .desired_width(f32::INFINITY)
+                                            .font(egui::TextStyle::Body),
+                                    );
+                                });
+                            }
                         }
                     }
                 } else {
                     ui.label("Select a file node to view its content.");
                 }
             });
+
+        // Overlay while files are dragged over the window
+        if ctx.input(|i| !i.raw.hovered_files.is_empty()) {
+            let painter = ctx.layer_painter(egui::LayerId::new(
+                egui::Order::Foreground,
+                egui::Id::new("file_drop_overlay"),
+            ));
+            let screen_rect = ctx.screen_rect();
+            painter.rect_filled(screen_rect, 0.0, Color32::from_black_alpha(160));
+            painter.text(
+                screen_rect.center(),
+                egui::Align2::CENTER_CENTER,
+                "Drop a folder to scan it, or a file to preview it",
+                egui::TextStyle::Heading.resolve(&ctx.style()),
+                Color32::WHITE,
+            );
+        }
     }
 }
 
 impl<'a> FileGraphApp<'a> {
-    pub fn new(scan_dir: PathBuf) -> Self {
+    pub fn new(scan_dir: PathBuf, settings: AppSettings) -> Self {
         let scanner = Arc::new(Mutex::new(FileScanner::new(&scan_dir)));
         let directory_tree = DirectoryNode::build_tree(&scan_dir);
         let (progress_sender, progress_receiver) = std::sync::mpsc::channel();
 
         let (page_render_sender, page_render_receiver) =
             mpsc::channel::<(PathBuf, usize, egui::TextureHandle, usize)>();
+        let (hover_preview_sender, hover_preview_receiver) = mpsc::channel();
 
-        // Initialize PDFium once when the app starts
-        let pdfium = Arc::new(Pdfium::new(
-            Pdfium::bind_to_system_library().expect("Failed to bind to system PDFium"),
-        ));
-
+        // Persisted fields start at their defaults and then take `settings`, the same
+        // path Reset Settings goes through
+        let defaults = AppSettings::default();
         let mut app = Self {
             scan_dir: scan_dir.clone(),
             show_directory_panel: true,
             directory_tree,
+            directory_filter: String::new(),
             selected_directory: None,
             current_scan_dir: scan_dir.clone(),
             scanner: scanner.clone(),
@@ -1519,48 +4077,147 @@ impl<'a> FileGraphApp<'a> {
             search_text: String::new(),
             filter_tags: String::new(),
             tag_graph: TagGraph::new(),
-            current_graph_mode: GraphMode::Links,
+            unified_graph: FileGraph::new(),
+            current_graph_mode: defaults.graph_mode,
             show_full_paths: false,
+            max_label_chars: 32,
+            highlight_shared_tags: false,
+            safe_mode: defaults.safe_mode,
+            search_history: defaults.search_history,
+            exclude_globs: Vec::new(),
+            exclude_glob_input: String::new(),
+            shared_tag_edges: true,
+            shared_tag_nodes: None,
+            label_truncation: LabelTruncation::Middle,
+            show_tag_badges: true,
+            focus_on_hover: false,
+            color_by_folder: false,
+            size_by_file_size: false,
+            size_by_degree: false,
+            orphans_only: false,
+            show_density_heatmap: false,
+            bundle_edges: false,
+            curved_edges: false,
+            placement_mode: false,
+            held_node: None,
+            graph_quality: GraphQuality::High,
+            show_edge_flow: false,
+            follow_selected: false,
+            followed_node: None,
+            bundling_strength: 0.6,
+            orphan_count: 0,
+            hovered_node: None,
+            visible_counts: (0, 0),
+            hover_started: None,
+            hover_previews: HashMap::new(),
+            hover_preview_sender,
+            hover_preview_receiver,
             physics_simulator: PhysicsSimulator::new(),
+            physics_clock: FixedTimestep::new(60.0),
             // show_physics_menu: false,
-            show_physics_window: true,
+            show_physics_window: defaults.show_physics_window,
+            show_display_window: false,
+            show_settings_window: false,
+            settings_tab: SettingsTab::Scan,
+            saved_chrome: None,
+            confirm_reset_settings: false,
+            reset_clears_search_history: false,
+            edge_style: EdgeStyle::default(),
+            snapshot_scale: 1.0,
+            last_picture: GraphPicture::default(),
+            label_scale: 1.0,
+            scale_labels_with_zoom: false,
+            content_font_size: defaults.content_font_size,
+            content_line_spacing: defaults.content_line_spacing,
+            content_find_query: String::new(),
+            content_find_index: 0,
+            content_find_scroll: false,
             is_scanning: false,
+            has_scanned: false,
             scan_error: None,
             selected_node: None,
+            pending_selection: settings.selected_node.clone(),
+            previewed_file: None,
             selected_file_content: None,
             selected_image: None,
+            selected_animation: None,
+            image_view_states: HashMap::new(),
             tag_filter_input: String::new(),
+            tag_completion_index: 0,
+            min_connections: 0,
+            isolated_node: None,
+            show_tag_cooccurrence: false,
+            show_untagged_files: false,
+            duplicate_groups: Vec::new(),
+            duplicate_receiver: None,
+            show_duplicates_window: false,
+            show_cycles_window: false,
+            scan_snapshots: ScanSnapshots::default(),
+            show_scan_stats_window: false,
+            scan_stats: None,
+            highlighted_cycle: None,
+            show_scan_errors_window: false,
+            keybindings: defaults.keybindings,
+            show_keybindings_window: false,
+            command_palette: CommandPalette::default(),
+            recent_window: RecentWindow::All,
+            export_positions: true,
+            imported_graph: None,
+            freeze_when_settled: defaults.freeze_when_settled,
+            awaiting_settle: false,
+            watch_for_changes: false,
+            watcher: None,
+            rescan_debouncer: RescanDebouncer::new(
+                Duration::from_millis(defaults.watch_debounce_ms),
+                defaults.batch_watch_events,
+            ),
+            rebinding_action: None,
+            max_graph_nodes: defaults.max_graph_nodes,
+            cooccurrence_threshold: 2,
+            isolate_depth: 1,
             initial_node_layout: HashMap::new(),
             graph_center_offset: egui::Vec2::ZERO,
             graph_zoom_factor: 1.0,
             dragged_node: None,
+            node_drag_threshold: defaults.node_drag_threshold,
             last_drag_pos: None,
             current_directory_label: scan_dir.display().to_string(),
             show_images: true,
+            images_affect_layout: true,
             // show_orphans: true,
             show_hidden_files: false,
+            parse_code_imports: false,
+            max_scan_depth: defaults.max_scan_depth,
+            max_scan_file_size_mb: defaults.max_scan_file_size_mb,
+            collapse_directories: false,
+            respect_gitignore: defaults.respect_gitignore,
             graph_rect: egui::Rect::NOTHING,
             markdown_cache: egui_commonmark::CommonMarkCache::default(),
             scan_progress: 0.0,
             scan_status: String::new(),
-            graph_build_progress: 0.0,
+            graph_build_progress: 1.0,
             graph_build_status: "Ready".to_string(),
+            graph_build_receiver: None,
+            graph_build_cancel: Arc::new(AtomicBool::new(false)),
             scan_sender: Some(progress_sender),
             scan_progress_receiver: Some(progress_receiver),
             search_query: String::new(),
             search_results: Vec::new(),
             current_search_result: 0,
             open_menu_on_node: None,
+            new_tag_input: String::new(),
+            rename_input: String::new(),
+            edit_history: Vec::new(),
             right_click_menu_pos: None,
             menu_open: false,
             syntax_cache: HashMap::new(),
             markdown_syntax: SYNTAX_SET.find_syntax_by_extension("md").cloned(),
-            show_content_panel: true,
+            show_content_panel: defaults.show_content_panel,
             cancel_sender: None,
             scan_thread_handle: None,
             state: AppState::Idle,
             pdf_file_data: HashMap::new(),
-            // pdfium_instance: pdfium,
+            pdfium_status: PdfiumStatus::default(),
             pdf_viewer_state: PdfViewerState {
                 zoom_level: 1.0,
                 render_quality: RenderQuality::Normal,
@@ -1573,6 +4230,8 @@ impl<'a> FileGraphApp<'a> {
             selected_text: None,
         };
 
+        app.apply_settings(settings);
+
         if let Some(initial_scan_path) = app.selected_directory.clone() {
             app.trigger_scan(initial_scan_path.clone(), &egui::Context::default());
         }
@@ -1586,6 +4245,79 @@ impl<'a> FileGraphApp<'a> {
         (adjusted.clamp(0.0, 1.0) * 255.0) as u8
     }
 
+    // Rescan the current directory and merge it into the graph, keeping the layout
+    fn rescan_in_place(&mut self) {
+        self.scan_error = None;
+        self.is_scanning = true;
+        self.scan_progress = 0.0;
+        self.scan_status = "Starting scan...".to_string();
+
+        let scan_dir = self
+            .selected_directory
+            .clone()
+            .unwrap_or_else(|| self.scan_dir.clone());
+        self.current_scan_dir = scan_dir.clone();
+
+        let scanner_arc_clone = self.scanner.clone();
+        let (progress_sender, progress_receiver) = std::sync::mpsc::channel();
+
+        thread::spawn(move || {
+            // Scan into a detached scanner so the UI never waits on the lock. Unchanged
+            // files keep their earlier results, so only edited notes are read again.
+            let mut scanned = scanner_arc_clone.lock().unwrap().incremental();
+            scanned.stream_into(scanner_arc_clone.clone());
+            let result = scanned.scan_directory_with_progress(&scan_dir, progress_sender.clone());
+            scanner_arc_clone
+                .lock()
+                .unwrap()
+                .merge_scan(&scan_dir, scanned);
+            if let Err(e) = result {
+                eprintln!("Error during scan: {}", e);
+            }
+            let _ = progress_sender.send((1.0, "Scan complete".to_string()));
+        });
+
+        self.scan_progress_receiver = Some(progress_receiver);
+    }
+
+    // Feed file changes into the debouncer and rescan once a burst of them is over
+    fn poll_watcher(&mut self, ctx: &egui::Context) {
+        if !self.watch_for_changes {
+            self.watcher = None;
+            return;
+        }
+        if self
+            .watcher
+            .as_ref()
+            .is_none_or(|watcher| watcher.root != self.current_scan_dir)
+        {
+            // Stop the old watcher before starting one with the current scan rules
+            self.watcher = None;
+            let Ok(scanner) = self.scanner.lock().map(|scanner| scanner.detached()) else {
+                return;
+            };
+            self.watcher = Some(spawn_poll_watcher(
+                scanner,
+                self.current_scan_dir.clone(),
+                WATCH_POLL_INTERVAL,
+            ));
+        }
+        if let Some(watcher) = &self.watcher {
+            let now = Instant::now();
+            while watcher.changes.try_recv().is_ok() {
+                self.rescan_debouncer.record(now);
+            }
+        }
+        if self.rescan_debouncer.is_pending() {
+            if !self.is_scanning && self.rescan_debouncer.poll(Instant::now()) {
+                self.rescan_in_place();
+            }
+            ctx.request_repaint_after(Duration::from_millis(100));
+        } else {
+            ctx.request_repaint_after(WATCH_POLL_INTERVAL);
+        }
+    }
+
     fn trigger_scan(&mut self, path_to_scan: PathBuf, ctx: &egui::Context) {
         self.cancel_scan();
 
@@ -1594,7 +4326,7 @@ impl<'a> FileGraphApp<'a> {
             return;
         }
 
-        if !path_to_scan.is_dir() {
+        if !is_scan_root(&path_to_scan) {
             self.state = AppState::Error("Selected path is not a directory".to_string());
             return;
         }
@@ -1617,25 +4349,45 @@ impl<'a> FileGraphApp<'a> {
         let scanner_arc_clone = self.scanner.clone();
         let ctx_clone = ctx.clone();
         let show_hidden_clone = self.show_hidden_files;
+        let parse_code_imports = self.parse_code_imports;
+        let max_scan_depth = self.max_scan_depth;
+        let max_file_size = self.max_scan_file_size();
+        let respect_gitignore = self.respect_gitignore;
 
         self.scan_thread_handle = Some(thread::spawn(move || {
             if cancel_receiver.try_recv().is_ok() {
                 return;
             }
 
-            match scanner_arc_clone.lock() {
+            // Only lock briefly: to copy settings, then to merge the results
+            let mut scanned = match scanner_arc_clone.lock() {
                 Ok(mut scanner_guard) => {
                     scanner_guard.set_show_hidden(show_hidden_clone);
-                    match scanner_guard.scan_directory_with_progress(&path_to_scan, progress_sender)
-                    {
-                        Ok(_) => println!("Scan completed successfully"),
-                        Err(e) => eprintln!("Scan error: {}", e),
-                    }
+                    scanner_guard.parse_code_imports = parse_code_imports;
+                    scanner_guard.set_max_depth(max_scan_depth);
+                    scanner_guard.set_max_file_size(max_file_size);
+                    scanner_guard.respect_gitignore = respect_gitignore;
+                    scanner_guard.detached()
                 }
-                Err(e) => eprintln!("Failed to lock scanner: {}", e),
-            }
-
-            ctx_clone.request_repaint();
+                Err(e) => {
+                    eprintln!("Failed to lock scanner: {}", e);
+                    return;
+                }
+            };
+            scanned.stream_into(scanner_arc_clone.clone());
+            let result =
+                scanned.scan_directory_with_progress(&path_to_scan, progress_sender.clone());
+            match scanner_arc_clone.lock() {
+                Ok(mut scanner_guard) => scanner_guard.merge_scan(&path_to_scan, scanned),
+                Err(e) => eprintln!("Failed to lock scanner: {}", e),
+            }
+            match result {
+                Ok(_) => println!("Scan completed successfully"),
+                Err(e) => eprintln!("Scan error: {}", e),
+            }
+            let _ = progress_sender.send((1.0, "Scan complete".to_string()));
+
+            ctx_clone.request_repaint();
         }));
     }
 
@@ -1657,35 +4409,6 @@ impl<'a> FileGraphApp<'a> {
         }
     }
 
-    // Helper to build the directory tree
-    fn build_directory_tree(path: &Path) -> Option<DirectoryNode> {
-        if !path.is_dir() {
-            return None;
-        }
-
-        let mut node = DirectoryNode {
-            path: path.to_path_buf(),
-            children: Vec::new(),
-            expanded: false,
-            selected: false,
-        };
-
-        if let Ok(entries) = fs::read_dir(path) {
-            let mut sorted_entries: Vec<_> = entries.filter_map(|e| e.ok()).collect();
-            sorted_entries.sort_by_key(|e| e.path()); // Sort entries for consistent order
-
-            for entry in sorted_entries {
-                let entry_path = entry.path();
-                if entry_path.is_dir() {
-                    if let Some(child_node) = Self::build_directory_tree(&entry_path) {
-                        node.children.push(child_node);
-                    }
-                }
-            }
-        }
-        Some(node)
-    }
-
     fn load_and_render_pdf_page(&mut self, ctx: &egui::Context, path: PathBuf, page_idx: usize) {
         // Check cache first
         if let Some(texture) = self.pdf_viewer_state.page_cache.get(&page_idx) {
@@ -1701,6 +4424,13 @@ impl<'a> FileGraphApp<'a> {
         self.pdf_viewer_state.loading = true;
         self.pdf_viewer_state.error = None;
 
+        // PDFium is only bound once a PDF is actually opened
+        if let Err(e) = self.pdfium_status.ensure() {
+            self.pdf_viewer_state.error = Some(e);
+            self.pdf_viewer_state.loading = false;
+            return;
+        }
+
         let ctx_clone = ctx.clone();
         let render_sender = self
             .pdf_viewer_state
@@ -1713,10 +4443,10 @@ impl<'a> FileGraphApp<'a> {
         let path_clone = path.to_path_buf();
 
         thread::spawn(move || {
-            let pdfium = match Pdfium::bind_to_system_library() {
-                Ok(bindings) => Pdfium::new(bindings),
+            let pdfium = match bind_pdfium() {
+                Ok(pdfium) => pdfium,
                 Err(e) => {
-                    eprintln!("Failed to bind to PDFium: {:?}", e);
+                    eprintln!("Failed to bind to PDFium: {}", e);
                     ctx_clone.request_repaint();
                     return;
                 }
@@ -2027,122 +4757,6 @@ impl<'a> FileGraphApp<'a> {
         }
     }
 
-    fn display_directory_node(&mut self, ui: &mut egui::Ui, node: &mut DirectoryNode) {
-        ui.indent("dir_indent", |ui| {
-            ui.horizontal(|ui| {
-                let dir_name = node
-                    .path
-                    .file_name()
-                    .map_or(".".into(), |n| n.to_string_lossy().into_owned());
-                let expanded = node.expanded;
-                let response =
-                    ui.toggle_value(&mut node.expanded, if expanded { "📂" } else { "📁" });
-                if response.clicked() {
-                    // If directory is expanded/collapsed, no need to select it
-                }
-
-                let label_response = ui.label(dir_name).interact(Sense::click());
-                if label_response.clicked() {
-                    // Clicking the label should also toggle expansion
-                    node.expanded = !node.expanded;
-                }
-            });
-
-            if node.expanded {
-                let mut files_and_subdirs: Vec<PathBuf> = Vec::new();
-                if let Ok(entries) = fs::read_dir(&node.path) {
-                    for entry in entries.filter_map(|e| e.ok()) {
-                        let path = entry.path();
-                        if !self.scanner.lock().unwrap().show_hidden
-                            && path
-                                .file_name()
-                                .map_or(false, |name| name.to_string_lossy().starts_with("."))
-                        {
-                            continue;
-                        }
-                        files_and_subdirs.push(path);
-                    }
-                }
-                files_and_subdirs.sort_by_key(|p| (p.is_file(), p.clone())); // Sort: directories first, then files, then alphabetically
-
-                for entry_path in files_and_subdirs {
-                    if entry_path.is_dir() {
-                        if let Some(child_node) =
-                            node.children.iter_mut().find(|n| n.path == entry_path)
-                        {
-                            self.display_directory_node(ui, child_node);
-                        }
-                    } else if entry_path.is_file() {
-                        let file_name = entry_path
-                            .file_name()
-                            .map_or("".into(), |n| n.to_string_lossy().into_owned());
-                        ui.horizontal(|ui| {
-                            let is_selected = self
-                                .selected_file_content
-                                .as_ref()
-                                .map(|s| PathBuf::from(s))
-                                == Some(entry_path.clone());
-                            let label_text = if is_selected {
-                                egui::RichText::new(file_name)
-                                    .strong()
-                                    .color(ui.visuals().selection.stroke.color)
-                            } else {
-                                egui::RichText::new(file_name)
-                            };
-
-                            if ui.selectable_label(is_selected, label_text).clicked() {
-                                self.selected_file_content = Some(entry_path.display().to_string());
-                                self.file_content = None; // Clear previous content
-                                self.image_content = None; // Clear previous image
-                                // Clear PDF state if new file is not PDF or is a different PDF
-                                if !is_pdf_path(&entry_path)
-                                    || self.pdf_viewer_state.current_pdf_path.as_ref()
-                                        != Some(&entry_path)
-                                {
-                                    self.pdf_viewer_state = Default::default();
-                                    // Re-establish channels as they are None after Default::default()
-                                    let (sender, receiver) = mpsc::channel();
-                                    self.pdf_viewer_state.page_render_sender = Some(sender);
-                                    self.pdf_viewer_state.page_render_receiver = Some(receiver);
-                                }
-
-                                // Load PDF metadata if it's a PDF
-                                if is_pdf_path(&entry_path)
-                                    && !self.pdf_file_data.contains_key(&entry_path)
-                                {
-                                    let path_clone = entry_path.clone();
-                                    let ctx_clone = ui.ctx().clone();
-                                    thread::spawn(move || {
-                                        // Create new PDFium instance in the thread
-                                        let pdfium = match Pdfium::bind_to_system_library() {
-                                            Ok(bindings) => Pdfium::new(bindings),
-                                            Err(e) => {
-                                                eprintln!("Failed to bind to PDFium: {:?}", e);
-                                                return;
-                                            }
-                                        };
-
-                                        match pdfium.load_pdf_from_file(&path_clone, None) {
-                                            Ok(document) => {
-                                                let metadata = document.metadata();
-                                                let pages: Vec<PdfPage> =
-                                                    document.pages().iter().collect();
-                                                // Store or process the metadata as needed
-                                                ctx_clone.request_repaint();
-                                            }
-                                            Err(e) => {
-                                                eprintln!("Failed to load PDF: {:?}", e);
-                                            }
-                                        }
-                                    });
-                                }
-                            }
-                        });
-                    }
-                }
-            }
-        });
-    }
     // Helper function to clear graph data
     fn clear_graph_data(&mut self) {
         // Clear physics data
@@ -2153,15 +4767,29 @@ impl<'a> FileGraphApp<'a> {
         // Clear graph structures
         self.file_graph.graph.clear();
         self.file_graph.node_indices.clear();
+        self.file_graph.external_node_indices.clear();
+        self.file_graph.directory_node_indices.clear();
         self.tag_graph.graph.clear();
         self.tag_graph.file_node_indices.clear();
         self.tag_graph.tag_node_indices.clear();
         self.tag_graph.image_node_indices.clear();
+        self.unified_graph = FileGraph::new();
+        // A build still running would bring the old graphs back
+        self.graph_build_cancel.store(true, Ordering::Relaxed);
+        // Restarted by poll_watcher for whatever folder is scanned next
+        self.watcher = None;
+        self.graph_build_receiver = None;
+        self.graph_build_progress = 1.0;
 
         // Clear UI state
         self.selected_node = None;
+        self.previewed_file = None;
+        self.isolated_node = None;
+        self.hover_previews.clear();
+        self.duplicate_groups.clear();
         self.selected_file_content = None;
         self.selected_image = None;
+        self.selected_animation = None;
         self.search_results.clear();
         self.current_search_result = 0;
     }
@@ -2175,47 +4803,106 @@ impl<'a> FileGraphApp<'a> {
                         self.scan_status = status;
                         if progress >= 1.0 {
                             self.state = AppState::BuildingGraph;
-                            if let Err(e) = self.build_graphs() {
-                                self.state = AppState::Error(e);
-                            }
+                            self.build_graphs(ctx);
                         }
                         ctx.request_repaint();
                     }
                 }
             }
             AppState::BuildingGraph => {
-                if self.graph_build_progress >= 1.0 {
-                    self.state = AppState::Ready;
-                    ctx.request_repaint();
+                let Some(receiver) = self.graph_build_receiver.take() else {
+                    return;
+                };
+                ctx.request_repaint();
+                loop {
+                    match receiver.try_recv() {
+                        Ok(GraphBuildMessage::Progress(progress, status)) => {
+                            self.graph_build_progress = progress;
+                            self.graph_build_status = status;
+                        }
+                        Ok(GraphBuildMessage::Done(Ok(graphs))) => {
+                            self.install_graphs(graphs);
+                            self.state = AppState::Ready;
+                            return;
+                        }
+                        Ok(GraphBuildMessage::Done(Err(e))) => {
+                            self.graph_build_progress = 1.0;
+                            self.state = AppState::Error(e);
+                            return;
+                        }
+                        Err(mpsc::TryRecvError::Empty) => break,
+                        Err(mpsc::TryRecvError::Disconnected) => {
+                            self.graph_build_progress = 1.0;
+                            self.state = AppState::Error("Graph building stopped".to_string());
+                            return;
+                        }
+                    }
                 }
+                self.graph_build_receiver = Some(receiver);
             }
             AppState::Ready | AppState::Idle | AppState::Error(_) => {}
         }
     }
 
-    fn build_graphs(&mut self) -> Result<(), String> {
+    // Rebuild every graph on a worker thread; update_ui_state shows its progress and
+    // installs the result
+    fn build_graphs(&mut self, ctx: &egui::Context) {
         self.graph_build_progress = 0.0;
         self.graph_build_status = "Building graphs...".to_string();
 
-        let scanner_guard = match self.scanner.lock() {
-            Ok(guard) => guard,
-            Err(_) => return Err("Failed to lock scanner".to_string()),
-        };
-
-        // Clear old graphs before rebuilding
-        self.file_graph.graph.clear();
-        self.file_graph.node_indices.clear();
-        self.file_graph.build_from_scanner(&scanner_guard);
-
-        self.graph_build_progress = 0.5;
-        self.graph_build_status = "Building tag graph...".to_string();
+        // Only one build at a time; an older one stops and its result is dropped
+        self.graph_build_cancel.store(true, Ordering::Relaxed);
+        let cancel = Arc::new(AtomicBool::new(false));
+        self.graph_build_cancel = cancel.clone();
+
+        let scanner = self.scanner.clone();
+        let directory_grouping = self.file_graph.directory_grouping.clone();
+        let show_untagged_files = self.show_untagged_files;
+        let max_nodes = self.max_graph_nodes;
+        let ctx = ctx.clone();
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            // Copy the scan results and let go of the lock, so the UI isn't blocked
+            // for the whole build
+            let scanner = match scanner.lock() {
+                Ok(scanner) => scanner.snapshot(),
+                Err(_) => {
+                    let _ = sender.send(GraphBuildMessage::Done(Err(
+                        "Failed to lock scanner".to_string()
+                    )));
+                    return;
+                }
+            };
+            // Whole percents only, so a large graph doesn't flood the channel
+            let mut last_sent = -1.0;
+            let graphs = build_all_graphs(
+                &scanner,
+                directory_grouping,
+                show_untagged_files,
+                max_nodes,
+                &cancel,
+                &mut |progress, status| {
+                    if progress - last_sent >= 0.01 {
+                        last_sent = progress;
+                        let _ =
+                            sender.send(GraphBuildMessage::Progress(progress, status.to_string()));
+                        ctx.request_repaint();
+                    }
+                },
+            );
+            if let Some(graphs) = graphs {
+                let _ = sender.send(GraphBuildMessage::Done(Ok(Box::new(graphs))));
+                ctx.request_repaint();
+            }
+        });
+        self.graph_build_receiver = Some(receiver);
+    }
 
-        // Clear old tag graph before rebuilding
-        self.tag_graph.graph.clear();
-        self.tag_graph.file_node_indices.clear();
-        self.tag_graph.tag_node_indices.clear();
-        self.tag_graph.image_node_indices.clear();
-        self.tag_graph.build_from_tags(&scanner_guard);
+    // Swap in freshly built graphs with a new random layout
+    fn install_graphs(&mut self, graphs: Box<BuiltGraphs>) {
+        self.file_graph = graphs.file_graph;
+        self.tag_graph = graphs.tag_graph;
+        self.unified_graph = graphs.unified_graph;
 
         // Calculate initial layout for physics simulation
         self.initial_node_layout.clear();
@@ -2223,7 +4910,7 @@ impl<'a> FileGraphApp<'a> {
         let graph_center = self.graph_rect.center();
         let radius = self.graph_rect.width().min(self.graph_rect.height()) / 3.0;
 
-        // Use the combined nodes from both graphs to initialize physics
+        // Use the combined nodes from all graphs to initialize physics
         let mut all_node_indices: HashMap<NodeIndex, GraphNode> = HashMap::new();
         for (idx, node) in self.file_graph.graph.node_weights().enumerate() {
             all_node_indices.insert(NodeIndex::new(idx), node.clone());
@@ -2231,6 +4918,9 @@ impl<'a> FileGraphApp<'a> {
         for (idx, node) in self.tag_graph.graph.node_weights().enumerate() {
             all_node_indices.insert(NodeIndex::new(idx), node.clone());
         }
+        for (idx, node) in self.unified_graph.graph.node_weights().enumerate() {
+            all_node_indices.insert(NodeIndex::new(idx), node.clone());
+        }
 
         for (node_idx, _) in &all_node_indices {
             let angle = rng.random_range(0.0..std::f32::consts::TAU);
@@ -2244,8 +4934,6 @@ impl<'a> FileGraphApp<'a> {
 
         self.graph_build_progress = 1.0;
         self.graph_build_status = "Graph ready".to_string();
-
-        Ok(())
     }
 
     fn draw_directory_node_recursive(
@@ -2277,36 +4965,7 @@ impl<'a> FileGraphApp<'a> {
         self.collect_selected_paths(&self.directory_tree, &mut selected_paths);
 
         if !selected_paths.is_empty() {
-            self.scan_error = None;
-            self.is_scanning = true;
-            self.scan_progress = 0.0;
-            self.scan_status = "Starting scan...".to_string();
-
-            // Clear old physics data
-            self.physics_simulator.node_positions.clear();
-            self.physics_simulator.node_velocities.clear();
-            self.initial_node_layout.clear();
-
-            let scanner_arc_clone = self.scanner.clone();
-            let (progress_sender, progress_receiver) = std::sync::mpsc::channel();
-
-            thread::spawn(move || {
-                let mut scanner = scanner_arc_clone.lock().unwrap();
-                // Clear previous results before scanning new directories
-                scanner.files.clear();
-                scanner.tags.clear();
-                scanner.images.clear();
-
-                for path in selected_paths {
-                    if let Err(e) =
-                        scanner.scan_directory_with_progress(&path, progress_sender.clone())
-                    {
-                        eprintln!("Error scanning {}: {}", path.display(), e);
-                    }
-                }
-            });
-
-            self.scan_progress_receiver = Some(progress_receiver);
+            self.start_scan(selected_paths);
         } else {
             // If no directories selected, clear everything
             self.scanner.lock().unwrap().files.clear();
@@ -2317,168 +4976,1085 @@ impl<'a> FileGraphApp<'a> {
             self.initial_node_layout.clear();
             self.file_graph.graph.clear();
             self.file_graph.node_indices.clear();
+            self.file_graph.external_node_indices.clear();
+            self.file_graph.directory_node_indices.clear();
             self.tag_graph.graph.clear();
             self.tag_graph.file_node_indices.clear();
             self.tag_graph.tag_node_indices.clear();
             self.tag_graph.image_node_indices.clear();
+            self.unified_graph = FileGraph::new();
 
             self.scan_error = Some("No directories selected for scanning".to_string());
         }
     }
 
-    fn collect_selected_paths(&self, node: &DirectoryNode, paths: &mut Vec<PathBuf>) {
-        if node.selected {
-            paths.push(node.path.clone());
-        }
-        for child in &node.children {
-            self.collect_selected_paths(child, paths);
-        }
-    }
+    fn start_scan(&mut self, selected_paths: Vec<PathBuf>) {
+        self.is_scanning = true;
+        self.awaiting_settle = true;
+        self.physics_simulator.frozen = false;
+        self.imported_graph = None;
+        self.scan_progress = 0.0;
+        self.scan_status = "Starting scan...".to_string();
 
-    fn try_load_file_content(&mut self, path: PathBuf, ctx: &egui::Context) {
-        if is_pdf_path(&path) {
-            self.selected_file_content = Some("PDF Document".to_string());
-            self.selected_image = None;
+        // Clear old physics data
+        self.physics_simulator.node_positions.clear();
+        self.physics_simulator.node_velocities.clear();
+        self.physics_simulator.pinned_nodes.clear();
+        self.held_node = None;
+        self.initial_node_layout.clear();
 
-            // Initialize PDF viewer state
-            self.pdf_viewer_state = PdfViewerState {
-                zoom_level: 1.0,
-                render_quality: RenderQuality::Normal,
-                page_cache: HashMap::new(),
-                page_render_sender: self.pdf_viewer_state.page_render_sender.take(),
-                page_render_receiver: self.pdf_viewer_state.page_render_receiver.take(),
-                ..Default::default()
-            };
+        let scanner_arc_clone = self.scanner.clone();
+        let (progress_sender, progress_receiver) = std::sync::mpsc::channel();
 
-            // Load the first page
-            self.load_and_render_pdf_page(ctx, path.clone(), 0);
+        thread::spawn(move || {
+            // Clear previous results before scanning new directories
+            let template = {
+                let mut scanner = scanner_arc_clone.lock().unwrap();
+                scanner.clear();
+                scanner.detached()
+            };
 
-            // Extract text in background
-            let path_clone = path.clone();
-            let ctx_clone = ctx.clone();
-            thread::spawn(move || {
-                match pdf_utils::extract_text_with_layout(&path_clone) {
-                    Ok(blocks) => {
-                        // Process text blocks and send back to UI thread
-                        ctx_clone.request_repaint();
-                    }
-                    Err(e) => {
-                        eprintln!("Failed to extract text: {}", e);
-                    }
-                }
-            });
-        } else if is_image_path(&path) {
-            match image::open(&path) {
-                Ok(img) => {
-                    let rgba_image = img.into_rgba8();
-                    let pixels: Vec<u8> = rgba_image.as_flat_samples().as_slice().to_vec();
-                    let image_size = [rgba_image.width() as _, rgba_image.height() as _];
-                    let image_data = egui::ColorImage::from_rgba_unmultiplied(image_size, &pixels);
-                    self.selected_image = Some(ctx.load_texture(
-                        path.to_string_lossy(),
-                        image_data,
-                        Default::default(),
-                    ));
-                    self.selected_file_content = None;
-                }
-                Err(e) => {
-                    self.selected_file_content = Some(format!("Failed to load image: {}", e));
-                    self.selected_image = None;
-                }
-            }
-        } else {
-            match std::fs::read_to_string(&path) {
-                Ok(content) => {
-                    self.selected_file_content = Some(content);
-                    self.selected_image = None;
-                }
-                Err(e) => {
-                    self.selected_file_content = Some(format!("Failed to read file: {}", e));
-                    self.selected_image = None;
+            for path in selected_paths {
+                let mut scanned = template.detached();
+                scanned.stream_into(scanner_arc_clone.clone());
+                let result = scanned.scan_directory_with_progress(&path, progress_sender.clone());
+                scanner_arc_clone.lock().unwrap().merge_scan(&path, scanned);
+                if let Err(e) = result {
+                    eprintln!("Error scanning {}: {}", path.display(), e);
                 }
             }
-        }
-    }
-
-    fn render_directory_tree_node(ui: &mut egui::Ui, node: &mut DirectoryNode) -> bool {
-        let mut changed = false;
-        let label = node.path.file_name().unwrap().to_string_lossy().to_string();
-
-        ui.horizontal(|ui| {
-            if ui.checkbox(&mut node.selected, "").changed() {
-                changed = true;
-            }
-
-            // Clickable label for expansion/collapse
-            if ui
-                .add(egui::Label::new(label).sense(Sense::click()))
-                .clicked()
-            {
-                node.expanded = !node.expanded;
-                changed = true;
-            }
+            let _ = progress_sender.send((1.0, "Scan complete".to_string()));
         });
 
-        if node.expanded {
-            ui.indent("dir_indent", |ui| {
-                for child in &mut node.children {
-                    if Self::render_directory_tree_node(ui, child) {
-                        changed = true;
-                    }
-                }
-            });
-        }
-
-        changed
+        self.scan_progress_receiver = Some(progress_receiver);
     }
 
-    fn focus_on_node(&mut self, node_idx: NodeIndex) {
-        if let Some(&node_pos) = self.physics_simulator.get_node_position(node_idx) {
-            let current_center_offset = self.graph_center_offset;
-            let target_center_offset = -node_pos; // Center the node at (0,0) in graph coordinates
-            self.graph_center_offset = target_center_offset;
-            self.graph_zoom_factor = 1.0; // Reset zoom to default
+    // Open dropped folders: a single folder becomes the new root
+    fn open_directories(&mut self, directories: Vec<PathBuf>) {
+        if self.is_scanning {
+            return;
         }
+        if let [directory] = directories.as_slice() {
+            self.scan_dir = directory.clone();
+            self.directory_tree = DirectoryNode::build_tree(directory);
+            self.selected_directory = None;
+            self.current_scan_dir = directory.clone();
+            self.current_directory_label = directory.display().to_string();
+        } else {
+            self.current_directory_label = directories
+                .iter()
+                .map(|d| d.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+        }
+        self.clear_graph_data();
+        self.start_scan(directories);
     }
 
-    fn center_graph(&mut self) {
-        self.graph_center_offset = egui::Vec2::ZERO;
-        self.graph_zoom_factor = 1.0;
-    }
+    fn export_tags(&mut self, inverted: bool) {
+        let default_name = if inverted {
+            "tags_to_files.json"
+        } else {
+            "files_to_tags.json"
+        };
+        let Some(target) = rfd::FileDialog::new()
+            .set_directory(&self.current_scan_dir)
+            .set_file_name(default_name)
+            .add_filter("JSON", &["json"])
+            .save_file()
+        else {
+            return;
+        };
 
-    fn perform_search(&mut self) {
-        self.search_results.clear();
-        self.current_search_result = 0;
+        let json = match self.scanner.lock() {
+            Ok(scanner) => scanner
+                .export_tags_json(inverted)
+                .map_err(|e| e.to_string()),
+            Err(_) => Err("Failed to lock scanner".to_string()),
+        };
+        if let Err(e) = json.and_then(|json| fs::write(&target, json).map_err(|e| e.to_string())) {
+            self.state = AppState::Error(format!("Failed to export tags: {}", e));
+        }
+    }
 
-        let query_lower = self.search_query.to_lowercase();
-        if query_lower.is_empty() {
+    // The current mode's graph, optionally with the physics layout
+    fn export_graph(&mut self, graphml: bool) {
+        let (default_name, filter_name, extension) = if graphml {
+            ("graph.graphml", "GraphML", "graphml")
+        } else {
+            ("graph.json", "JSON", "json")
+        };
+        let Some(target) = rfd::FileDialog::new()
+            .set_directory(&self.current_scan_dir)
+            .set_file_name(default_name)
+            .add_filter(filter_name, &[extension])
+            .save_file()
+        else {
             return;
-        }
+        };
 
-        let graph_to_search = match self.current_graph_mode {
+        let graph = match self.current_graph_mode {
             GraphMode::Links => &self.file_graph.graph,
             GraphMode::Tags => &self.tag_graph.graph,
+            GraphMode::Unified => &self.unified_graph.graph,
         };
-
-        for node_idx in graph_to_search.node_indices() {
-            let node_name = match &graph_to_search[node_idx] {
-                GraphNode::File(s) => PathBuf::from(s)
-                    .file_name()
-                    .map_or_else(|| s.clone(), |os_str| os_str.to_string_lossy().into_owned()),
-                GraphNode::Tag(s) => s.clone(),
-            };
-            if node_name.to_lowercase().contains(&query_lower) {
-                self.search_results.push(node_idx);
-            }
-        }
-
-        if !self.search_results.is_empty() {
-            self.selected_node = Some(self.search_results[0]);
-            self.focus_on_node(self.search_results[0]);
+        let positions = self
+            .export_positions
+            .then_some(&self.physics_simulator.node_positions);
+        let exported = ExportedGraph::from_graph(graph, positions);
+        let output = if graphml {
+            Ok(exported.to_graphml())
+        } else {
+            exported.to_json().map_err(|e| e.to_string())
+        };
+        if let Err(e) =
+            output.and_then(|output| fs::write(&target, output).map_err(|e| e.to_string()))
+        {
+            self.state = AppState::Error(format!("Failed to export graph: {}", e));
         }
     }
 
-    fn focus_next_search_result(&mut self) {
+    fn export_cooccurrence(&mut self, csv: bool) {
+        let (default_name, filter_name, extension) = if csv {
+            ("tag_cooccurrence.csv", "CSV", "csv")
+        } else {
+            ("tag_cooccurrence.json", "JSON", "json")
+        };
+        let Some(target) = rfd::FileDialog::new()
+            .set_directory(&self.current_scan_dir)
+            .set_file_name(default_name)
+            .add_filter(filter_name, &[extension])
+            .save_file()
+        else {
+            return;
+        };
+
+        let matrix = CooccurrenceMatrix::from_tags(&self.scanner.lock().unwrap().tags);
+        let output = if csv {
+            Ok(matrix.to_csv())
+        } else {
+            matrix.to_json().map_err(|e| e.to_string())
+        };
+        if let Err(e) =
+            output.and_then(|output| fs::write(&target, output).map_err(|e| e.to_string()))
+        {
+            self.state = AppState::Error(format!("Failed to export co-occurrence: {}", e));
+        }
+    }
+
+    // Show a JSON export in Links mode, laid out as it was exported
+    fn import_graph(&mut self) {
+        let Some(source) = rfd::FileDialog::new()
+            .set_directory(&self.current_scan_dir)
+            .add_filter("JSON", &["json"])
+            .pick_file()
+        else {
+            return;
+        };
+
+        let imported = fs::read_to_string(&source)
+            .map_err(|e| e.to_string())
+            .and_then(|json| ExportedGraph::from_json(&json).map_err(|e| e.to_string()))
+            .and_then(|exported| exported.to_file_graph());
+        let (file_graph, positions) = match imported {
+            Ok(imported) => imported,
+            Err(e) => {
+                self.state = AppState::Error(format!("Failed to import graph: {}", e));
+                return;
+            }
+        };
+
+        self.physics_simulator.node_positions.clear();
+        self.physics_simulator.node_velocities.clear();
+        self.initial_node_layout.clear();
+        for (&node_idx, &pos) in &positions {
+            self.physics_simulator.node_positions.insert(node_idx, pos);
+            self.physics_simulator
+                .node_velocities
+                .insert(node_idx, egui::Vec2::ZERO);
+            self.initial_node_layout.insert(node_idx, pos);
+        }
+        self.file_graph = file_graph;
+        self.collapse_directories = false;
+        self.current_graph_mode = GraphMode::Links;
+        self.selected_node = None;
+        self.isolated_node = None;
+        self.current_directory_label = format!("Imported: {}", source.display());
+        self.imported_graph = Some(source);
+    }
+
+    // Write `#tag` into the file and into the scanner, so the tag graph picks it up
+    fn add_tag_to_file(&mut self, path: &Path, ctx: &egui::Context) {
+        if self.safe_mode {
+            return;
+        }
+        if let Some(archive) = containing_archive(path) {
+            self.state =
+                AppState::Error(format!("Files inside {} are read-only", archive.display()));
+            return;
+        }
+        let tag = self
+            .new_tag_input
+            .trim()
+            .trim_start_matches('#')
+            .to_lowercase();
+        self.new_tag_input.clear();
+        if tag.is_empty() || !tag.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            self.state = AppState::Error(format!("\"{}\" is not a valid tag", tag));
+            return;
+        }
+
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) => {
+                self.state = AppState::Error(format!("Failed to add tag: {}", e));
+                return;
+            }
+        };
+        // Already tagged
+        let Some(updated) = append_tag(&content, &tag) else {
+            return;
+        };
+        match file_edits::rewrite_file(path, content, &updated) {
+            Ok(rewrite) => self.push_edit(FileEdit::AddTag {
+                tag: tag.clone(),
+                rewrite,
+            }),
+            Err(e) => {
+                self.state = AppState::Error(format!("Failed to add tag: {}", e));
+                return;
+            }
+        }
+
+        if let Ok(mut scanner) = self.scanner.lock() {
+            scanner
+                .tags
+                .entry(path.to_path_buf())
+                .or_default()
+                .push(tag);
+            scanner.refresh_metadata(path);
+        }
+        if self.selected_file_path().as_deref() == Some(path) {
+            self.try_load_file_content(path.to_path_buf(), ctx);
+        }
+    }
+
+    // Draw `picture` offscreen, cropped to `frame`, and put it on the clipboard
+    fn copy_picture(&mut self, picture: &GraphPicture, frame: egui::Rect, pixels_per_point: f32) {
+        if !frame.is_positive() {
+            return;
+        }
+        let Some(font_bytes) = default_font_bytes() else {
+            self.state = AppState::Error("No font available to draw labels with".to_string());
+            return;
+        };
+        let font = match ab_glyph::FontRef::try_from_slice(&font_bytes) {
+            Ok(font) => font,
+            Err(e) => {
+                self.state = AppState::Error(format!("Failed to load label font: {}", e));
+                return;
+            }
+        };
+        let image = picture.render(frame, pixels_per_point, &font);
+        // The clipboard wants straight alpha
+        let bytes: Vec<u8> = image
+            .pixels
+            .iter()
+            .flat_map(|pixel| pixel.to_srgba_unmultiplied())
+            .collect();
+        let result = arboard::Clipboard::new().and_then(|mut clipboard| {
+            clipboard.set_image(arboard::ImageData {
+                width: image.size[0],
+                height: image.size[1],
+                bytes: bytes.into(),
+            })
+        });
+        if let Err(e) = result {
+            self.state = AppState::Error(format!("Failed to copy image: {}", e));
+        }
+    }
+
+    fn push_edit(&mut self, edit: FileEdit) {
+        if self.edit_history.len() == MAX_EDIT_HISTORY {
+            self.edit_history.remove(0);
+        }
+        self.edit_history.push(edit);
+    }
+
+    // True when `path` can't be changed from the graph right now
+    fn file_edit_blocked(&mut self, path: &Path) -> bool {
+        if self.safe_mode || self.is_scanning {
+            return true;
+        }
+        if let Some(archive) = containing_archive(path) {
+            self.state =
+                AppState::Error(format!("Files inside {} are read-only", archive.display()));
+            return true;
+        }
+        false
+    }
+
+    // Create, delete and rename change which files exist, so the graph is rescanned
+    fn apply_structural_edit(&mut self, result: Result<FileEdit, String>) {
+        match result {
+            Ok(edit) => {
+                self.push_edit(edit);
+                self.rescan_in_place();
+            }
+            Err(e) => self.state = AppState::Error(e),
+        }
+    }
+
+    fn create_note_beside(&mut self, path: &Path) {
+        if self.file_edit_blocked(path) {
+            return;
+        }
+        let dir = path.parent().unwrap_or(path);
+        let result = file_edits::create_note(&file_edits::untitled_note_path(dir));
+        self.apply_structural_edit(result);
+    }
+
+    fn trash_file(&mut self, path: &Path) {
+        if self.file_edit_blocked(path) {
+            return;
+        }
+        self.apply_structural_edit(file_edits::delete_to_trash(path));
+    }
+
+    // Rename to the name typed in the menu, keeping the extension when none is given,
+    // and update the links of every file pointing at it
+    fn rename_file(&mut self, path: &Path) {
+        let name = self.rename_input.trim().to_string();
+        self.rename_input.clear();
+        if self.file_edit_blocked(path) || name.is_empty() {
+            return;
+        }
+        if name.contains(['/', '\\']) {
+            self.state = AppState::Error(format!("\"{}\" is not a valid file name", name));
+            return;
+        }
+        let mut new_path = path.with_file_name(&name);
+        if new_path.extension().is_none()
+            && let Some(extension) = path.extension()
+        {
+            new_path.set_extension(extension);
+        }
+        let referencing = self.file_graph.backlinks(path);
+        let result = file_edits::rename_file(path, &new_path, &referencing);
+        self.apply_structural_edit(result);
+    }
+
+    // An edit whose files changed since is dropped without touching them
+    fn undo_last_edit(&mut self, ctx: &egui::Context) {
+        let Some(edit) = self.edit_history.pop() else {
+            return;
+        };
+        if let Err(e) = file_edits::undo(&edit) {
+            self.state = AppState::Error(format!("Can't undo: {}", e));
+            return;
+        }
+        match edit {
+            FileEdit::AddTag { tag, rewrite } => {
+                let path = rewrite.path;
+                if let Ok(mut scanner) = self.scanner.lock() {
+                    if let Some(tags) = scanner.tags.get_mut(&path)
+                        && let Some(pos) = tags.iter().rposition(|t| *t == tag)
+                    {
+                        tags.remove(pos);
+                    }
+                    scanner.refresh_metadata(&path);
+                }
+                if self.selected_file_path().as_deref() == Some(path.as_path()) {
+                    self.try_load_file_content(path, ctx);
+                }
+            }
+            FileEdit::Create { .. } | FileEdit::Delete { .. } | FileEdit::Rename { .. } => {
+                self.rescan_in_place();
+            }
+        }
+    }
+
+    // The cached preview of `path` if the file hasn't changed since it was read, otherwise
+    // Loading while a worker thread reads it
+    fn hover_preview(
+        &mut self,
+        path: &Path,
+        modified: SystemTime,
+        ctx: &egui::Context,
+    ) -> HoverPreview {
+        if let Some((cached_modified, preview)) = self.hover_previews.get(path)
+            && *cached_modified == modified
+        {
+            return preview.clone();
+        }
+        self.hover_previews
+            .insert(path.to_path_buf(), (modified, HoverPreview::Loading));
+        let sender = self.hover_preview_sender.clone();
+        let path = path.to_path_buf();
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let preview = HoverPreview::load(&path);
+            let _ = sender.send((path, modified, preview));
+            ctx.request_repaint();
+        });
+        HoverPreview::Loading
+    }
+
+    fn find_duplicate_files(&mut self) {
+        self.show_duplicates_window = true;
+        if self.duplicate_receiver.is_some() {
+            return;
+        }
+
+        let paths: Vec<PathBuf> = match self.scanner.lock() {
+            Ok(scanner) => scanner
+                .files
+                .keys()
+                .chain(scanner.images.iter())
+                .cloned()
+                .collect(),
+            Err(_) => return,
+        };
+        let (sender, receiver) = mpsc::channel();
+        self.duplicate_receiver = Some(receiver);
+        thread::spawn(move || {
+            let _ = sender.send(find_duplicates(&paths, MAX_DUPLICATE_HASH_SIZE));
+        });
+    }
+
+    // Welcome screen before the first scan, or hints when a scan found nothing
+    fn show_empty_state(&mut self, ui: &mut egui::Ui, ctx: &egui::Context, view: CentralView) {
+        ui.vertical_centered(|ui| {
+            ui.add_space(ui.available_height() / 4.0);
+            ui.heading("NexusView");
+            ui.add_space(8.0);
+
+            if view == CentralView::EmptyResult {
+                ui.label(format!(
+                    "No supported files were found in {}.",
+                    self.current_scan_dir.display()
+                ));
+                ui.label("NexusView shows Markdown, code, images and PDF files.");
+                if !self.show_hidden_files {
+                    ui.label(
+                        "Hidden files and folders are skipped unless Show Hidden Files is on.",
+                    );
+                    if ui.button("Show Hidden Files").clicked() {
+                        self.show_hidden_files = true;
+                        self.apply_scan_options(ctx);
+                    }
+                }
+            } else {
+                ui.label("Explore how your notes, code and images link to each other.");
+                ui.label("Open a folder, or drop one onto this window, to build its graph.");
+            }
+
+            ui.add_space(8.0);
+            if ui.button("📂 Open Folder...").clicked()
+                && let Some(directory) = rfd::FileDialog::new()
+                    .set_directory(&self.scan_dir)
+                    .pick_folder()
+            {
+                self.open_directories(vec![directory]);
+            }
+        });
+    }
+
+    fn physics_sliders_ui(&mut self, ui: &mut egui::Ui) {
+        ui.add(
+            egui::Slider::new(&mut self.physics_clock.steps_per_second, 15.0..=240.0)
+                .text("Updates/sec"),
+        );
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.freeze_when_settled, "Freeze When Settled")
+                .on_hover_text("Stop the layout once it settles after a scan");
+            ui.checkbox(&mut self.physics_simulator.frozen, "Freeze Physics")
+                .on_hover_text("Hold every node in place; dragged nodes still move");
+        });
+        ui.horizontal(|ui| {
+            ui.label("Preset:");
+            egui::ComboBox::from_id_salt("layout_preset")
+                .selected_text("Choose...")
+                .show_ui(ui, |ui| {
+                    for preset in LayoutPreset::ALL {
+                        if ui.selectable_label(false, preset.name()).clicked() {
+                            self.physics_simulator.apply_preset(preset);
+                        }
+                    }
+                });
+        });
+        ui.horizontal(|ui| {
+            ui.vertical(|ui| {
+                ui.add(
+                    egui::Slider::new(&mut self.physics_simulator.spring_constant, 0.001..=0.5)
+                        .text("Spring K"),
+                );
+                ui.add(
+                    egui::Slider::new(&mut self.physics_simulator.damping, 0.0..=0.9)
+                        .text("Damping"),
+                );
+                ui.add(
+                    egui::Slider::new(&mut self.physics_simulator.time_step, 0.1..=1.0)
+                        .text("Time Step"),
+                );
+            });
+            ui.vertical(|ui| {
+                ui.add(
+                    egui::Slider::new(
+                        &mut self.physics_simulator.repulsion_constant,
+                        100.0..=50000.0,
+                    )
+                    .text("Repulsion K"),
+                );
+                ui.add(
+                    egui::Slider::new(&mut self.physics_simulator.ideal_edge_length, 10.0..=300.0)
+                        .text("Ideal Length"),
+                );
+                ui.add(
+                    egui::Slider::new(&mut self.physics_simulator.friction, 0.0..=0.9)
+                        .text("Friction"),
+                );
+            });
+        });
+    }
+
+    fn display_settings_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Quality:");
+            ui.radio_value(&mut self.graph_quality, GraphQuality::Low, "Low")
+                .on_hover_text("Flat edges and nodes, faster on large graphs");
+            ui.radio_value(&mut self.graph_quality, GraphQuality::High, "High")
+                .on_hover_text("Glow and shadows");
+        });
+        ui.label("Edge Style:");
+        ui.horizontal(|ui| {
+            ui.label("Color");
+            egui::color_picker::color_edit_button_srgba(
+                ui,
+                &mut self.edge_style.color,
+                egui::color_picker::Alpha::Opaque,
+            );
+            ui.checkbox(&mut self.edge_style.glow, "Glow");
+        });
+        ui.add(egui::Slider::new(&mut self.edge_style.opacity, 0.05..=1.0).text("Opacity"));
+        ui.add(egui::Slider::new(&mut self.edge_style.width, 0.5..=5.0).text("Width"));
+        ui.checkbox(&mut self.curved_edges, "Curved edges")
+            .on_hover_text("Links in both directions between two files curve apart");
+        ui.checkbox(&mut self.bundle_edges, "Bundle edges")
+            .on_hover_text("Curve edges around shared nodes together to untangle dense graphs");
+        ui.add_enabled(
+            self.bundle_edges,
+            egui::Slider::new(&mut self.bundling_strength, 0.0..=1.0).text("Bundling"),
+        );
+        ui.checkbox(&mut self.show_edge_flow, "Animated flow")
+            .on_hover_text("Dots travel along edges from source to target");
+        if ui.button("Reset Edge Style").clicked() {
+            self.edge_style = EdgeStyle::default();
+        }
+        ui.separator();
+        ui.label("Labels:");
+        ui.add(egui::Slider::new(&mut self.label_scale, 0.5..=2.5).text("Label size"));
+        ui.checkbox(&mut self.scale_labels_with_zoom, "Scale with zoom");
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::DragValue::new(&mut self.max_label_chars)
+                    .range(0..=200)
+                    .prefix("Max length: "),
+            )
+            .on_hover_text("0 shows labels in full");
+            ui.radio_value(
+                &mut self.label_truncation,
+                LabelTruncation::Middle,
+                "…middle…",
+            );
+            ui.radio_value(&mut self.label_truncation, LabelTruncation::End, "end…");
+        });
+        ui.separator();
+        ui.add(egui::Slider::new(&mut self.snapshot_scale, 0.5..=4.0).text("Copy scale"));
+    }
+
+    // Optional limit on how deep a scan goes. True once a new limit should be applied;
+    // while the value is dragged that waits for the drag to end, as each change rescans.
+    fn max_depth_field(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut limited = self.max_scan_depth.is_some();
+        let mut depth = self.max_scan_depth.unwrap_or(DEFAULT_MAX_SCAN_DEPTH);
+        let mut changed = ui
+            .checkbox(&mut limited, "Max Depth")
+            .on_hover_text("0 scans only the selected folder, without subfolders")
+            .changed();
+        let response = ui.add_enabled(limited, egui::DragValue::new(&mut depth).range(0..=64));
+        changed |= response.drag_stopped() || (response.changed() && !response.dragged());
+        self.max_scan_depth = limited.then_some(depth);
+        changed
+    }
+
+    // Optional cap on the size of scanned files, applied like `max_depth_field`
+    fn max_file_size_field(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut limited = self.max_scan_file_size_mb.is_some();
+        let mut size_mb = self
+            .max_scan_file_size_mb
+            .unwrap_or(DEFAULT_MAX_SCAN_FILE_SIZE_MB);
+        let mut changed = ui
+            .checkbox(&mut limited, "Max File Size")
+            .on_hover_text("Leave larger files out of the scan")
+            .changed();
+        let response = ui.add_enabled(
+            limited,
+            egui::DragValue::new(&mut size_mb)
+                .range(1..=4096)
+                .suffix(" MB"),
+        );
+        changed |= response.drag_stopped() || (response.changed() && !response.dragged());
+        self.max_scan_file_size_mb = limited.then_some(size_mb);
+        changed
+    }
+
+    fn max_scan_file_size(&self) -> Option<u64> {
+        self.max_scan_file_size_mb
+            .map(|size_mb| size_mb * 1024 * 1024)
+    }
+
+    // Tag filter box with completions from the scanned tags: Up/Down to choose,
+    // Tab or Enter to accept
+    fn tag_filter_field(&mut self, ui: &mut egui::Ui) {
+        let response =
+            ui.add(egui::TextEdit::singleline(&mut self.tag_filter_input).lock_focus(true));
+        if response.changed() {
+            self.tag_completion_index = 0;
+        }
+        let editing = response.has_focus() || response.lost_focus();
+        if !editing {
+            return;
+        }
+
+        let completions = match self.scanner.lock() {
+            Ok(scanner) => tag_completions(
+                scanner.tags.values().flatten().map(String::as_str),
+                &self.tag_filter_input,
+                8,
+            ),
+            Err(_) => Vec::new(),
+        };
+        if completions.is_empty() {
+            return;
+        }
+
+        let (up, down, accept) = ui.input(|i| {
+            (
+                i.key_pressed(egui::Key::ArrowUp),
+                i.key_pressed(egui::Key::ArrowDown),
+                i.key_pressed(egui::Key::Tab) || i.key_pressed(egui::Key::Enter),
+            )
+        });
+        if down {
+            self.tag_completion_index = (self.tag_completion_index + 1) % completions.len();
+        } else if up {
+            self.tag_completion_index =
+                (self.tag_completion_index + completions.len() - 1) % completions.len();
+        }
+        let chosen = self.tag_completion_index.min(completions.len() - 1);
+
+        if accept {
+            self.tag_filter_input =
+                apply_tag_completion(&self.tag_filter_input, &completions[chosen]);
+            self.tag_completion_index = 0;
+            // Keep typing after the completed tag
+            if let Some(mut state) = egui::TextEdit::load_state(ui.ctx(), response.id) {
+                let end = egui::text::CCursor::new(self.tag_filter_input.chars().count());
+                state
+                    .cursor
+                    .set_char_range(Some(egui::text::CCursorRange::one(end)));
+                state.store(ui.ctx(), response.id);
+            }
+            return;
+        }
+        if !response.has_focus() {
+            return;
+        }
+
+        egui::Area::new(response.id.with("tag_completions"))
+            .order(egui::Order::Foreground)
+            .fixed_pos(response.rect.left_bottom())
+            .interactable(false)
+            .show(ui.ctx(), |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    for (i, completion) in completions.iter().enumerate() {
+                        let text = egui::RichText::new(format!("#{}", completion));
+                        if i == chosen {
+                            ui.label(
+                                text.strong()
+                                    .background_color(ui.visuals().selection.bg_fill),
+                            );
+                        } else {
+                            ui.label(text);
+                        }
+                    }
+                });
+            });
+    }
+
+    // Push the hidden-files toggle and other scan options to the scanner and rescan
+    fn apply_scan_options(&mut self, ctx: &egui::Context) {
+        if let Ok(mut scanner_guard) = self.scanner.lock() {
+            scanner_guard.set_show_hidden(self.show_hidden_files);
+            scanner_guard.parse_code_imports = self.parse_code_imports;
+            scanner_guard.set_max_depth(self.max_scan_depth);
+            scanner_guard.set_max_file_size(self.max_scan_file_size());
+            scanner_guard.respect_gitignore = self.respect_gitignore;
+            if let Err(e) = scanner_guard.set_exclude_globs(&self.exclude_globs) {
+                self.scan_error = Some(e);
+                return;
+            }
+        } else {
+            eprintln!("Failed to lock scanner mutex when setting scan options.");
+            return;
+        }
+
+        // Trigger a rescan of the currently selected directory
+        if !self.is_scanning {
+            let scan_dir = self
+                .selected_directory
+                .clone()
+                .unwrap_or_else(|| self.scan_dir.clone());
+            self.current_scan_dir = scan_dir.clone();
+            self.trigger_scan(scan_dir, ctx);
+        }
+    }
+
+    fn collect_selected_paths(&self, node: &DirectoryNode, paths: &mut Vec<PathBuf>) {
+        if node.selected {
+            paths.push(node.path.clone());
+        }
+        for child in &node.children {
+            self.collect_selected_paths(child, paths);
+        }
+    }
+
+    fn try_load_file_content(&mut self, path: PathBuf, ctx: &egui::Context) {
+        self.selected_animation = None;
+        self.content_find_index = 0;
+        // Archive members only have the text read during the scan
+        if let Some(archive) = containing_archive(&path) {
+            let text = self
+                .scanner
+                .lock()
+                .unwrap()
+                .archive_text
+                .get(&path)
+                .cloned();
+            self.selected_file_content = Some(text.unwrap_or_else(|| {
+                format!("No preview for binary files inside {}", archive.display())
+            }));
+            self.selected_image = None;
+        } else if is_pdf_path(&path) {
+            self.selected_file_content = Some("PDF Document".to_string());
+            self.selected_image = None;
+
+            // Initialize PDF viewer state
+            self.pdf_viewer_state = PdfViewerState {
+                zoom_level: 1.0,
+                render_quality: RenderQuality::Normal,
+                page_cache: HashMap::new(),
+                page_render_sender: self.pdf_viewer_state.page_render_sender.take(),
+                page_render_receiver: self.pdf_viewer_state.page_render_receiver.take(),
+                ..Default::default()
+            };
+
+            // Load the first page
+            self.load_and_render_pdf_page(ctx, path.clone(), 0);
+
+            // Extract text in background
+            let path_clone = path.clone();
+            let ctx_clone = ctx.clone();
+            thread::spawn(move || {
+                match pdf_utils::extract_text_with_layout(&path_clone) {
+                    Ok(blocks) => {
+                        // Process text blocks and send back to UI thread
+                        ctx_clone.request_repaint();
+                    }
+                    Err(e) => {
+                        eprintln!("Failed to extract text: {}", e);
+                    }
+                }
+            });
+        } else if is_image_path(&path) {
+            // Animated GIFs keep every frame, everything else shows a single texture
+            let is_gif = path
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("gif"));
+            if is_gif && let Some(animation) = GifAnimation::load(&path, ctx) {
+                self.selected_image = Some(animation.texture.clone());
+                self.selected_animation = Some(animation);
+                self.selected_file_content = None;
+                return;
+            }
+
+            match image::open(&path) {
+                Ok(img) => {
+                    let rgba_image = img.into_rgba8();
+                    let pixels: Vec<u8> = rgba_image.as_flat_samples().as_slice().to_vec();
+                    let image_size = [rgba_image.width() as _, rgba_image.height() as _];
+                    let image_data = egui::ColorImage::from_rgba_unmultiplied(image_size, &pixels);
+                    self.selected_image = Some(ctx.load_texture(
+                        path.to_string_lossy(),
+                        image_data,
+                        Default::default(),
+                    ));
+                    self.selected_file_content = None;
+                }
+                Err(e) => {
+                    self.selected_file_content = Some(format!("Failed to load image: {}", e));
+                    self.selected_image = None;
+                }
+            }
+        } else {
+            match std::fs::read_to_string(&path) {
+                Ok(content) => {
+                    self.selected_file_content = Some(content);
+                    self.selected_image = None;
+                }
+                Err(e) => {
+                    self.selected_file_content = Some(format!("Failed to read file: {}", e));
+                    self.selected_image = None;
+                }
+            }
+        }
+    }
+
+    fn render_directory_tree_node(
+        ui: &mut egui::Ui,
+        node: &mut DirectoryNode,
+        filter: &str,
+        show_hidden: bool,
+        files_by_dir: &HashMap<PathBuf, Vec<PathBuf>>,
+        selected_file: Option<&Path>,
+        clicked_file: &mut Option<PathBuf>,
+    ) -> bool {
+        if !node.matches_filter(filter, show_hidden) {
+            return false;
+        }
+
+        let mut changed = false;
+        let label = node.path.file_name().unwrap().to_string_lossy().to_string();
+
+        ui.horizontal(|ui| {
+            if ui.checkbox(&mut node.selected, "").changed() {
+                changed = true;
+            }
+
+            // Clickable label for expansion/collapse
+            if ui
+                .add(egui::Label::new(label).sense(Sense::click()))
+                .clicked()
+            {
+                node.expanded = !node.expanded;
+                changed = true;
+            }
+        });
+
+        if node.expanded && !node.populated {
+            node.populate();
+        }
+
+        // Matching branches are auto-expanded while a filter is active
+        if node.expanded || !filter.is_empty() {
+            ui.indent("dir_indent", |ui| {
+                if node.children.is_empty() && filter.is_empty() {
+                    ui.weak("(no subdirectories)");
+                }
+                for child in node.shown_children(show_hidden) {
+                    if Self::render_directory_tree_node(
+                        ui,
+                        child,
+                        filter,
+                        show_hidden,
+                        files_by_dir,
+                        selected_file,
+                        clicked_file,
+                    ) {
+                        changed = true;
+                    }
+                }
+                if node.expanded {
+                    for file in files_by_dir.get(&node.path).into_iter().flatten() {
+                        let name = file.file_name().unwrap_or_default().to_string_lossy();
+                        let is_selected = selected_file == Some(file.as_path());
+                        if ui
+                            .selectable_label(is_selected, format!("📄 {}", name))
+                            .clicked()
+                        {
+                            *clicked_file = Some(file.clone());
+                        }
+                    }
+                }
+            });
+        }
+
+        changed
+    }
+
+    fn focus_on_node(&mut self, node_idx: NodeIndex) {
+        if let Some(&node_pos) = self.physics_simulator.get_node_position(node_idx) {
+            let current_center_offset = self.graph_center_offset;
+            let target_center_offset = -node_pos; // Center the node at (0,0) in graph coordinates
+            self.graph_center_offset = target_center_offset;
+            self.graph_zoom_factor = 1.0; // Reset zoom to default
+        }
+    }
+
+    // Per-node colours by top-level folder of the scan root, plus the legend entries
+    fn folder_colors(
+        &self,
+        nodes: &[NodeIndex],
+    ) -> (HashMap<NodeIndex, Color32>, Vec<(String, Color32)>) {
+        let graph = match self.current_graph_mode {
+            GraphMode::Links => &self.file_graph.graph,
+            GraphMode::Tags => &self.tag_graph.graph,
+            GraphMode::Unified => &self.unified_graph.graph,
+        };
+        let node_folders: Vec<(NodeIndex, Option<String>)> = nodes
+            .iter()
+            .filter_map(|&node_idx| match graph.node_weight(node_idx) {
+                Some(GraphNode::File(path)) => {
+                    Some((node_idx, top_level_folder(Path::new(path), &self.scan_dir)))
+                }
+                _ => None,
+            })
+            .collect();
+
+        let folders: Vec<String> = node_folders
+            .iter()
+            .filter_map(|(_, folder)| folder.clone())
+            .collect();
+        let palette = folder_palette(&folders);
+        let lookup: HashMap<&str, Color32> = palette
+            .iter()
+            .map(|(folder, color)| (folder.as_str(), *color))
+            .collect();
+
+        let colors = node_folders
+            .iter()
+            .map(|(node_idx, folder)| {
+                let color = folder
+                    .as_deref()
+                    .and_then(|folder| lookup.get(folder).copied())
+                    .unwrap_or(ROOT_FOLDER_COLOR);
+                (*node_idx, color)
+            })
+            .collect();
+        (colors, palette)
+    }
+
+    fn chrome_visibility(&self) -> ChromeVisibility {
+        ChromeVisibility {
+            directory_panel: self.show_directory_panel,
+            content_panel: self.show_content_panel,
+            physics_window: self.show_physics_window,
+            display_window: self.show_display_window,
+            settings_window: self.show_settings_window,
+            duplicates_window: self.show_duplicates_window,
+        }
+    }
+
+    fn set_chrome_visibility(&mut self, visibility: ChromeVisibility) {
+        self.show_directory_panel = visibility.directory_panel;
+        self.show_content_panel = visibility.content_panel;
+        self.show_physics_window = visibility.physics_window;
+        self.show_display_window = visibility.display_window;
+        self.show_settings_window = visibility.settings_window;
+        self.show_duplicates_window = visibility.duplicates_window;
+    }
+
+    fn toggle_presentation_mode(&mut self, ctx: &egui::Context) {
+        match self.saved_chrome.take() {
+            Some(saved) => {
+                self.set_chrome_visibility(saved);
+                ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(false));
+            }
+            None => {
+                self.saved_chrome = Some(self.chrome_visibility());
+                self.set_chrome_visibility(ChromeVisibility::HIDDEN);
+                ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(true));
+            }
+        }
+        self.center_graph();
+    }
+
+    // Auto-freeze: hold the first settled layout after a scan
+    fn freeze_if_settled(&mut self) {
+        if self.awaiting_settle && !self.is_scanning && self.physics_simulator.is_settled() {
+            self.awaiting_settle = false;
+            if self.freeze_when_settled {
+                self.physics_simulator.frozen = true;
+            }
+        }
+    }
+
+    fn center_graph(&mut self) {
+        self.graph_center_offset = egui::Vec2::ZERO;
+        self.graph_zoom_factor = 1.0;
+    }
+
+    fn perform_search(&mut self) {
+        self.search_results.clear();
+        self.current_search_result = 0;
+
+        let query_lower = self.search_query.to_lowercase();
+        if query_lower.is_empty() {
+            return;
+        }
+
+        let graph_to_search = match self.current_graph_mode {
+            GraphMode::Links => &self.file_graph.graph,
+            GraphMode::Tags => &self.tag_graph.graph,
+            GraphMode::Unified => &self.unified_graph.graph,
+        };
+
+        for node_idx in graph_to_search.node_indices() {
+            let node_name = match &graph_to_search[node_idx] {
+                GraphNode::File(path) | GraphNode::Directory(path) => display_path(path, false),
+                GraphNode::Tag(s) | GraphNode::External(s) => s.clone(),
+            };
+            if node_name.to_lowercase().contains(&query_lower) {
+                self.search_results.push(node_idx);
+            }
+        }
+
+        if !self.search_results.is_empty() {
+            self.selected_node = Some(self.search_results[0]);
+            self.focus_on_node(self.search_results[0]);
+        }
+    }
+
+    // File nodes of the current graph a random jump may land on: no tags or images
+    fn random_node_candidates(&self) -> Vec<NodeIndex> {
+        let file_nodes = match self.current_graph_mode {
+            GraphMode::Links | GraphMode::Unified => &self.links_graph().node_indices,
+            GraphMode::Tags => &self.tag_graph.file_node_indices,
+        };
+        file_nodes
+            .iter()
+            .filter(|(path, _)| !is_image_path(path))
+            .map(|(_, &node_idx)| node_idx)
+            .collect()
+    }
+
+    // Select, open and centre a random note
+    fn jump_to_random_node(&mut self, ctx: &egui::Context) {
+        let eligible = self.random_node_candidates();
+        let Some(node_idx) = pick_random_node(&eligible, &mut rand::rng()) else {
+            return;
+        };
+
+        self.selected_node = Some(node_idx);
+        self.previewed_file = None;
+        self.focus_on_node(node_idx);
+        if let Some(GraphNode::File(path)) = self.selected_graph_node().cloned() {
+            self.try_load_file_content(path, ctx);
+        }
+    }
+
+    fn focus_next_search_result(&mut self) {
         if self.search_results.is_empty() {
             return;
         }
@@ -2491,39 +6067,422 @@ impl<'a> FileGraphApp<'a> {
         if self.search_results.is_empty() {
             return;
         }
-        if self.current_search_result == 0 {
-            self.current_search_result = self.search_results.len() - 1;
-        } else {
-            self.current_search_result -= 1;
-        }
-        self.selected_node = Some(self.search_results[self.current_search_result]);
-        self.focus_on_node(self.search_results[self.current_search_result]);
+        if self.current_search_result == 0 {
+            self.current_search_result = self.search_results.len() - 1;
+        } else {
+            self.current_search_result -= 1;
+        }
+        self.selected_node = Some(self.search_results[self.current_search_result]);
+        self.focus_on_node(self.search_results[self.current_search_result]);
+    }
+
+    fn current_settings(&self) -> AppSettings {
+        // Presentation mode hides windows temporarily; remember the user's choice instead
+        let chrome = self
+            .saved_chrome
+            .unwrap_or_else(|| self.chrome_visibility());
+        AppSettings {
+            show_physics_window: chrome.physics_window,
+            content_font_size: self.content_font_size,
+            content_line_spacing: self.content_line_spacing,
+            graph_mode: self.current_graph_mode,
+            selected_node: self
+                .selected_graph_node()
+                .cloned()
+                .or_else(|| self.pending_selection.clone()),
+            show_content_panel: chrome.content_panel,
+            max_graph_nodes: self.max_graph_nodes,
+            keybindings: self.keybindings.clone(),
+            freeze_when_settled: self.freeze_when_settled,
+            watch_debounce_ms: self.rescan_debouncer.window.as_millis() as u64,
+            batch_watch_events: self.rescan_debouncer.batch_events,
+            safe_mode: self.safe_mode,
+            search_history: self.search_history.clone(),
+            node_drag_threshold: self.node_drag_threshold,
+            respect_gitignore: self.respect_gitignore,
+            max_scan_depth: self.max_scan_depth,
+            max_scan_file_size_mb: self.max_scan_file_size_mb,
+        }
+    }
+
+    // A click on a node in placement mode. The held node sits under the pointer, so
+    // any click drops it; otherwise the clicked node is picked up and pinned.
+    fn pick_up_or_drop(&mut self, node_idx: NodeIndex, node_pos: egui::Vec2) {
+        self.held_node = match self.held_node {
+            Some(_) => None,
+            None => {
+                self.physics_simulator.pinned_nodes.insert(node_idx);
+                Some((node_idx, node_pos))
+            }
+        };
+    }
+
+    // Move the held node to the pointer (in graph coordinates); a click drops it there
+    fn carry_held_node(&mut self, pointer: Option<egui::Vec2>, clicked: bool) {
+        let Some((node_idx, _)) = self.held_node else {
+            return;
+        };
+        if let Some(pointer) = pointer {
+            self.physics_simulator.set_node_position(node_idx, pointer);
+        }
+        if clicked {
+            self.held_node = None;
+        }
+    }
+
+    // Put a held node back where it was picked up, free to move again
+    fn cancel_held_node(&mut self) {
+        if let Some((node_idx, origin)) = self.held_node.take() {
+            self.physics_simulator.set_node_position(node_idx, origin);
+            self.physics_simulator.pinned_nodes.remove(&node_idx);
+        }
+    }
+
+    // Show the files of a collapsed folder node as their own nodes again
+    fn expand_directory(&mut self, dir: PathBuf) {
+        if let Some(expanded) = &mut self.file_graph.directory_grouping {
+            expanded.insert(dir);
+            self.selected_node = None;
+        }
+    }
+
+    fn status_bar(&self) -> StatusBar {
+        let graph = match self.current_graph_mode {
+            GraphMode::Links => &self.file_graph.graph,
+            GraphMode::Tags => &self.tag_graph.graph,
+            GraphMode::Unified => &self.unified_graph.graph,
+        };
+        let hovered = self
+            .hovered_node
+            .and_then(|node_idx| graph.node_weight(node_idx))
+            .map(|node| match node {
+                GraphNode::File(path) => (display_path(path, false), path.display().to_string()),
+                GraphNode::Tag(tag) => (format!("#{}", tag), format!("#{}", tag)),
+                GraphNode::External(url) => (url.clone(), url.clone()),
+                GraphNode::Directory(dir) => (display_path(dir, false), dir.display().to_string()),
+            });
+        StatusBar {
+            visible_nodes: self.visible_counts.0,
+            visible_edges: self.visible_counts.1,
+            graph_mode: self.current_graph_mode,
+            zoom: self.graph_zoom_factor,
+            hovered,
+        }
+    }
+
+    // Take on every persisted setting. True when a scan option changed, so the folder
+    // needs a rescan; the graph is rebuilt from the scanner each frame. The saved
+    // selection is left alone: it names a node to find once the folder is scanned.
+    fn apply_settings(&mut self, settings: AppSettings) -> bool {
+        // In presentation mode the panels come back with these settings on exit
+        match &mut self.saved_chrome {
+            Some(saved) => {
+                saved.physics_window = settings.show_physics_window;
+                saved.content_panel = settings.show_content_panel;
+            }
+            None => {
+                self.show_physics_window = settings.show_physics_window;
+                self.show_content_panel = settings.show_content_panel;
+            }
+        }
+        self.content_font_size = settings.content_font_size;
+        self.content_line_spacing = settings.content_line_spacing;
+        if self.current_graph_mode != settings.graph_mode {
+            self.current_graph_mode = settings.graph_mode;
+            self.selected_node = None;
+            self.isolated_node = None;
+            self.physics_simulator
+                .reset_positions(&self.initial_node_layout);
+        }
+        self.max_graph_nodes = settings.max_graph_nodes;
+        self.keybindings = settings.keybindings;
+        self.freeze_when_settled = settings.freeze_when_settled;
+        self.rescan_debouncer.window = Duration::from_millis(settings.watch_debounce_ms);
+        self.rescan_debouncer.batch_events = settings.batch_watch_events;
+        self.safe_mode = settings.safe_mode;
+        self.search_history = settings.search_history;
+        self.node_drag_threshold = settings.node_drag_threshold;
+        let scan_options = (
+            self.respect_gitignore,
+            self.max_scan_depth,
+            self.max_scan_file_size_mb,
+        );
+        self.respect_gitignore = settings.respect_gitignore;
+        self.max_scan_depth = settings.max_scan_depth;
+        self.max_scan_file_size_mb = settings.max_scan_file_size_mb;
+        scan_options
+            != (
+                self.respect_gitignore,
+                self.max_scan_depth,
+                self.max_scan_file_size_mb,
+            )
+    }
+
+    // Put every persisted setting back to its default, keeping the search history
+    // unless asked to clear it
+    fn reset_settings(&mut self, clear_search_history: bool) -> bool {
+        let search_history = if clear_search_history {
+            Vec::new()
+        } else {
+            self.search_history.clone()
+        };
+        self.apply_settings(AppSettings {
+            search_history,
+            ..AppSettings::default()
+        })
+    }
+
+    // Shortcuts are ignored while typing or while a new key is being captured
+    fn run_action(&mut self, action: Action, ctx: &egui::Context) {
+        match action {
+            Action::FocusSelected => {
+                if let Some(node_idx) = self.selected_node {
+                    self.focus_on_node(node_idx);
+                }
+            }
+            Action::ToggleFullPaths => self.show_full_paths = !self.show_full_paths,
+            Action::RandomNode => self.jump_to_random_node(ctx),
+            Action::ToggleIsolate => {
+                self.isolated_node = match self.isolated_node {
+                    Some(_) => None,
+                    None => self.selected_node,
+                };
+            }
+            Action::NextSearchResult => self.focus_next_search_result(),
+            Action::PrevSearchResult => self.focus_prev_search_result(),
+            Action::PresentationMode => self.toggle_presentation_mode(ctx),
+            Action::Undo => {
+                if !self.safe_mode {
+                    self.undo_last_edit(ctx);
+                }
+            }
+            Action::CommandPalette => self.command_palette.toggle(),
+        }
+    }
+
+    fn run_command(&mut self, command: Command, ctx: &egui::Context) {
+        match command {
+            Command::Action(action) => self.run_action(action, ctx),
+            Command::SwitchMode(mode) => self.switch_graph_mode(mode),
+            Command::ExportGraph { graphml } => self.export_graph(graphml),
+            Command::ExportTags { inverted } => self.export_tags(inverted),
+            Command::ExportCooccurrence { csv } => self.export_cooccurrence(csv),
+            Command::ImportGraph => self.import_graph(),
+            Command::Rescan => {
+                if !self.is_scanning {
+                    self.rescan_in_place();
+                }
+            }
+            Command::FindDuplicates => self.find_duplicate_files(),
+            Command::ToggleDirectoryPanel => self.show_directory_panel = !self.show_directory_panel,
+            Command::ToggleContentPanel => self.show_content_panel = !self.show_content_panel,
+            Command::ToggleSettings => self.show_settings_window = !self.show_settings_window,
+            Command::ToggleKeyBindings => {
+                self.show_keybindings_window = !self.show_keybindings_window
+            }
+            Command::ToggleCycles => self.show_cycles_window = !self.show_cycles_window,
+            Command::ToggleScanStats => self.show_scan_stats_window = !self.show_scan_stats_window,
+            Command::ToggleCurvedEdges => self.curved_edges = !self.curved_edges,
+            Command::ToggleEdgeBundling => self.bundle_edges = !self.bundle_edges,
+            Command::ToggleEdgeFlow => self.show_edge_flow = !self.show_edge_flow,
+            Command::ToggleFollowSelected => {
+                self.follow_selected = !self.follow_selected && self.selected_node.is_some();
+                self.followed_node = self.selected_node;
+            }
+            Command::TogglePlacementMode => {
+                self.placement_mode = !self.placement_mode;
+                if !self.placement_mode {
+                    self.cancel_held_node();
+                }
+            }
+        }
+    }
+
+    // A new mode starts from a clean selection and layout
+    fn switch_graph_mode(&mut self, mode: GraphMode) {
+        self.current_graph_mode = mode;
+        self.selected_node = None;
+        self.isolated_node = None;
+        self.physics_simulator
+            .reset_positions(&self.initial_node_layout);
+    }
+
+    fn action_pressed(&self, ctx: &egui::Context, action: Action) -> bool {
+        self.rebinding_action.is_none()
+            && (action.works_while_typing() || !ctx.wants_keyboard_input())
+            && ctx.input(|i| self.keybindings.pressed(i, action))
+    }
+
+    // Nodes sharing a tag with the selected file; empty when the option is off
+    fn update_shared_tag_nodes(&mut self) -> HashSet<NodeIndex> {
+        let selected_file = match (self.highlight_shared_tags, self.selected_graph_node()) {
+            (true, Some(GraphNode::File(path))) => path.clone(),
+            _ => {
+                self.shared_tag_nodes = None;
+                return HashSet::new();
+            }
+        };
+        let selected = self.selected_node.unwrap();
+        if let Some((cached_for, nodes)) = &self.shared_tag_nodes
+            && *cached_for == selected
+        {
+            return nodes.clone();
+        }
+
+        let sharing: Vec<PathBuf> = {
+            let scanner = self.scanner.lock().unwrap();
+            let index = tag_file_index(&scanner.tags);
+            files_sharing_tags(&selected_file, &scanner.tags, &index)
+                .into_iter()
+                .map(Path::to_path_buf)
+                .collect()
+        };
+        let nodes: HashSet<NodeIndex> = sharing
+            .iter()
+            .filter_map(|path| self.node_for_path(path))
+            .collect();
+        self.shared_tag_nodes = Some((selected, nodes.clone()));
+        nodes
+    }
+
+    // Graph behind Links-style views: the links graph, or the unified one with tags
+    fn links_graph(&self) -> &FileGraph {
+        if self.current_graph_mode == GraphMode::Unified {
+            &self.unified_graph
+        } else {
+            &self.file_graph
+        }
+    }
+
+    fn selected_graph_node(&self) -> Option<&GraphNode> {
+        let graph = match self.current_graph_mode {
+            GraphMode::Links => &self.file_graph.graph,
+            GraphMode::Tags => &self.tag_graph.graph,
+            GraphMode::Unified => &self.unified_graph.graph,
+        };
+        self.selected_node
+            .and_then(|node_idx| graph.node_weight(node_idx))
+    }
+
+    // File shown in the content panel, from the selected node or a direct preview
+    fn selected_file_path(&self) -> Option<PathBuf> {
+        match self.selected_graph_node() {
+            Some(GraphNode::File(path)) => Some(path.clone()),
+            Some(_) => None,
+            None => self.previewed_file.clone(),
+        }
+    }
+
+    // Scanned files and images grouped by their folder, sorted, for the directory panel
+    fn scanned_files_by_dir(&self) -> HashMap<PathBuf, Vec<PathBuf>> {
+        let scanner = self.scanner.lock().unwrap();
+        let mut by_dir: HashMap<PathBuf, Vec<PathBuf>> = HashMap::new();
+        for path in scanner.files.keys().chain(&scanner.images) {
+            if let Some(parent) = path.parent() {
+                by_dir
+                    .entry(parent.to_path_buf())
+                    .or_default()
+                    .push(path.clone());
+            }
+        }
+        for files in by_dir.values_mut() {
+            files.sort();
+            files.dedup();
+        }
+        by_dir
+    }
+
+    fn node_for_path(&self, path: &Path) -> Option<NodeIndex> {
+        match self.current_graph_mode {
+            GraphMode::Links | GraphMode::Unified => {
+                self.links_graph().node_indices.get(path).copied()
+            }
+            GraphMode::Tags => self
+                .tag_graph
+                .file_node_indices
+                .get(path)
+                .or_else(|| self.tag_graph.image_node_indices.get(path))
+                .copied(),
+        }
+    }
+
+    fn node_index_for(&self, node: &GraphNode) -> Option<NodeIndex> {
+        match node {
+            GraphNode::File(path) => self.node_for_path(Path::new(path)),
+            GraphNode::Tag(tag) => match self.current_graph_mode {
+                GraphMode::Tags => self.tag_graph.tag_node_indices.get(tag).copied(),
+                GraphMode::Unified => self.unified_graph.tag_node_indices.get(tag).copied(),
+                GraphMode::Links => None,
+            },
+            GraphNode::External(url) => match self.current_graph_mode {
+                GraphMode::Links | GraphMode::Unified => {
+                    self.links_graph().external_node_indices.get(url).copied()
+                }
+                GraphMode::Tags => None,
+            },
+            GraphNode::Directory(dir) => match self.current_graph_mode {
+                GraphMode::Links => self.file_graph.directory_node_indices.get(dir).copied(),
+                GraphMode::Tags | GraphMode::Unified => None,
+            },
+        }
+    }
+
+    fn view_state(&self) -> ViewState {
+        ViewState {
+            scan_dir: self.current_scan_dir.clone(),
+            graph_mode: self.current_graph_mode,
+            selected_node: self.selected_graph_node().cloned(),
+            zoom: self.graph_zoom_factor,
+            offset: [self.graph_center_offset.x, self.graph_center_offset.y],
+        }
+    }
+
+    pub fn restore_scan_snapshots(&mut self, snapshots: ScanSnapshots) {
+        self.scan_snapshots = snapshots;
+    }
+
+    // Scan the shared view's directory and restore its mode and camera; the
+    // selection is restored once the scan finishes
+    pub fn apply_view_state(&mut self, view: ViewState) {
+        self.open_directories(vec![view.scan_dir]);
+        self.current_graph_mode = view.graph_mode;
+        self.pending_selection = view.selected_node;
+        self.graph_zoom_factor = view.zoom.clamp(0.1, 10.0);
+        self.graph_center_offset = egui::vec2(view.offset[0], view.offset[1]);
+    }
+
+    // Select a saved node if it still exists; otherwise leave the selection empty
+    fn restore_selection(&mut self, saved: &GraphNode, ctx: &egui::Context) {
+        let Some(node_idx) = self.node_index_for(saved) else {
+            return;
+        };
+        self.selected_node = Some(node_idx);
+        if let GraphNode::File(path) = saved {
+            self.try_load_file_content(path.clone(), ctx);
+        }
     }
 
-    fn is_markdown_file(&self) -> bool {
-        if let Some(node_idx) = self.selected_node {
-            let graph = match self.current_graph_mode {
-                GraphMode::Links => &self.file_graph.graph,
-                GraphMode::Tags => &self.tag_graph.graph,
-            };
-            if let GraphNode::File(file_path_str) = &graph[node_idx] {
-                return is_markdown_path(Path::new(file_path_str));
+    fn preview_file(&mut self, path: PathBuf, ctx: &egui::Context) {
+        self.selected_node = self.node_for_path(&path);
+        self.previewed_file = match self.selected_node {
+            Some(node_idx) => {
+                self.focus_on_node(node_idx);
+                None
             }
-        }
-        false
+            None => Some(path.clone()),
+        };
+        self.try_load_file_content(path, ctx);
+        self.show_content_panel = true;
+    }
+
+    fn is_markdown_file(&self) -> bool {
+        self.selected_file_path()
+            .is_some_and(|path| is_markdown_path(&path))
     }
 
     fn is_code_file(&self) -> bool {
-        if let Some(node_idx) = self.selected_node {
-            let graph = match self.current_graph_mode {
-                GraphMode::Links => &self.file_graph.graph,
-                GraphMode::Tags => &self.tag_graph.graph,
-            };
-            if let GraphNode::File(file_path_str) = &graph[node_idx] {
-                return is_code_path(Path::new(file_path_str));
-            }
-        }
-        false
+        self.selected_file_path()
+            .is_some_and(|path| is_code_path(&path))
     }
 
     fn is_pdf_file(&self) -> bool {
@@ -2531,6 +6490,7 @@ impl<'a> FileGraphApp<'a> {
             let graph = match self.current_graph_mode {
                 GraphMode::Links => &self.file_graph.graph,
                 GraphMode::Tags => &self.tag_graph.graph,
+                GraphMode::Unified => &self.unified_graph.graph,
             };
             if let GraphNode::File(file_path_str) = &graph[node_idx] {
                 return is_pdf_path(Path::new(file_path_str));
@@ -2544,9 +6504,10 @@ impl<'a> FileGraphApp<'a> {
             let graph = match self.current_graph_mode {
                 GraphMode::Links => &self.file_graph.graph,
                 GraphMode::Tags => &self.tag_graph.graph,
+                GraphMode::Unified => &self.unified_graph.graph,
             };
             if let GraphNode::File(file_path_str) = &graph[node_idx] {
-                let path = Path::new(file_path_str);
+                let path = &file_path_str.clone();
 
                 // Simple PDF info display
                 ui.label("PDF Document");
@@ -2569,7 +6530,14 @@ impl<'a> FileGraphApp<'a> {
                                 }
 
                                 // Add a button to open the PDF in external viewer
-                                if ui.button("Open in External Viewer").clicked() {
+                                if ui
+                                    .add_enabled(
+                                        !self.safe_mode,
+                                        egui::Button::new("Open in External Viewer"),
+                                    )
+                                    .on_disabled_hover_text(SAFE_MODE_HINT)
+                                    .clicked()
+                                {
                                     self.open_file_externally(path);
                                 }
                             }
@@ -2592,51 +6560,25 @@ impl<'a> FileGraphApp<'a> {
         Ok(text)
     }
 
-    fn open_file_externally(&self, path: &Path) {
-        #[cfg(target_os = "linux")]
-        {
-            std::process::Command::new("xdg-open")
-                .arg(path)
-                .spawn()
-                .expect("Failed to open file");
+    fn open_file_externally(&mut self, path: &Path) {
+        if self.safe_mode {
+            return;
         }
-        #[cfg(target_os = "macos")]
-        {
-            std::process::Command::new("open")
-                .arg(path)
-                .spawn()
-                .expect("Failed to open file");
+        if let Some(archive) = containing_archive(path) {
+            self.state = AppState::Error(format!(
+                "{} is inside {}; extract it to open it",
+                path.display(),
+                archive.display()
+            ));
+            return;
         }
-        #[cfg(target_os = "windows")]
-        {
-            std::process::Command::new("cmd")
-                .arg("/C")
-                .arg("start")
-                .arg(path)
-                .spawn()
-                .expect("Failed to open file");
+        if let Err(e) = open_with_default_app(path) {
+            self.state = AppState::Error(e);
         }
     }
 
-    fn render_code_with_syntax_highlighting(&mut self, ui: &mut egui::Ui, _code_content: &str) {
-        let content = if let Some(content) = &self.selected_file_content {
-            content.clone()
-        } else {
-            return;
-        };
-
-        if let Some(node_idx) = self.selected_node {
-            let graph = match self.current_graph_mode {
-                GraphMode::Links => &self.file_graph.graph,
-                GraphMode::Tags => &self.tag_graph.graph,
-            };
-            let file_path_str = if let GraphNode::File(s) = &graph[node_idx] {
-                s
-            } else {
-                return;
-            };
-
-            let path = PathBuf::from(file_path_str);
+    fn render_code_with_syntax_highlighting(&mut self, ui: &mut egui::Ui, code_content: &str) {
+        if let Some(path) = self.selected_file_path() {
             let lang = path
                 .extension()
                 .and_then(|s| s.to_str())
@@ -2644,45 +6586,116 @@ impl<'a> FileGraphApp<'a> {
                 .to_lowercase();
 
             let syntax = self.get_syntax_for_language(&lang);
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                self.show_searchable_text(ui, code_content, syntax, egui::TextStyle::Monospace);
+            });
+        }
+    }
 
-            if let Some(syntax_ref) = syntax {
-                let mut h = HighlightLines::new(syntax_ref, *DEFAULT_THEME);
-                let mut layouter = |ui: &egui::Ui, text: &str, _wrap_width: f32| {
-                    let mut job = egui::text::LayoutJob::default();
-                    for line in LinesWithEndings::from(text) {
-                        let ranges = h.highlight_line(line, &SYNTAX_SET).unwrap();
-                        for (style, text) in ranges {
-                            let color = style.foreground;
-                            let egui_color = egui::Color32::from_rgb(color.r, color.g, color.b);
-                            job.append(
-                                text,
-                                0.0,
-                                egui::TextFormat {
-                                    font_id: egui::TextStyle::Monospace.resolve(ui.style()),
-                                    color: egui_color,
-                                    ..Default::default()
-                                },
-                            );
-                        }
-                    }
-                    ui.fonts(|f| f.layout_job(job))
+    fn find_in_file_bar(&mut self, ui: &mut egui::Ui, content: &str) {
+        let match_count = find_match_ranges(content, &self.content_find_query).len();
+        ui.horizontal(|ui| {
+            ui.label("🔍");
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut self.content_find_query)
+                    .hint_text("Find in file")
+                    .desired_width(160.0),
+            );
+            if response.changed() {
+                self.content_find_index = 0;
+                self.content_find_scroll = true;
+            }
+            let enter_pressed =
+                response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+
+            if match_count > 0 {
+                let current = self.content_find_index.min(match_count - 1);
+                ui.label(format!("{}/{}", current + 1, match_count));
+                if ui.button("◀").clicked() {
+                    self.content_find_index = (current + match_count - 1) % match_count;
+                    self.content_find_scroll = true;
+                }
+                if ui.button("▶").clicked() || enter_pressed {
+                    self.content_find_index = (current + 1) % match_count;
+                    self.content_find_scroll = true;
+                }
+                if enter_pressed {
+                    response.request_focus();
+                }
+            } else if !self.content_find_query.is_empty() {
+                ui.weak("No matches");
+            }
+        });
+        ui.separator();
+    }
+
+    // Read-only text view with optional syntax colours and find-in-file highlights
+    fn show_searchable_text(
+        &mut self,
+        ui: &mut egui::Ui,
+        content: &str,
+        syntax: Option<&SyntaxReference>,
+        text_style: egui::TextStyle,
+    ) {
+        let matches = find_match_ranges(content, &self.content_find_query);
+        let current = (!matches.is_empty()).then(|| self.content_find_index.min(matches.len() - 1));
+        let line_spacing = self.content_line_spacing;
+        let wrap = syntax.is_none();
+        let font_style = text_style.clone();
+        let mut highlighter =
+            syntax.map(|syntax_ref| HighlightLines::new(syntax_ref, *DEFAULT_THEME));
+
+        let mut layouter = |ui: &egui::Ui, text: &str, wrap_width: f32| {
+            let mut job = LayoutJob::default();
+            if wrap {
+                job.wrap.max_width = wrap_width;
+            }
+            let font_id = text_style.resolve(ui.style());
+            let plain_color = ui.visuals().text_color();
+            let mut offset = 0;
+            for line in LinesWithEndings::from(text) {
+                let pieces: Vec<(Color32, &str)> = match highlighter.as_mut() {
+                    Some(h) => h
+                        .highlight_line(line, &SYNTAX_SET)
+                        .map(|ranges| {
+                            ranges
+                                .into_iter()
+                                .map(|(style, piece)| {
+                                    let color = style.foreground;
+                                    (Color32::from_rgb(color.r, color.g, color.b), piece)
+                                })
+                                .collect()
+                        })
+                        .unwrap_or_else(|_| vec![(plain_color, line)]),
+                    None => vec![(plain_color, line)],
                 };
+                for (color, piece) in pieces {
+                    let format = content_text_format(font_id.clone(), color, line_spacing);
+                    append_with_matches(&mut job, piece, offset, format, &matches, current);
+                    offset += piece.len();
+                }
+            }
+            ui.fonts(|f| f.layout_job(job))
+        };
 
-                let mut text = content;
-                ui.add(
-                    egui::TextEdit::multiline(&mut text)
-                        .font(egui::TextStyle::Monospace)
-                        .desired_width(ui.available_width())
-                        .interactive(false)
-                        .layouter(&mut layouter),
-                );
-            } else {
-                let mut text = content;
-                ui.add(
-                    egui::TextEdit::multiline(&mut text)
-                        .font(egui::TextStyle::Monospace)
-                        .desired_width(ui.available_width()),
-                );
+        let mut text = content;
+        let output = egui::TextEdit::multiline(&mut text)
+            .font(font_style)
+            .desired_width(ui.available_width())
+            .interactive(false)
+            .layouter(&mut layouter)
+            .show(ui);
+
+        // Bring the current match into view after typing or navigating
+        if self.content_find_scroll {
+            self.content_find_scroll = false;
+            if let Some(i) = current {
+                let char_index = content[..matches[i].start].chars().count();
+                let match_rect = output
+                    .galley
+                    .pos_from_ccursor(egui::text::CCursor::new(char_index))
+                    .translate(output.galley_pos.to_vec2());
+                ui.scroll_to_rect(match_rect, Some(egui::Align::Center));
             }
         }
     }
@@ -2693,48 +6706,51 @@ impl<'a> FileGraphApp<'a> {
         code_block_content: &str,
         syntax: Option<&SyntaxReference>,
     ) {
-        if let Some(syntax_ref) = syntax {
-            let mut h = HighlightLines::new(syntax_ref, *DEFAULT_THEME);
-            let mut layouter = |ui: &egui::Ui, text: &str, _wrap_width: f32| {
-                let mut job = egui::text::LayoutJob::default();
-                for line in LinesWithEndings::from(text) {
-                    let ranges = h.highlight_line(line, &SYNTAX_SET).unwrap();
-                    for (style, text) in ranges {
-                        let color = style.foreground;
-                        let egui_color = egui::Color32::from_rgb(color.r, color.g, color.b);
-                        job.append(
-                            text,
-                            0.0,
-                            egui::TextFormat {
-                                font_id: egui::TextStyle::Monospace.resolve(ui.style()),
-                                color: egui_color,
-                                ..Default::default()
-                            },
-                        );
-                    }
+        let line_spacing = self.content_line_spacing;
+        let mut highlighter =
+            syntax.map(|syntax_ref| HighlightLines::new(syntax_ref, *DEFAULT_THEME));
+        let mut layouter = |ui: &egui::Ui, text: &str, _wrap_width: f32| {
+            let mut job = egui::text::LayoutJob::default();
+            let font_id = egui::TextStyle::Monospace.resolve(ui.style());
+            let plain_color = ui.visuals().text_color();
+            for line in LinesWithEndings::from(text) {
+                let pieces: Vec<(Color32, &str)> = match highlighter.as_mut() {
+                    Some(h) => h
+                        .highlight_line(line, &SYNTAX_SET)
+                        .map(|ranges| {
+                            ranges
+                                .into_iter()
+                                .map(|(style, piece)| {
+                                    let color = style.foreground;
+                                    (Color32::from_rgb(color.r, color.g, color.b), piece)
+                                })
+                                .collect()
+                        })
+                        .unwrap_or_else(|_| vec![(plain_color, line)]),
+                    None => vec![(plain_color, line)],
+                };
+                for (color, piece) in pieces {
+                    job.append(
+                        piece,
+                        0.0,
+                        content_text_format(font_id.clone(), color, line_spacing),
+                    );
                 }
-                ui.fonts(|f| f.layout_job(job))
-            };
+            }
+            ui.fonts(|f| f.layout_job(job))
+        };
 
-            let mut text = code_block_content.to_string();
-            ui.add(
-                egui::TextEdit::multiline(&mut text)
-                    .font(egui::TextStyle::Monospace)
-                    .desired_width(ui.available_width())
-                    .interactive(false)
-                    .layouter(&mut layouter),
-            );
-        } else {
-            let mut text = code_block_content.to_string();
-            ui.add(
-                egui::TextEdit::multiline(&mut text)
-                    .font(egui::TextStyle::Monospace)
-                    .desired_width(ui.available_width()),
-            );
-        }
+        let mut text = code_block_content.to_string();
+        ui.add(
+            egui::TextEdit::multiline(&mut text)
+                .font(egui::TextStyle::Monospace)
+                .desired_width(ui.available_width())
+                .interactive(false)
+                .layouter(&mut layouter),
+        );
     }
 
-    fn get_syntax_for_language(&self, lang: &str) -> Option<&SyntaxReference> {
+    fn get_syntax_for_language(&self, lang: &str) -> Option<&'static SyntaxReference> {
         match lang.to_lowercase().as_str() {
             "" => Some(SYNTAX_SET.find_syntax_plain_text()),
             "python" | "py" => SYNTAX_SET.find_syntax_by_extension("py"),
@@ -2779,7 +6795,7 @@ impl<'a> FileGraphApp<'a> {
                     if let Some(node_data) = graph.node_weight(idx) {
                         match node_data {
                             GraphNode::File(name) => {
-                                let name_lower = name.to_lowercase();
+                                let name_lower = name.to_string_lossy().to_lowercase();
                                 let matches_search =
                                     search_lower.is_empty() || name_lower.contains(&search_lower);
                                 let matches_tags = if filter_tags_lower.is_empty() {
@@ -2808,6 +6824,14 @@ impl<'a> FileGraphApp<'a> {
                                     || filter_tags_lower.contains(&tag_lower);
                                 matches_search && matches_filter
                             }
+                            GraphNode::External(url) => {
+                                search_lower.is_empty()
+                                    || url.to_lowercase().contains(&search_lower)
+                            }
+                            GraphNode::Directory(dir) => {
+                                search_lower.is_empty()
+                                    || dir.to_string_lossy().to_lowercase().contains(&search_lower)
+                            }
                         }
                     } else {
                         false
@@ -2889,10 +6913,10 @@ impl<'a> FileGraphApp<'a> {
                                 let ctx_clone = ui.ctx().clone();
                                 thread::spawn(move || {
                                     // Create new PDFium instance in the thread
-                                    let pdfium = match Pdfium::bind_to_system_library() {
-                                        Ok(bindings) => Pdfium::new(bindings),
+                                    let pdfium = match bind_pdfium() {
+                                        Ok(pdfium) => pdfium,
                                         Err(e) => {
-                                            eprintln!("Failed to bind to PDFium: {:?}", e);
+                                            eprintln!("Failed to bind to PDFium: {}", e);
                                             return;
                                         }
                                     };
@@ -2938,7 +6962,7 @@ impl<'a> FileGraphApp<'a> {
                             pos2(source_pos.x + center.x, source_pos.y + center.y),
                             pos2(target_pos.x + center.x, target_pos.y + center.y),
                         ],
-                        Stroke::new(1.0, Color32::GRAY),
+                        self.edge_style.stroke(1.0),
                     );
                 }
             }
@@ -2952,7 +6976,7 @@ impl<'a> FileGraphApp<'a> {
 
                 let (text, fill_color, stroke_color) = match node_data {
                     Some(GraphNode::File(name)) => {
-                        let path = PathBuf::from(name);
+                        let path = name.clone();
                         let is_selected = self
                             .selected_file_content
                             .as_ref()
@@ -2968,7 +6992,7 @@ impl<'a> FileGraphApp<'a> {
                         } else {
                             Color32::DARK_BLUE
                         };
-                        (name.clone(), fill, stroke)
+                        (name.display().to_string(), fill, stroke)
                     }
                     Some(GraphNode::Tag(tag)) => {
                         let is_filtered = self
@@ -2987,6 +7011,14 @@ impl<'a> FileGraphApp<'a> {
                         };
                         (format!("#{}", tag), fill, stroke)
                     }
+                    Some(GraphNode::External(url)) => {
+                        (url.clone(), Color32::GOLD, Color32::DARK_GRAY)
+                    }
+                    Some(GraphNode::Directory(dir)) => (
+                        dir.display().to_string(),
+                        DIRECTORY_NODE_COLOR,
+                        Color32::DARK_GRAY,
+                    ),
                     None => ("Unknown".to_string(), Color32::RED, Color32::BLACK),
                 };
 
@@ -3010,3 +7042,930 @@ impl<'a> FileGraphApp<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folders_load_their_children_on_first_expand() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("a/b")).unwrap();
+
+        let mut root = DirectoryNode::build_tree(dir.path());
+        assert!(root.populated);
+        let a = &mut root.children[0];
+        assert_eq!(a.path, dir.path().join("a"));
+        assert!(!a.populated && a.children.is_empty());
+
+        a.populate();
+        assert_eq!(a.children.len(), 1);
+        assert_eq!(a.children[0].path, dir.path().join("a/b"));
+        assert!(a.children[0].children.is_empty());
+    }
+
+    #[test]
+    fn fit_scale_fits_the_tighter_side_of_the_panel() {
+        let viewport = egui::vec2(800.0, 600.0);
+        // Wide image: the width decides, and it shrinks
+        assert_eq!(
+            ImageViewState::fit_scale(egui::vec2(1600.0, 400.0), viewport),
+            0.5
+        );
+        // Small tall image: the height decides, and it grows
+        assert_eq!(
+            ImageViewState::fit_scale(egui::vec2(100.0, 200.0), viewport),
+            3.0
+        );
+        assert_eq!(ImageViewState::fit_scale(egui::Vec2::ZERO, viewport), 1.0);
+    }
+
+    #[test]
+    fn hidden_folders_are_left_out_of_the_tree_unless_shown() {
+        let dir = tempfile::tempdir().unwrap();
+        for folder in ["notes", ".git", "notes/.obsidian", "notes/daily"] {
+            fs::create_dir_all(dir.path().join(folder)).unwrap();
+        }
+        let names = |node: &mut DirectoryNode, show_hidden: bool| {
+            let mut names: Vec<_> = node
+                .shown_children(show_hidden)
+                .map(|child| {
+                    child
+                        .path
+                        .file_name()
+                        .unwrap()
+                        .to_string_lossy()
+                        .to_string()
+                })
+                .collect();
+            names.sort();
+            names
+        };
+
+        let mut root = DirectoryNode::build_tree(dir.path());
+        assert_eq!(names(&mut root, false), ["notes"]);
+        assert_eq!(names(&mut root, true), [".git", "notes"]);
+
+        let notes = root
+            .children
+            .iter_mut()
+            .find(|c| c.path.ends_with("notes"))
+            .unwrap();
+        notes.populate();
+        assert_eq!(names(notes, false), ["daily"]);
+        // A hidden folder's name doesn't match the tree filter while it's hidden
+        assert!(!root.matches_filter("obsidian", false));
+        assert!(root.matches_filter("obsidian", true));
+    }
+
+    #[test]
+    fn tree_filter_matches_deep_folders_in_collapsed_subtrees() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("notes/2024/Projects")).unwrap();
+        fs::create_dir_all(dir.path().join("notes/.cache/projects-old")).unwrap();
+        fs::create_dir_all(dir.path().join("images")).unwrap();
+
+        let mut root = DirectoryNode::build_tree(dir.path());
+        assert!(root.matches_filter("", false));
+        assert!(root.matches_filter("projects", false));
+        let notes = &mut root.children[1];
+        assert!(notes.matches_filter("projects", false));
+        assert!(notes.matches_filter("2024", false));
+        assert!(!notes.matches_filter("missing", false));
+        // Only hidden folders match, so the query finds them only when they are shown
+        assert!(!notes.matches_filter("old", false));
+        assert!(notes.matches_filter("old", true));
+        let images = &mut root.children[0];
+        assert!(!images.matches_filter("projects", false));
+    }
+
+    #[test]
+    fn content_style_and_line_spacing_follow_reader_settings() {
+        let base = egui::Style::default();
+        let style = content_style(&base, 20.0, 1.5);
+        let size = |text_style: egui::TextStyle| text_style.resolve(&style).size;
+        assert_eq!(size(egui::TextStyle::Body), 20.0);
+        assert_eq!(size(egui::TextStyle::Monospace), 20.0);
+        assert_eq!(size(egui::TextStyle::Heading), 29.0);
+        assert_eq!(size(egui::TextStyle::Small), 15.0);
+        assert_eq!(
+            egui::TextStyle::Button.resolve(&style),
+            egui::TextStyle::Button.resolve(&base)
+        );
+
+        // Three lines at double spacing are three line heights tall
+        let ctx = egui::Context::default();
+        let _ = ctx.run(Default::default(), |_| {});
+        let font_id = egui::TextStyle::Body.resolve(&style);
+        let mut job = LayoutJob::default();
+        job.append(
+            "one\ntwo\nthree",
+            0.0,
+            content_text_format(font_id, Color32::WHITE, 2.0),
+        );
+        let galley = ctx.fonts(|f| f.layout_job(job));
+        assert_eq!(galley.rows.len(), 3);
+        assert!((galley.size().y - 3.0 * 40.0).abs() < 0.5);
+    }
+
+    #[test]
+    fn scan_settings_load_into_and_save_from_the_app() {
+        let dir = tempfile::tempdir().unwrap();
+        let settings = AppSettings {
+            respect_gitignore: true,
+            max_scan_depth: Some(2),
+            max_scan_file_size_mb: Some(5),
+            ..AppSettings::default()
+        };
+        let mut app = FileGraphApp::new(dir.path().to_path_buf(), settings.clone());
+        assert!(app.respect_gitignore);
+        assert_eq!(app.max_scan_depth, Some(2));
+        assert_eq!(app.max_scan_file_size(), Some(5 * 1024 * 1024));
+        assert_eq!(app.current_settings(), settings);
+
+        app.max_scan_depth = None;
+        app.max_scan_file_size_mb = Some(64);
+        let saved = app.current_settings();
+        assert_eq!(saved.max_scan_depth, None);
+        assert_eq!(saved.max_scan_file_size_mb, Some(64));
+
+        assert!(app.reset_settings(false));
+        assert_eq!(app.current_settings(), AppSettings::default());
+        assert!(!app.reset_settings(false));
+    }
+
+    #[test]
+    fn gif_waits_out_the_current_frame_delay() {
+        let delays = [0.1, 0.5, 0.2];
+        let timing = |elapsed| {
+            let (frame, until_next) = GifAnimation::frame_timing(&delays, elapsed);
+            (frame, (until_next * 1000.0).round() as u32)
+        };
+        assert_eq!(timing(0.0), (0, 100));
+        assert_eq!(timing(0.25), (1, 350));
+        assert_eq!(timing(0.7), (2, 100));
+        // Loops back to the start after 0.8s
+        assert_eq!(timing(0.85), (0, 50));
+        assert_eq!(GifAnimation::frame_timing(&[], 1.0).0, 0);
+    }
+
+    #[test]
+    fn hover_preview_is_read_again_once_the_file_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("note.md");
+        fs::write(&path, "first").unwrap();
+        let ctx = egui::Context::default();
+        let mut app = FileGraphApp::new(dir.path().to_path_buf(), AppSettings::default());
+        let wait_for_preview = |app: &mut FileGraphApp, modified: SystemTime| {
+            let (received, _, preview) = app
+                .hover_preview_receiver
+                .recv_timeout(Duration::from_secs(5))
+                .unwrap();
+            app.hover_previews.insert(received, (modified, preview));
+        };
+
+        let first = SystemTime::UNIX_EPOCH + Duration::from_secs(1);
+        assert_eq!(app.hover_preview(&path, first, &ctx), HoverPreview::Loading);
+        wait_for_preview(&mut app, first);
+        fs::write(&path, "second").unwrap();
+        // Same modification time: the cached excerpt, without reading the file
+        assert_eq!(
+            app.hover_preview(&path, first, &ctx),
+            HoverPreview::Text("first".to_string())
+        );
+
+        let second = first + Duration::from_secs(1);
+        assert_eq!(
+            app.hover_preview(&path, second, &ctx),
+            HoverPreview::Loading
+        );
+        wait_for_preview(&mut app, second);
+        assert_eq!(
+            app.hover_preview(&path, second, &ctx),
+            HoverPreview::Text("second".to_string())
+        );
+    }
+
+    #[test]
+    fn rust_fence_is_highlighted_and_longer_fences_nest_shorter_ones() {
+        let content =
+            "Intro\n````md\n```rust\nnot code\n```\n````\n```rust\nfn main() { let x = 1; }\n```\n";
+        let segments = split_markdown_code_fences(content);
+        assert_eq!(
+            segments,
+            vec![
+                MarkdownSegment::Text("Intro\n".to_string()),
+                MarkdownSegment::Code {
+                    language: "md".to_string(),
+                    code: "```rust\nnot code\n```\n".to_string(),
+                },
+                MarkdownSegment::Code {
+                    language: "rust".to_string(),
+                    code: "fn main() { let x = 1; }\n".to_string(),
+                },
+            ]
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let app = FileGraphApp::new(dir.path().to_path_buf(), AppSettings::default());
+        let syntax = app.get_syntax_for_language("rust").unwrap();
+        let mut highlighter = HighlightLines::new(syntax, *DEFAULT_THEME);
+        let default_color = DEFAULT_THEME.settings.foreground.unwrap();
+        let colors: HashSet<_> = highlighter
+            .highlight_line("fn main() { let x = 1; }\n", &SYNTAX_SET)
+            .unwrap()
+            .into_iter()
+            .map(|(style, _)| style.foreground)
+            .filter(|&color| color != default_color)
+            .collect();
+        assert!(colors.len() >= 2);
+    }
+
+    #[test]
+    fn startup_and_reset_share_one_settings_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut keybindings = KeyBindings::default();
+        keybindings.rebind(
+            Action::FocusSelected,
+            egui::KeyboardShortcut::new(egui::Modifiers::NONE, egui::Key::F9),
+        );
+        let settings = AppSettings {
+            show_physics_window: false,
+            content_font_size: 20.0,
+            content_line_spacing: 1.6,
+            graph_mode: GraphMode::Tags,
+            selected_node: Some(GraphNode::Tag("todo".to_string())),
+            show_content_panel: false,
+            max_graph_nodes: 300,
+            keybindings,
+            freeze_when_settled: true,
+            watch_debounce_ms: 250,
+            batch_watch_events: false,
+            safe_mode: true,
+            search_history: vec!["query".to_string()],
+            node_drag_threshold: 12.0,
+            respect_gitignore: true,
+            max_scan_depth: Some(1),
+            max_scan_file_size_mb: Some(8),
+        };
+
+        let mut app = FileGraphApp::new(dir.path().to_path_buf(), settings.clone());
+        assert_eq!(app.current_settings(), settings);
+
+        app.pending_selection = None;
+        assert!(app.reset_settings(false));
+        assert_eq!(
+            app.current_settings(),
+            AppSettings {
+                search_history: vec!["query".to_string()],
+                ..AppSettings::default()
+            }
+        );
+        app.reset_settings(true);
+        assert_eq!(app.current_settings(), AppSettings::default());
+    }
+
+    #[test]
+    fn graph_build_progress_rises_to_one() {
+        let mut scanner = FileScanner::new("/notes");
+        for i in 0..20 {
+            let path = PathBuf::from(format!("/notes/{}.md", i));
+            let next = PathBuf::from(format!("/notes/{}.md", (i + 1) % 20));
+            scanner.files.insert(path.clone(), vec![next]);
+            scanner.tags.insert(path, vec![format!("tag{}", i % 3)]);
+        }
+
+        let mut reported = Vec::new();
+        let graphs = build_all_graphs(
+            &scanner,
+            None,
+            false,
+            1000,
+            &AtomicBool::new(false),
+            &mut |progress, _| reported.push(progress),
+        )
+        .unwrap();
+
+        assert!(reported.len() > 20);
+        assert!(reported.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert_eq!(reported.last(), Some(&1.0));
+        assert_eq!(graphs.file_graph.graph.node_count(), 20);
+    }
+
+    #[test]
+    fn edge_style_sets_stroke_width_colour_and_glow() {
+        let style = EdgeStyle {
+            color: Color32::from_rgb(200, 40, 10),
+            opacity: 0.5,
+            width: 2.0,
+            glow: true,
+        };
+
+        let stroke = style.stroke(1.5);
+        assert_eq!(stroke.width, 3.0);
+        assert_eq!(
+            stroke.color,
+            Color32::from_rgba_premultiplied(200, 40, 10, 128)
+        );
+
+        let glow = style.glow_strokes(1.0, GraphQuality::High);
+        assert_eq!(glow.len(), 3);
+        assert!(glow.windows(2).all(|pair| pair[0].width > pair[1].width));
+        assert!(style.glow_strokes(1.0, GraphQuality::Low).is_empty());
+        let plain = EdgeStyle {
+            glow: false,
+            ..style
+        };
+        assert!(plain.glow_strokes(1.0, GraphQuality::High).is_empty());
+    }
+
+    #[test]
+    fn dropped_folders_are_scanned_and_a_lone_file_previewed() {
+        let dir = tempfile::tempdir().unwrap();
+        let (folder, other, note) = (
+            dir.path().join("vault"),
+            dir.path().join("other"),
+            dir.path().join("note.md"),
+        );
+        fs::create_dir_all(&folder).unwrap();
+        fs::create_dir_all(&other).unwrap();
+        fs::write(&note, "text").unwrap();
+
+        assert_eq!(
+            DropAction::classify(&[note.clone(), folder.clone(), other.clone()]),
+            DropAction::ScanDirectories(vec![folder, other])
+        );
+        assert_eq!(
+            DropAction::classify(std::slice::from_ref(&note)),
+            DropAction::PreviewFile(note)
+        );
+        assert_eq!(
+            DropAction::classify(&[dir.path().join("missing.md")]),
+            DropAction::Ignore
+        );
+    }
+
+    #[test]
+    fn label_font_size_scales_and_stays_within_bounds() {
+        assert_eq!(label_font_size(14.0, 1.0, 3.0, false), 14.0);
+        assert_eq!(label_font_size(14.0, 1.5, 1.0, false), 21.0);
+        assert_eq!(label_font_size(14.0, 1.0, 2.0, true), 28.0);
+        assert_eq!(label_font_size(14.0, 1.0, 0.1, true), MIN_LABEL_FONT_SIZE);
+        assert_eq!(label_font_size(14.0, 2.0, 5.0, true), MAX_LABEL_FONT_SIZE);
+    }
+
+    #[test]
+    fn central_view_shows_welcome_until_a_scan_then_graph_or_empty_notice() {
+        assert_eq!(CentralView::choose(false, false, 0), CentralView::Welcome);
+        assert_eq!(CentralView::choose(false, true, 0), CentralView::Graph);
+        assert_eq!(
+            CentralView::choose(true, false, 0),
+            CentralView::EmptyResult
+        );
+        assert_eq!(CentralView::choose(true, false, 3), CentralView::Graph);
+    }
+
+    #[test]
+    fn presentation_mode_hides_all_chrome_and_restores_it() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut app = FileGraphApp::new(dir.path().to_path_buf(), AppSettings::default());
+        let ctx = egui::Context::default();
+        let before = ChromeVisibility {
+            directory_panel: true,
+            content_panel: false,
+            physics_window: true,
+            display_window: false,
+            settings_window: true,
+            duplicates_window: false,
+        };
+        app.set_chrome_visibility(before);
+        app.graph_zoom_factor = 2.5;
+
+        app.toggle_presentation_mode(&ctx);
+        assert_eq!(app.chrome_visibility(), ChromeVisibility::HIDDEN);
+        assert_eq!(app.graph_zoom_factor, 1.0);
+
+        app.toggle_presentation_mode(&ctx);
+        assert_eq!(app.chrome_visibility(), before);
+        assert!(app.saved_chrome.is_none());
+    }
+
+    #[test]
+    fn files_are_coloured_by_their_top_level_folder() {
+        let root = PathBuf::from("/vault");
+        let mut app = FileGraphApp::new(root.clone(), AppSettings::default());
+        let nodes: Vec<NodeIndex> = [
+            "projects/a.md",
+            "projects/deep/b.md",
+            "journal/c.md",
+            "root.md",
+        ]
+        .iter()
+        .map(|path| {
+            app.file_graph
+                .graph
+                .add_node(GraphNode::File(root.join(path)))
+        })
+        .collect();
+        let tag = app
+            .file_graph
+            .graph
+            .add_node(GraphNode::Tag("todo".to_string()));
+
+        let mut with_tag = nodes.clone();
+        with_tag.push(tag);
+        let (colors, legend) = app.folder_colors(&with_tag);
+        assert_eq!(colors[&nodes[0]], colors[&nodes[1]]);
+        assert_ne!(colors[&nodes[0]], colors[&nodes[2]]);
+        assert_eq!(colors[&nodes[3]], ROOT_FOLDER_COLOR);
+        assert!(!colors.contains_key(&tag));
+        let folders: Vec<_> = legend.iter().map(|(folder, _)| folder.as_str()).collect();
+        assert_eq!(folders, ["journal", "projects"]);
+
+        // The same folders keep their hues, whatever order the nodes come in
+        let reversed: Vec<_> = nodes.iter().rev().copied().collect();
+        assert_eq!(app.folder_colors(&reversed), (colors, legend));
+    }
+
+    #[test]
+    fn saved_selection_maps_to_the_node_in_the_current_graph() {
+        let mut scanner = FileScanner::new("/notes");
+        for name in ["a", "b", "c"] {
+            let path = PathBuf::from(format!("/notes/{}.md", name));
+            scanner
+                .files
+                .insert(path.clone(), vec![PathBuf::from("/notes/a.md")]);
+            scanner.tags.insert(path, vec!["todo".to_string()]);
+        }
+        let graphs = build_all_graphs(
+            &scanner,
+            None,
+            false,
+            1000,
+            &AtomicBool::new(false),
+            &mut |_, _| {},
+        )
+        .unwrap();
+        let mut app = FileGraphApp::new(PathBuf::from("/notes"), AppSettings::default());
+        app.file_graph = graphs.file_graph;
+        app.tag_graph = graphs.tag_graph;
+        let saved_file = GraphNode::File(PathBuf::from("/notes/b.md"));
+        let saved_tag = GraphNode::Tag("todo".to_string());
+
+        app.current_graph_mode = GraphMode::Links;
+        let b = app.file_graph.node_indices()[Path::new("/notes/b.md")];
+        assert_eq!(app.node_index_for(&saved_file), Some(b));
+        assert_eq!(app.node_index_for(&saved_tag), None);
+
+        app.current_graph_mode = GraphMode::Tags;
+        assert_eq!(
+            app.node_index_for(&saved_file),
+            Some(app.tag_graph.file_node_indices()[Path::new("/notes/b.md")])
+        );
+        assert_eq!(
+            app.node_index_for(&saved_tag),
+            Some(app.tag_graph.tag_node_indices()["todo"])
+        );
+
+        // A file deleted since the last session leaves nothing selected
+        app.restore_selection(
+            &GraphNode::File(PathBuf::from("/notes/gone.md")),
+            &egui::Context::default(),
+        );
+        assert_eq!(app.selected_node, None);
+    }
+
+    #[test]
+    fn random_jump_only_lands_on_notes() {
+        use rand::SeedableRng;
+
+        let mut scanner = FileScanner::new("/notes");
+        for name in ["a.md", "b.md", "photo.png"] {
+            scanner
+                .files
+                .insert(PathBuf::from("/notes").join(name), Vec::new());
+            scanner
+                .tags
+                .insert(PathBuf::from("/notes").join(name), vec!["todo".to_string()]);
+        }
+        scanner.images.push(PathBuf::from("/notes/photo.png"));
+        scanner.external_links.insert(
+            PathBuf::from("/notes/a.md"),
+            vec!["https://example.com".to_string()],
+        );
+        let graphs = build_all_graphs(
+            &scanner,
+            None,
+            false,
+            1000,
+            &AtomicBool::new(false),
+            &mut |_, _| {},
+        )
+        .unwrap();
+        let mut app = FileGraphApp::new(PathBuf::from("/notes"), AppSettings::default());
+        app.file_graph = graphs.file_graph;
+        app.tag_graph = graphs.tag_graph;
+
+        for mode in [GraphMode::Links, GraphMode::Tags] {
+            app.current_graph_mode = mode;
+            let graph = match mode {
+                GraphMode::Tags => &app.tag_graph.graph,
+                _ => &app.file_graph.graph,
+            };
+            let candidates = app.random_node_candidates();
+            let mut names: Vec<_> = candidates
+                .iter()
+                .map(|&idx| match &graph[idx] {
+                    GraphNode::File(path) => {
+                        path.file_name().unwrap().to_string_lossy().to_string()
+                    }
+                    other => panic!("{:?} is not a note", other),
+                })
+                .collect();
+            names.sort();
+            assert_eq!(names, ["a.md", "b.md"]);
+
+            let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+            for _ in 0..20 {
+                let picked = pick_random_node(&candidates, &mut rng).unwrap();
+                assert!(candidates.contains(&picked));
+            }
+        }
+        assert_eq!(pick_random_node(&[], &mut rand::rng()), None);
+    }
+
+    fn key_press(key: egui::Key) -> egui::Event {
+        egui::Event::Key {
+            key,
+            physical_key: None,
+            pressed: true,
+            repeat: false,
+            modifiers: egui::Modifiers::NONE,
+        }
+    }
+
+    #[test]
+    fn p_toggles_between_names_and_full_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut app = FileGraphApp::new(dir.path().to_path_buf(), AppSettings::default());
+        let ctx = egui::Context::default();
+        let press_p = |app: &mut FileGraphApp| {
+            let input = egui::RawInput {
+                events: vec![key_press(egui::Key::P)],
+                ..Default::default()
+            };
+            let _ = ctx.run(input, |ctx| {
+                if app.action_pressed(ctx, Action::ToggleFullPaths) {
+                    app.run_action(Action::ToggleFullPaths, ctx);
+                }
+            });
+        };
+        let path = Path::new("/notes/projects/plan.md");
+
+        assert!(!app.show_full_paths);
+        assert_eq!(display_path(path, app.show_full_paths), "plan.md");
+        press_p(&mut app);
+        assert!(app.show_full_paths);
+        assert_eq!(
+            display_path(path, app.show_full_paths),
+            "/notes/projects/plan.md"
+        );
+        press_p(&mut app);
+        assert!(!app.show_full_paths);
+    }
+
+    #[test]
+    fn file_size_radius_grows_with_size_and_is_clamped() {
+        let sizes = [0, 1, 1024, 100 * 1024, 1 << 20, 1 << 30, u64::MAX];
+        let scales: Vec<f32> = sizes
+            .iter()
+            .map(|&bytes| file_size_radius_scale(bytes))
+            .collect();
+        assert!(scales.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert!(scales.iter().all(|scale| (0.6..=2.5).contains(scale)));
+        assert_eq!(scales[0], 0.6);
+        assert_eq!(file_size_radius_scale(u64::MAX), 2.5);
+        // Logarithmic: a thousand times bigger is only about one step larger
+        assert!(file_size_radius_scale(1 << 20) - file_size_radius_scale(1 << 10) < 1.1);
+    }
+
+    #[test]
+    fn plain_text_is_shown_in_the_selectable_view() {
+        assert_eq!(
+            TextView::choose(false, false, false),
+            TextView::SelectableText
+        );
+        assert_eq!(
+            TextView::choose(false, false, true),
+            TextView::SearchableText
+        );
+        assert_eq!(TextView::choose(false, true, false), TextView::Code);
+        assert_eq!(TextView::choose(true, false, false), TextView::Markdown);
+        assert_eq!(
+            TextView::choose(true, false, true),
+            TextView::MarkdownSource
+        );
+    }
+
+    #[test]
+    fn layout_freezes_once_it_first_settles_after_a_scan() {
+        let dir = tempfile::tempdir().unwrap();
+        let settings = AppSettings {
+            freeze_when_settled: true,
+            ..AppSettings::default()
+        };
+        let mut app = FileGraphApp::new(dir.path().to_path_buf(), settings);
+        let node = NodeIndex::new(0);
+        app.physics_simulator
+            .node_positions
+            .insert(node, egui::Vec2::ZERO);
+        app.physics_simulator
+            .node_velocities
+            .insert(node, egui::vec2(5.0, 0.0));
+        app.awaiting_settle = true;
+
+        app.freeze_if_settled();
+        assert!(!app.physics_simulator.frozen);
+
+        app.physics_simulator
+            .node_velocities
+            .insert(node, egui::vec2(0.01, 0.0));
+        assert!(app.physics_simulator.kinetic_energy() < crate::physics_nodes::SETTLED_ENERGY);
+        app.freeze_if_settled();
+        assert!(app.physics_simulator.frozen);
+
+        // Reheating by hand isn't undone until the next scan
+        app.physics_simulator.frozen = false;
+        app.freeze_if_settled();
+        assert!(!app.physics_simulator.frozen);
+    }
+
+    #[test]
+    fn safe_mode_blocks_file_changing_actions() {
+        let dir = tempfile::tempdir().unwrap();
+        let note = dir.path().join("note.md");
+        fs::write(&note, "hello\n").unwrap();
+        let settings = AppSettings {
+            safe_mode: true,
+            ..AppSettings::default()
+        };
+        let mut app = FileGraphApp::new(dir.path().to_path_buf(), settings);
+        let ctx = egui::Context::default();
+        assert!(app.file_edit_blocked(&note));
+
+        app.rename_input = "renamed".to_string();
+        app.rename_file(&note);
+        app.create_note_beside(&note);
+        app.trash_file(&note);
+        app.new_tag_input = "draft".to_string();
+        app.add_tag_to_file(&note, &ctx);
+
+        assert_eq!(fs::read_to_string(&note).unwrap(), "hello\n");
+        let names: Vec<_> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(names, vec![std::ffi::OsString::from("note.md")]);
+
+        app.safe_mode = false;
+        assert!(!app.file_edit_blocked(&note));
+    }
+
+    #[test]
+    fn path_lookup_finds_the_node_in_each_graph_mode() {
+        let mut scanner = FileScanner::new("/notes");
+        for name in ["a.md", "b.md", "photo.png"] {
+            let path = PathBuf::from("/notes").join(name);
+            scanner.files.insert(path.clone(), Vec::new());
+            scanner.tags.insert(path, vec!["todo".to_string()]);
+        }
+        scanner.images.push(PathBuf::from("/notes/photo.png"));
+        let graphs = build_all_graphs(
+            &scanner,
+            None,
+            false,
+            1000,
+            &AtomicBool::new(false),
+            &mut |_, _| {},
+        )
+        .unwrap();
+        let mut app = FileGraphApp::new(PathBuf::from("/notes"), AppSettings::default());
+        app.file_graph = graphs.file_graph;
+        app.tag_graph = graphs.tag_graph;
+        app.unified_graph = graphs.unified_graph;
+
+        for mode in [GraphMode::Links, GraphMode::Unified, GraphMode::Tags] {
+            app.current_graph_mode = mode;
+            let graph = match mode {
+                GraphMode::Links => &app.file_graph.graph,
+                GraphMode::Unified => &app.unified_graph.graph,
+                GraphMode::Tags => &app.tag_graph.graph,
+            };
+            for name in ["a.md", "b.md", "photo.png"] {
+                let path = PathBuf::from("/notes").join(name);
+                let idx = app
+                    .node_for_path(&path)
+                    .unwrap_or_else(|| panic!("{:?} {}", mode, name));
+                assert_eq!(graph[idx], GraphNode::File(path), "{:?}", mode);
+            }
+            assert_eq!(app.node_for_path(Path::new("/notes/gone.md")), None);
+        }
+    }
+
+    #[test]
+    fn status_bar_shows_the_draw_set_and_hovered_node() {
+        let mut scanner = FileScanner::new("/notes");
+        scanner.files.insert(
+            PathBuf::from("/notes/a.md"),
+            vec![PathBuf::from("/notes/b.md")],
+        );
+        scanner
+            .files
+            .insert(PathBuf::from("/notes/b.md"), Vec::new());
+        scanner
+            .tags
+            .insert(PathBuf::from("/notes/a.md"), vec!["todo".to_string()]);
+        let graphs = build_all_graphs(
+            &scanner,
+            None,
+            false,
+            1000,
+            &AtomicBool::new(false),
+            &mut |_, _| {},
+        )
+        .unwrap();
+        let mut app = FileGraphApp::new(PathBuf::from("/notes"), AppSettings::default());
+        app.file_graph = graphs.file_graph;
+        app.tag_graph = graphs.tag_graph;
+        app.current_graph_mode = GraphMode::Links;
+        app.graph_zoom_factor = 1.5;
+        app.visible_counts = (2, 1);
+        app.hovered_node = Some(app.file_graph.node_indices()[Path::new("/notes/a.md")]);
+
+        assert_eq!(
+            app.status_bar(),
+            StatusBar {
+                visible_nodes: 2,
+                visible_edges: 1,
+                graph_mode: GraphMode::Links,
+                zoom: 1.5,
+                hovered: Some(("a.md".to_string(), "/notes/a.md".to_string())),
+            }
+        );
+
+        // A tag shows its name in both places; nothing hovered leaves the slot empty
+        app.current_graph_mode = GraphMode::Tags;
+        app.hovered_node = Some(app.tag_graph.tag_node_indices()["todo"]);
+        let status = app.status_bar();
+        assert_eq!(status.graph_mode, GraphMode::Tags);
+        assert_eq!(
+            status.hovered,
+            Some(("#todo".to_string(), "#todo".to_string()))
+        );
+        app.hovered_node = None;
+        assert_eq!(app.status_bar().hovered, None);
+    }
+
+    #[test]
+    fn low_quality_draws_fewer_passes_per_edge() {
+        let style = EdgeStyle {
+            color: Color32::from_rgb(90, 160, 255),
+            opacity: 1.0,
+            width: 1.5,
+            glow: true,
+        };
+        // Glow strokes plus the main stroke
+        let passes_per_edge = |quality| style.glow_strokes(1.0, quality).len() + 1;
+        assert_eq!(passes_per_edge(GraphQuality::High), 4);
+        assert_eq!(passes_per_edge(GraphQuality::Low), 1);
+
+        // Node glow and hover rings are dropped the same way
+        for full in [0, 3, 5] {
+            assert_eq!(GraphQuality::High.effect_passes(full), full);
+            assert_eq!(GraphQuality::Low.effect_passes(full), 0);
+        }
+    }
+
+    #[test]
+    fn placed_node_follows_the_pointer_until_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut app = FileGraphApp::new(dir.path().to_path_buf(), AppSettings::default());
+        let node = NodeIndex::new(0);
+        let origin = egui::vec2(10.0, 20.0);
+        app.physics_simulator.node_positions.insert(node, origin);
+
+        app.pick_up_or_drop(node, origin);
+        assert_eq!(app.held_node, Some((node, origin)));
+        assert!(app.physics_simulator.pinned_nodes.contains(&node));
+
+        // Held, it tracks the pointer; leaving the view keeps the last spot
+        app.carry_held_node(Some(egui::vec2(-40.0, 5.0)), false);
+        assert_eq!(
+            app.physics_simulator.get_node_position(node),
+            Some(&egui::vec2(-40.0, 5.0))
+        );
+        app.carry_held_node(None, false);
+        assert_eq!(
+            app.physics_simulator.get_node_position(node),
+            Some(&egui::vec2(-40.0, 5.0))
+        );
+
+        // A click drops it where the pointer is, still pinned
+        app.carry_held_node(Some(egui::vec2(70.0, 70.0)), true);
+        assert_eq!(app.held_node, None);
+        assert_eq!(
+            app.physics_simulator.get_node_position(node),
+            Some(&egui::vec2(70.0, 70.0))
+        );
+        assert!(app.physics_simulator.pinned_nodes.contains(&node));
+        app.carry_held_node(Some(egui::Vec2::ZERO), false);
+        assert_eq!(
+            app.physics_simulator.get_node_position(node),
+            Some(&egui::vec2(70.0, 70.0))
+        );
+
+        // Picking it up again and cancelling puts it back, free to move
+        app.pick_up_or_drop(node, egui::vec2(70.0, 70.0));
+        app.carry_held_node(Some(egui::vec2(0.0, -90.0)), false);
+        app.cancel_held_node();
+        assert_eq!(app.held_node, None);
+        assert_eq!(
+            app.physics_simulator.get_node_position(node),
+            Some(&egui::vec2(70.0, 70.0))
+        );
+        assert!(!app.physics_simulator.pinned_nodes.contains(&node));
+    }
+
+    #[test]
+    fn added_tags_are_lowercased_to_merge_with_scanned_ones() {
+        let dir = tempfile::tempdir().unwrap();
+        let note = dir.path().join("note.md");
+        let other = dir.path().join("other.md");
+        fs::write(&note, "hello\n").unwrap();
+        fs::write(&other, "#foo\n").unwrap();
+        let mut app = FileGraphApp::new(dir.path().to_path_buf(), AppSettings::default());
+        app.scanner
+            .lock()
+            .unwrap()
+            .tags
+            .insert(other.clone(), vec!["foo".to_string()]);
+
+        app.new_tag_input = "#Foo".to_string();
+        app.add_tag_to_file(&note, &egui::Context::default());
+        assert_eq!(fs::read_to_string(&note).unwrap(), "hello\n\n#foo\n");
+
+        let scanner = app.scanner.lock().unwrap();
+        assert_eq!(scanner.tags[&note], ["foo"]);
+        let mut tag_graph = TagGraph::new();
+        tag_graph.build_from_tags(&scanner, false, usize::MAX, &mut |_| {});
+        assert_eq!(tag_graph.tag_node_indices.len(), 1);
+        assert!(scanner.has_tag_matching(&note, &"Foo".to_lowercase()));
+    }
+
+    #[test]
+    fn clearing_the_graphs_cancels_a_running_build() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut app = FileGraphApp::new(dir.path().to_path_buf(), AppSettings::default());
+        app.build_graphs(&egui::Context::default());
+        let first = app.graph_build_cancel.clone();
+        // A second build takes over from the first
+        app.build_graphs(&egui::Context::default());
+        assert!(first.load(Ordering::Relaxed));
+        let second = app.graph_build_cancel.clone();
+        assert!(!second.load(Ordering::Relaxed));
+
+        app.clear_graph_data();
+        assert!(second.load(Ordering::Relaxed));
+        assert!(app.graph_build_receiver.is_none());
+
+        // The worker holds only a copy of the scan, never the shared lock
+        let mut scanner = FileScanner::new("/notes");
+        scanner
+            .files
+            .insert(PathBuf::from("/notes/a.md"), Vec::new());
+        let snapshot = scanner.snapshot();
+        assert_eq!(snapshot.files, scanner.files);
+        let cancel = AtomicBool::new(true);
+        assert!(build_all_graphs(&snapshot, None, false, 1000, &cancel, &mut |_, _| {}).is_none());
+    }
+
+    #[test]
+    fn tree_filter_answers_are_cached_per_query() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("notes/projects")).unwrap();
+        fs::create_dir_all(dir.path().join("notes/.archive")).unwrap();
+        let mut root = DirectoryNode::build_tree(dir.path());
+        assert!(root.matches_filter("projects", false));
+
+        // Another query or hidden-files setting looks at the folders again
+        assert!(!root.matches_filter("archive", false));
+        assert!(root.matches_filter("archive", true));
+
+        // With the children gone the cached answer still stands, until cleared
+        assert!(root.matches_filter("projects", false));
+        root.children[0].children.clear();
+        assert!(root.matches_filter("projects", false));
+        root.clear_filter_matches();
+        assert!(!root.matches_filter("projects", false));
+    }
+}
@@ -1,5 +1,9 @@
 // src/util.rs
-use std::path::Path;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read};
+use std::ops::Range;
+use std::path::{Component, Path, PathBuf};
+use std::time::SystemTime;
 
 pub fn is_image_path(path: &Path) -> bool {
     path.extension().map_or(false, |ext| {
@@ -8,6 +12,11 @@ pub fn is_image_path(path: &Path) -> bool {
     })
 }
 
+pub fn is_hidden_path(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+}
+
 pub fn is_markdown_path(path: &Path) -> bool {
     path.extension().map_or(false, |ext| {
         ext.to_str().unwrap_or("").to_lowercase() == "md"
@@ -21,12 +30,542 @@ pub fn is_code_path(path: &Path) -> bool {
     })
 }
 
+pub fn is_html_path(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| {
+        let ext_lower = ext.to_str().unwrap_or("").to_lowercase();
+        ext_lower == "html" || ext_lower == "htm"
+    })
+}
+
+pub fn is_zip_path(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.to_string_lossy().eq_ignore_ascii_case("zip"))
+}
+
+// `segments` points around a circle, closed by repeating the first one
+pub fn circle_points(center: egui::Pos2, radius: f32, segments: usize) -> Vec<egui::Pos2> {
+    (0..=segments)
+        .map(|i| {
+            let angle = i as f32 / segments as f32 * std::f32::consts::TAU;
+            center + radius * egui::vec2(angle.cos(), angle.sin())
+        })
+        .collect()
+}
+
+// Subsequence match of `query` in `text`, ignoring case and spaces in the query. Letters
+// at word starts and runs of consecutive letters score higher; None when nothing matches.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut next = 0;
+    for c in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = (next..text.len()).find(|&i| text[i] == c)?;
+        score += 1;
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        if found > 0 && found == next {
+            score += 2;
+        }
+        next = found + 1;
+    }
+    Some(score)
+}
+
+// Folders and zip archives can both be scanned
+pub fn is_scan_root(path: &Path) -> bool {
+    path.is_dir() || (is_zip_path(path) && path.is_file())
+}
+
+// The zip archive a scanned path lives in, for files that only exist inside one
+pub fn containing_archive(path: &Path) -> Option<&Path> {
+    path.ancestors()
+        .skip(1)
+        .find(|ancestor| is_zip_path(ancestor) && ancestor.is_file())
+}
+
+// Remove `.` and `..` without touching the filesystem, so targets match scanned paths
+pub fn normalize_lexically(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
 pub fn is_pdf_path(path: &Path) -> bool {
     path.extension().map_or(false, |ext| {
         ext.to_str().unwrap_or("").to_lowercase() == "pdf"
     })
 }
 
+// First directory below `root` that contains `path`, or None for files directly in `root`
+pub fn top_level_folder(path: &Path, root: &Path) -> Option<String> {
+    let relative = path.strip_prefix(root).ok()?;
+    let mut components = relative.components();
+    let first = components.next()?;
+    components
+        .next()
+        .map(|_| first.as_os_str().to_string_lossy().into_owned())
+}
+
+pub fn is_external_link(link: &str) -> bool {
+    let link = link.trim().to_lowercase();
+    link.starts_with("http://") || link.starts_with("https://")
+}
+
+// Case-insensitive; `filter_lower` is lowercased by the caller, once per filter
+pub fn tag_matches_filter(tag: &str, filter_lower: &str) -> bool {
+    filter_lower.is_empty() || tag.to_lowercase().contains(filter_lower)
+}
+
+// Tags completing the last word of `input`: prefix matches first, then any other
+// tag containing it, case-insensitively
+pub fn tag_completions<'a>(
+    tags: impl IntoIterator<Item = &'a str>,
+    input: &str,
+    limit: usize,
+) -> Vec<String> {
+    let token = match input.split_whitespace().last() {
+        Some(token) if !input.ends_with(char::is_whitespace) => token.to_lowercase(),
+        _ => return Vec::new(),
+    };
+    let mut prefix_matches = Vec::new();
+    let mut other_matches = Vec::new();
+    for tag in tags {
+        let lower = tag.to_lowercase();
+        if lower == token {
+            continue;
+        } else if lower.starts_with(&token) {
+            prefix_matches.push(tag.to_string());
+        } else if lower.contains(&token) {
+            other_matches.push(tag.to_string());
+        }
+    }
+    for matches in [&mut prefix_matches, &mut other_matches] {
+        matches.sort();
+        matches.dedup();
+    }
+    prefix_matches.extend(other_matches);
+    prefix_matches.truncate(limit);
+    prefix_matches
+}
+
+// `input` with its last word replaced by `completion`
+pub fn apply_tag_completion(input: &str, completion: &str) -> String {
+    let start = input.rfind(char::is_whitespace).map_or(0, |i| {
+        i + input[i..].chars().next().map_or(1, char::len_utf8)
+    });
+    format!("{}{}", &input[..start], completion)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MarkdownSegment {
+    Text(String),
+    Code { language: String, code: String },
+}
+
+// Split markdown into prose and fenced code blocks so fences can be highlighted
+pub fn split_markdown_code_fences(content: &str) -> Vec<MarkdownSegment> {
+    let mut segments = Vec::new();
+    let mut text = String::new();
+    let mut code = String::new();
+    // The fence character, how many of it opened the block, and the block's language
+    let mut fence: Option<(char, usize, String)> = None;
+
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        match &fence {
+            Some((marker, length, language)) => {
+                // Closed by a run of the same character at least as long as the opener
+                let closing = trimmed.trim_end();
+                if closing.len() >= *length && closing.chars().all(|c| c == *marker) {
+                    segments.push(MarkdownSegment::Code {
+                        language: language.clone(),
+                        code: std::mem::take(&mut code),
+                    });
+                    fence = None;
+                } else {
+                    code.push_str(line);
+                }
+            }
+            None => {
+                let marker = trimmed.chars().next().filter(|&c| c == '`' || c == '~');
+                let length = marker.map_or(0, |marker| {
+                    trimmed.chars().take_while(|&c| c == marker).count()
+                });
+                if let Some(marker) = marker
+                    && length >= 3
+                {
+                    if !text.is_empty() {
+                        segments.push(MarkdownSegment::Text(std::mem::take(&mut text)));
+                    }
+                    let language = trimmed[length..]
+                        .split_whitespace()
+                        .next()
+                        .unwrap_or("")
+                        .to_string();
+                    fence = Some((marker, length, language));
+                } else {
+                    text.push_str(line);
+                }
+            }
+        }
+    }
+
+    // An unclosed fence runs to the end of the document
+    if let Some((_, _, language)) = fence {
+        segments.push(MarkdownSegment::Code { language, code });
+    }
+    if !text.is_empty() {
+        segments.push(MarkdownSegment::Text(text));
+    }
+    segments
+}
+
+// Open `path` with the system's default application. Errors instead of panicking
+// when the opener can't be started, e.g. when it isn't installed.
+pub fn open_with_default_app(path: &Path) -> Result<(), String> {
+    #[cfg(target_os = "linux")]
+    let command = std::process::Command::new("xdg-open");
+    #[cfg(target_os = "macos")]
+    let command = std::process::Command::new("open");
+    #[cfg(target_os = "windows")]
+    let command = {
+        // The empty argument is `start`'s window title, so quoted paths aren't taken for one
+        let mut command = std::process::Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    };
+    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+    return Err("Opening files is not supported on this platform".to_string());
+
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+    spawn_opener(command, path)
+}
+
+fn spawn_opener(mut command: std::process::Command, path: &Path) -> Result<(), String> {
+    command
+        .arg(path)
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| format!("Could not open {}: {}", path.display(), e))
+}
+
+// Count points per cell of a `cols` x `rows` grid laid over `bounds`, row-major.
+// Points outside `bounds` are ignored.
+pub fn density_grid(
+    points: &[egui::Pos2],
+    bounds: egui::Rect,
+    cols: usize,
+    rows: usize,
+) -> Vec<f32> {
+    let mut grid = vec![0.0; cols * rows];
+    if cols == 0 || rows == 0 || !bounds.is_positive() {
+        return grid;
+    }
+    let cell = egui::vec2(bounds.width() / cols as f32, bounds.height() / rows as f32);
+    for point in points {
+        if !bounds.contains(*point) {
+            continue;
+        }
+        let offset = *point - bounds.min;
+        let col = ((offset.x / cell.x) as usize).min(cols - 1);
+        let row = ((offset.y / cell.y) as usize).min(rows - 1);
+        grid[row * cols + col] += 1.0;
+    }
+    grid
+}
+
+// Spread each cell into its neighbours with a 1-2-1 kernel, a cheap gaussian blur
+pub fn smooth_grid(grid: &[f32], cols: usize, rows: usize) -> Vec<f32> {
+    let blur = |grid: &[f32], horizontal: bool| -> Vec<f32> {
+        let mut out = vec![0.0; grid.len()];
+        for row in 0..rows {
+            for col in 0..cols {
+                let at = |dc: isize, dr: isize| {
+                    let (c, r) = (col as isize + dc, row as isize + dr);
+                    if c < 0 || r < 0 || c >= cols as isize || r >= rows as isize {
+                        0.0
+                    } else {
+                        grid[r as usize * cols + c as usize]
+                    }
+                };
+                out[row * cols + col] = if horizontal {
+                    (at(-1, 0) + 2.0 * at(0, 0) + at(1, 0)) / 4.0
+                } else {
+                    (at(0, -1) + 2.0 * at(0, 0) + at(0, 1)) / 4.0
+                };
+            }
+        }
+        out
+    };
+    blur(&blur(grid, true), false)
+}
+
+// Fraction of the way along its edge, in [0, 1), of flow dot `dot` out of `dots` at
+// `time`; the dots are spread evenly and wrap back to the source
+pub fn flow_phase(time: f32, speed: f32, dot: usize, dots: usize) -> f32 {
+    (time * speed + dot as f32 / dots.max(1) as f32).rem_euclid(1.0)
+}
+
+// Point at `t` along a straight edge, or along its curve when it has a control point
+pub fn point_on_edge(
+    start: egui::Pos2,
+    control: Option<egui::Pos2>,
+    end: egui::Pos2,
+    t: f32,
+) -> egui::Pos2 {
+    match control {
+        Some(control) => start.lerp(control, t).lerp(control.lerp(end, t), t),
+        None => start.lerp(end, t),
+    }
+}
+
+// Unit direction an edge arrives at `end` in, along its curve when it has a control
+// point; arrowheads point this way
+pub fn edge_end_direction(
+    start: egui::Pos2,
+    control: Option<egui::Pos2>,
+    end: egui::Pos2,
+) -> egui::Vec2 {
+    (end - control.unwrap_or(start)).normalized()
+}
+
+// Whether pointer travel since the press is enough to count as a drag rather than a click
+pub fn exceeds_drag_threshold(travel: egui::Vec2, threshold: f32) -> bool {
+    travel.length() > threshold
+}
+
+// Control point bending an edge sideways by `curvature` times its length. An edge
+// in the opposite direction bends the other way, so the pair never overlaps.
+pub fn curved_control_point(start: egui::Pos2, end: egui::Pos2, curvature: f32) -> egui::Pos2 {
+    let along = end - start;
+    start.lerp(end, 0.5) + along.rot90() * curvature
+}
+
+// Offset after zooming from `old_zoom` to `new_zoom` that keeps the graph point under
+// `anchor` (relative to the view centre) in place
+pub fn zoom_about(
+    offset: egui::Vec2,
+    anchor: egui::Pos2,
+    old_zoom: f32,
+    new_zoom: f32,
+) -> egui::Vec2 {
+    let anchor = anchor.to_vec2();
+    anchor - (anchor - offset) * (new_zoom / old_zoom)
+}
+
+// Graph coordinates under a point given relative to the view centre
+pub fn screen_to_graph(local: egui::Pos2, offset: egui::Vec2, zoom: f32) -> egui::Vec2 {
+    (local.to_vec2() - offset) / zoom
+}
+
+// Graph offset that puts `node_pos` at the centre of the view at `zoom`
+pub fn follow_offset(node_pos: egui::Vec2, zoom: f32) -> egui::Vec2 {
+    -node_pos * zoom
+}
+
+// Mean position of each node's neighbours along `edges`
+pub fn neighbor_centroids<K: Copy + Eq + std::hash::Hash>(
+    edges: &[(K, K)],
+    positions: &HashMap<K, egui::Vec2>,
+) -> HashMap<K, egui::Vec2> {
+    let mut sums: HashMap<K, (egui::Vec2, f32)> = HashMap::new();
+    for &(start, end) in edges {
+        let (Some(&start_pos), Some(&end_pos)) = (positions.get(&start), positions.get(&end))
+        else {
+            continue;
+        };
+        let start_sum = sums.entry(start).or_insert((egui::Vec2::ZERO, 0.0));
+        start_sum.0 += end_pos;
+        start_sum.1 += 1.0;
+        let end_sum = sums.entry(end).or_insert((egui::Vec2::ZERO, 0.0));
+        end_sum.0 += start_pos;
+        end_sum.1 += 1.0;
+    }
+    sums.into_iter()
+        .map(|(node, (sum, count))| (node, sum / count))
+        .collect()
+}
+
+// Control point of a bundled edge: its midpoint pulled towards the average of the
+// endpoints' neighbour centroids, so edges around a shared hub curve the same way.
+// `strength` 0 keeps the edge straight, 1 pulls all the way.
+pub fn bundled_control_point(
+    start: egui::Pos2,
+    end: egui::Pos2,
+    start_centroid: egui::Pos2,
+    end_centroid: egui::Pos2,
+    strength: f32,
+) -> egui::Pos2 {
+    let midpoint = start.lerp(end, 0.5);
+    let pull = start_centroid.lerp(end_centroid, 0.5);
+    midpoint.lerp(pull, strength.clamp(0.0, 1.0))
+}
+
+// How a file is named in labels, tooltips and the content panel
+pub fn display_path(path: &Path, full_path: bool) -> String {
+    match path.file_name() {
+        Some(name) if !full_path => name.to_string_lossy().into_owned(),
+        _ => path.display().to_string(),
+    }
+}
+
+// `path` relative to `root`, or unchanged when it lies outside it
+pub fn relative_to_root(path: &Path, root: &Path) -> PathBuf {
+    path.strip_prefix(root)
+        .map_or_else(|_| path.to_path_buf(), Path::to_path_buf)
+}
+
+// `content` with `#tag` added to its tags line (the last line made only of tags),
+// or on a new line at the end. None if the file already has the tag.
+pub fn append_tag(content: &str, tag: &str) -> Option<String> {
+    // Same notion of a tag as the scanner, which ignores case
+    let tag_re = regex::Regex::new(r"#(\w+)").unwrap();
+    if tag_re
+        .captures_iter(content)
+        .any(|cap| cap[1].to_lowercase() == tag.to_lowercase())
+    {
+        return None;
+    }
+
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+    let tags_line = lines.iter().rposition(|line| {
+        let mut words = line.split_whitespace().peekable();
+        words.peek().is_some()
+            && words.all(|word| tag_re.find(word).is_some_and(|m| m.as_str() == word))
+    });
+    match tags_line {
+        Some(i) => lines[i] = format!("{} #{}", lines[i].trim_end(), tag),
+        None => {
+            if lines.last().is_some_and(|line| !line.trim().is_empty()) {
+                lines.push(String::new());
+            }
+            lines.push(format!("#{}", tag));
+        }
+    }
+    Some(lines.join("\n") + "\n")
+}
+
+// `content` with its links to a file named like `from` renamed to `to`'s name:
+// `[text](dir/from.md)`, `[ref]: from.md`, `[[from]]` and `[[from|alias]]`. Folders in
+// the link are kept. None when nothing in it refers to `from`.
+pub fn rename_link_targets(content: &str, from: &Path, to: &Path) -> Option<String> {
+    let from_name = from.file_name()?.to_str()?;
+    let to_name = to.file_name()?.to_str()?;
+    let from_stem = from.file_stem()?.to_str()?;
+    let to_stem = to.file_stem()?.to_str()?;
+    // The last path component of a link, swapped when it names `from`
+    let rename = |target: &str, with_stem: bool| -> Option<String> {
+        let (folder, name) = match target.rfind('/') {
+            Some(slash) => target.split_at(slash + 1),
+            None => ("", target),
+        };
+        let renamed = if name == from_name {
+            to_name
+        } else if with_stem && name == from_stem {
+            to_stem
+        } else {
+            return None;
+        };
+        Some(format!("{}{}", folder, renamed))
+    };
+
+    let link_re = regex::Regex::new(
+        r"(?m)(?P<open>\]\(|^ {0,3}\[[^\]]+\]:[ \t]*<?)(?P<target>[^)\s>]+)|(?P<wiki>\[\[)(?P<page>[^\]|]+)",
+    )
+    .unwrap();
+    let mut changed = false;
+    let updated = link_re.replace_all(content, |cap: &regex::Captures| {
+        let (open, target, with_stem) = match cap.name("target") {
+            Some(target) => (&cap["open"], target.as_str(), false),
+            None => (&cap["wiki"], &cap["page"], true),
+        };
+        match rename(target, with_stem) {
+            Some(renamed) => {
+                changed = true;
+                format!("{}{}", open, renamed)
+            }
+            None => cap[0].to_string(),
+        }
+    });
+    changed.then(|| updated.into_owned())
+}
+
+// Markdown bullet list of wikilinks, one per file, named without the extension
+pub fn wikilink_list(paths: &[PathBuf]) -> String {
+    paths
+        .iter()
+        .map(|path| {
+            let name = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_else(|| path.display().to_string());
+            format!("- [[{}]]", name)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Moves `query` to the front of `history`, dropping older copies and anything past `cap`
+pub fn push_history(history: &mut Vec<String>, query: &str, cap: usize) {
+    let query = query.trim();
+    if query.is_empty() {
+        return;
+    }
+    history.retain(|past| past != query);
+    history.insert(0, query.to_string());
+    history.truncate(cap);
+}
+
+// Byte ranges of every (ASCII case-insensitive) occurrence of `query`
+pub fn find_match_ranges(content: &str, query: &str) -> Vec<Range<usize>> {
+    if query.is_empty() {
+        return Vec::new();
+    }
+    let haystack = content.to_ascii_lowercase();
+    let needle = query.to_ascii_lowercase();
+    haystack
+        .match_indices(&needle)
+        .map(|(start, _)| start..start + needle.len())
+        .collect()
+}
+
+// First `max_lines` lines of a file, each cut to `max_line_chars`. The rest of a long
+// line is skipped unread into memory, so a minified file isn't loaded whole.
+pub fn read_excerpt(path: &Path, max_lines: usize, max_line_chars: usize) -> Option<String> {
+    let mut reader = BufReader::new(std::fs::File::open(path).ok()?);
+    let line_bytes = (max_line_chars * 4 + 1) as u64;
+    let mut lines = Vec::new();
+    while lines.len() < max_lines {
+        let mut bytes = Vec::new();
+        let read = (&mut reader)
+            .take(line_bytes)
+            .read_until(b'\n', &mut bytes)
+            .ok()?;
+        if read == 0 {
+            break;
+        }
+        if bytes.last() != Some(&b'\n') {
+            reader.skip_until(b'\n').ok()?;
+        }
+        let line = String::from_utf8_lossy(&bytes);
+        lines.push(truncate_label(
+            line.trim_end_matches(['\n', '\r']),
+            max_line_chars,
+            LabelTruncation::End,
+        ));
+    }
+    Some(lines.join("\n"))
+}
+
 pub fn rotate_vec2(vec: egui::Vec2, angle_radians: f32) -> egui::Vec2 {
     let cos_a = angle_radians.cos();
     let sin_a = angle_radians.sin();
@@ -128,3 +667,416 @@ pub mod pdf_utils {
         Ok(blocks)
     }
 }
+
+// Quick filters on modification time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RecentWindow {
+    All,
+    Today,
+    Days(u32),
+}
+
+impl RecentWindow {
+    pub fn label(self) -> String {
+        match self {
+            RecentWindow::All => "All".to_string(),
+            RecentWindow::Today => "Today".to_string(),
+            RecentWindow::Days(days) => format!("{} days", days),
+        }
+    }
+
+    // Oldest modification time still shown; Today starts at local midnight
+    pub fn cutoff(self, now: chrono::DateTime<chrono::Local>) -> Option<SystemTime> {
+        let start = match self {
+            RecentWindow::All => return None,
+            RecentWindow::Today => now
+                .date_naive()
+                .and_hms_opt(0, 0, 0)?
+                .and_local_timezone(chrono::Local)
+                .earliest()?,
+            RecentWindow::Days(days) => now - chrono::Duration::days(i64::from(days)),
+        };
+        Some(start.into())
+    }
+}
+
+// Where an over-long label loses characters
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LabelTruncation {
+    End,
+    Middle,
+}
+
+// `text` cut to at most `max_chars` characters including the ellipsis; 0 means no limit
+pub fn truncate_label(text: &str, max_chars: usize, mode: LabelTruncation) -> String {
+    let char_count = text.chars().count();
+    if max_chars == 0 || char_count <= max_chars {
+        return text.to_string();
+    }
+    let kept = max_chars.saturating_sub(1);
+    match mode {
+        LabelTruncation::End => text.chars().take(kept).chain(['…']).collect(),
+        LabelTruncation::Middle => {
+            // The extra character goes to the start, which usually names the file
+            let tail = kept / 2;
+            let head = kept - tail;
+            text.chars()
+                .take(head)
+                .chain(['…'])
+                .chain(text.chars().skip(char_count - tail))
+                .collect()
+        }
+    }
+}
+
+// A path glob: `**` spans folders, `*` and `?` stay within one.
+// Paths are matched with `/` separators.
+pub fn path_glob(glob: &str) -> Result<globset::Glob, globset::Error> {
+    globset::GlobBuilder::new(glob)
+        .literal_separator(true)
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zooming_keeps_the_point_under_the_cursor_in_place() {
+        let offset = egui::vec2(40.0, -25.0);
+        let anchor = egui::pos2(120.0, 80.0);
+        let under_cursor = screen_to_graph(anchor, offset, 1.5);
+
+        for new_zoom in [0.2, 1.5, 4.0] {
+            let new_offset = zoom_about(offset, anchor, 1.5, new_zoom);
+            let after = screen_to_graph(anchor, new_offset, new_zoom);
+            assert!((after - under_cursor).length() < 1e-3, "zoom {}", new_zoom);
+        }
+        // Zooming about the view centre scales the offset
+        assert_eq!(zoom_about(offset, egui::Pos2::ZERO, 1.0, 2.0), offset * 2.0);
+    }
+
+    #[test]
+    fn excerpt_keeps_the_first_lines_and_cuts_long_ones() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("long.md");
+        let mut content: Vec<String> = (1..=30).map(|i| format!("line {}", i)).collect();
+        content[1] = "x".repeat(5000);
+        std::fs::write(&path, content.join("\n")).unwrap();
+
+        let excerpt = read_excerpt(&path, 12, 80).unwrap();
+        let lines: Vec<&str> = excerpt.lines().collect();
+        assert_eq!(lines.len(), 12);
+        assert_eq!(lines[0], "line 1");
+        assert_eq!(lines[1], format!("{}…", "x".repeat(79)));
+        assert_eq!(lines[11], "line 12");
+
+        // One huge line is read only as far as the excerpt needs
+        std::fs::write(&path, "y".repeat(1_000_000)).unwrap();
+        assert_eq!(read_excerpt(&path, 12, 80).unwrap().chars().count(), 80);
+    }
+
+    #[test]
+    fn backlinks_become_a_bullet_list_of_wikilinks() {
+        let backlinks = [
+            PathBuf::from("/notes/daily/2024-05-01.md"),
+            PathBuf::from("/notes/Project Plan.md"),
+            PathBuf::from("/notes/script.py"),
+        ];
+        assert_eq!(
+            wikilink_list(&backlinks),
+            "- [[2024-05-01]]\n- [[Project Plan]]\n- [[script]]"
+        );
+        assert_eq!(wikilink_list(&[]), "");
+    }
+
+    #[test]
+    fn match_ranges_are_byte_offsets_ignoring_ascii_case() {
+        let content = "Graph — graph GRAPHS";
+        let ranges = find_match_ranges(content, "graph");
+        // The em dash is three bytes, so later matches start past it
+        assert_eq!(ranges, [0..5, 10..15, 16..21]);
+        assert!(
+            ranges
+                .iter()
+                .all(|range| content[range.clone()].eq_ignore_ascii_case("graph"))
+        );
+        assert_eq!(find_match_ranges("aaaa", "aa"), [0..2, 2..4]);
+        assert!(find_match_ranges(content, "").is_empty());
+        assert!(find_match_ranges(content, "nodes").is_empty());
+    }
+
+    #[test]
+    fn append_tag_extends_the_tags_line_and_skips_existing_tags() {
+        assert_eq!(
+            append_tag("# Title\n\nBody text\n#rust #egui\n", "graphs"),
+            Some("# Title\n\nBody text\n#rust #egui #graphs\n".to_string())
+        );
+        assert_eq!(
+            append_tag("# Title\nBody mentions #rust inline", "todo"),
+            Some("# Title\nBody mentions #rust inline\n\n#todo\n".to_string())
+        );
+        assert_eq!(append_tag("", "todo"), Some("#todo\n".to_string()));
+        assert_eq!(append_tag("Body mentions #rust inline", "rust"), None);
+        // A longer tag starting with the same letters is a different tag
+        assert!(append_tag("#rustacean", "rust").is_some());
+    }
+
+    #[test]
+    fn tag_completions_put_prefix_matches_first() {
+        let tags = [
+            "project",
+            "Programming",
+            "reproduce",
+            "pro",
+            "books",
+            "project",
+        ];
+        assert_eq!(
+            tag_completions(tags, "rust PRO", 10),
+            ["Programming", "project", "reproduce"]
+        );
+        assert_eq!(tag_completions(tags, "pro", 1), ["Programming"]);
+        // Nothing to complete after a space or in an empty field
+        assert!(tag_completions(tags, "pro ", 10).is_empty());
+        assert!(tag_completions(tags, "", 10).is_empty());
+
+        assert_eq!(apply_tag_completion("rust pro", "project"), "rust project");
+        assert_eq!(apply_tag_completion("pro", "project"), "project");
+    }
+
+    #[test]
+    fn missing_opener_is_an_error_not_a_panic() {
+        let path = Path::new("/notes/a.md");
+        let missing = std::process::Command::new("nexusview-no-such-opener");
+        let error = spawn_opener(missing, path).unwrap_err();
+        assert!(
+            error.starts_with("Could not open /notes/a.md: "),
+            "{}",
+            error
+        );
+
+        assert_eq!(
+            spawn_opener(std::process::Command::new("true"), path),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn density_bins_points_per_cell_and_smoothing_spreads_them() {
+        let bounds = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(30.0, 20.0));
+        let points = [
+            egui::pos2(1.0, 1.0),
+            egui::pos2(9.0, 9.0),
+            egui::pos2(15.0, 15.0),
+            // The far edges belong to the last cells
+            egui::pos2(30.0, 20.0),
+            egui::pos2(-1.0, 5.0),
+        ];
+        let grid = density_grid(&points, bounds, 3, 2);
+        assert_eq!(grid, [2.0, 0.0, 0.0, 0.0, 1.0, 1.0]);
+
+        let smoothed = smooth_grid(&[0.0, 0.0, 0.0, 0.0, 16.0, 0.0, 0.0, 0.0, 0.0], 3, 3);
+        assert_eq!(smoothed, [1.0, 2.0, 1.0, 2.0, 4.0, 2.0, 1.0, 2.0, 1.0]);
+        assert_eq!(smoothed.iter().sum::<f32>(), 16.0);
+    }
+
+    #[test]
+    fn paths_are_shown_relative_to_the_scan_root_when_inside_it() {
+        let root = Path::new("/home/me/notes");
+        assert_eq!(
+            relative_to_root(Path::new("/home/me/notes/daily/today.md"), root),
+            Path::new("daily/today.md")
+        );
+        assert_eq!(
+            relative_to_root(Path::new("/home/me/other/file.md"), root),
+            Path::new("/home/me/other/file.md")
+        );
+        // A sibling folder sharing the root's name as a prefix is outside it
+        assert_eq!(
+            relative_to_root(Path::new("/home/me/notes-old/a.md"), root),
+            Path::new("/home/me/notes-old/a.md")
+        );
+    }
+
+    #[test]
+    fn seven_day_window_starts_a_week_before_now() {
+        use chrono::TimeZone;
+
+        let now = chrono::Local
+            .with_ymd_and_hms(2024, 5, 10, 15, 30, 0)
+            .unwrap();
+        let week_ago = chrono::Local
+            .with_ymd_and_hms(2024, 5, 3, 15, 30, 0)
+            .unwrap();
+        assert_eq!(RecentWindow::Days(7).cutoff(now), Some(week_ago.into()));
+
+        let midnight = chrono::Local
+            .with_ymd_and_hms(2024, 5, 10, 0, 0, 0)
+            .unwrap();
+        assert_eq!(RecentWindow::Today.cutoff(now), Some(midnight.into()));
+        assert_eq!(RecentWindow::All.cutoff(now), None);
+
+        // A file saved eight days ago falls outside the week, one saved six days ago inside
+        let cutoff = RecentWindow::Days(7).cutoff(now).unwrap();
+        let days_ago = |days| SystemTime::from(now - chrono::Duration::days(days));
+        assert!(days_ago(8) < cutoff);
+        assert!(days_ago(6) >= cutoff);
+    }
+
+    #[test]
+    fn middle_truncation_keeps_both_ends() {
+        let name = "meeting-notes-2024-quarterly-review.md";
+        let cut = truncate_label(name, 12, LabelTruncation::Middle);
+        assert_eq!(cut, "meetin…ew.md");
+        assert_eq!(cut.chars().count(), 12);
+        assert_eq!(
+            truncate_label(name, 12, LabelTruncation::End),
+            "meeting-not…"
+        );
+        // Counted in characters, so multi-byte text isn't split inside a character
+        assert_eq!(
+            truncate_label("ñandú-ñandú", 6, LabelTruncation::Middle),
+            "ñan…dú"
+        );
+        assert_eq!(
+            truncate_label("short.md", 12, LabelTruncation::Middle),
+            "short.md"
+        );
+        assert_eq!(truncate_label(name, 0, LabelTruncation::Middle), name);
+    }
+
+    #[test]
+    fn history_moves_repeats_to_the_front_and_keeps_the_cap() {
+        let mut history = Vec::new();
+        for query in ["alpha", "beta", "  ", "gamma"] {
+            push_history(&mut history, query, 3);
+        }
+        assert_eq!(history, ["gamma", "beta", "alpha"]);
+
+        push_history(&mut history, " alpha ", 3);
+        assert_eq!(history, ["alpha", "gamma", "beta"]);
+
+        push_history(&mut history, "delta", 3);
+        assert_eq!(history, ["delta", "alpha", "gamma"]);
+    }
+
+    #[test]
+    fn bundled_edge_bends_towards_the_shared_neighbour() {
+        // `a` and `b` both link to a hub below them
+        let positions = HashMap::from([
+            ("a", egui::vec2(0.0, 0.0)),
+            ("b", egui::vec2(100.0, 0.0)),
+            ("hub", egui::vec2(50.0, 100.0)),
+        ]);
+        let edges = [("a", "b"), ("a", "hub"), ("b", "hub")];
+        let centroids = neighbor_centroids(&edges, &positions);
+        assert_eq!(centroids["a"], egui::vec2(75.0, 50.0));
+        assert_eq!(centroids["b"], egui::vec2(25.0, 50.0));
+
+        let (start, end) = (egui::pos2(0.0, 0.0), egui::pos2(100.0, 0.0));
+        let control = |strength| {
+            bundled_control_point(
+                start,
+                end,
+                centroids["a"].to_pos2(),
+                centroids["b"].to_pos2(),
+                strength,
+            )
+        };
+        assert_eq!(control(0.0), egui::pos2(50.0, 0.0));
+        assert_eq!(control(0.5), egui::pos2(50.0, 25.0));
+        assert_eq!(control(1.0), egui::pos2(50.0, 50.0));
+        assert_eq!(control(3.0), control(1.0));
+    }
+
+    #[test]
+    fn follow_offset_keeps_a_moving_node_centred() {
+        let path = [
+            egui::vec2(0.0, 0.0),
+            egui::vec2(12.5, -4.0),
+            egui::vec2(-300.0, 80.0),
+        ];
+        for zoom in [0.25, 1.0, 3.0] {
+            for node_pos in path {
+                let offset = follow_offset(node_pos, zoom);
+                // Where the node lands relative to the view centre
+                assert_eq!(node_pos * zoom + offset, egui::Vec2::ZERO);
+                assert_eq!(screen_to_graph(egui::Pos2::ZERO, offset, zoom), node_pos);
+            }
+        }
+    }
+
+    #[test]
+    fn flow_dots_move_from_source_to_target_and_wrap() {
+        let (start, end) = (egui::pos2(0.0, 0.0), egui::pos2(100.0, 0.0));
+        // Half a second at speed 0.5 puts the first dot a quarter of the way along
+        let t = flow_phase(0.5, 0.5, 0, 2);
+        assert_eq!(t, 0.25);
+        assert_eq!(point_on_edge(start, None, end, t), egui::pos2(25.0, 0.0));
+        // The second dot runs half an edge ahead, then wraps to the source
+        assert_eq!(flow_phase(0.5, 0.5, 1, 2), 0.75);
+        assert_eq!(flow_phase(1.5, 0.5, 1, 2), 0.25);
+
+        // On a curve the ends stay put and the middle is pulled towards the control point
+        let control = Some(egui::pos2(50.0, 40.0));
+        assert_eq!(point_on_edge(start, control, end, 0.0), start);
+        assert_eq!(point_on_edge(start, control, end, 1.0), end);
+        assert_eq!(
+            point_on_edge(start, control, end, 0.5),
+            egui::pos2(50.0, 20.0)
+        );
+    }
+
+    #[test]
+    fn small_pointer_travel_is_a_click_and_larger_travel_a_drag() {
+        let threshold = 6.0;
+        for travel in [
+            egui::Vec2::ZERO,
+            egui::vec2(1.0, -1.0),
+            egui::vec2(0.0, threshold),
+        ] {
+            assert!(!exceeds_drag_threshold(travel, threshold), "{:?}", travel);
+        }
+        for travel in [
+            egui::vec2(threshold + 0.5, 0.0),
+            egui::vec2(-threshold, threshold),
+        ] {
+            assert!(exceeds_drag_threshold(travel, threshold), "{:?}", travel);
+        }
+        // A zero threshold makes any movement a drag
+        assert!(exceeds_drag_threshold(egui::vec2(0.1, 0.0), 0.0));
+    }
+
+    #[test]
+    fn curved_edges_bend_sideways_and_arrows_follow_the_tangent() {
+        let (start, end) = (egui::pos2(0.0, 0.0), egui::pos2(100.0, 0.0));
+        let control = curved_control_point(start, end, 0.2);
+        // Off the midpoint, square to the edge, by a fifth of its length
+        assert_eq!(control.x, 50.0);
+        assert_eq!(control.y.abs(), 20.0);
+        // The reverse edge bends to the other side
+        assert_eq!(
+            curved_control_point(end, start, 0.2),
+            egui::pos2(50.0, -control.y)
+        );
+
+        let dir = edge_end_direction(start, Some(control), end);
+        assert!((dir.length() - 1.0).abs() < 1e-6);
+        // Matches the curve's own heading just before the target
+        let near_end = point_on_edge(start, Some(control), end, 0.999);
+        assert!((dir - (end - near_end).normalized()).length() < 1e-3);
+        assert_eq!(edge_end_direction(start, None, end), egui::vec2(1.0, 0.0));
+    }
+
+    #[test]
+    fn tag_filter_ignores_case() {
+        let filter = "Foo".to_lowercase();
+        assert!(tag_matches_filter("foo", &filter));
+        assert!(tag_matches_filter("FOOBAR", &filter));
+        assert!(!tag_matches_filter("bar", &filter));
+        assert!(tag_matches_filter("anything", ""));
+        // A differently cased tag already in the file counts as present
+        assert_eq!(append_tag("Notes #Foo", "foo"), None);
+    }
+}
@@ -0,0 +1,66 @@
+// src/view_state.rs
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::graph::GraphNode;
+use crate::ui::GraphMode;
+
+// Command-line flag that opens a shared view
+pub const VIEW_FLAG: &str = "--view";
+
+// Everything needed to reproduce what is on screen: directory, mode, selection and camera
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ViewState {
+    pub scan_dir: PathBuf,
+    pub graph_mode: GraphMode,
+    pub selected_node: Option<GraphNode>,
+    pub zoom: f32,
+    pub offset: [f32; 2],
+}
+
+impl ViewState {
+    // URL-safe base64 of the JSON form, so it can be pasted into a shell or a link
+    pub fn encode(&self) -> String {
+        let json = serde_json::to_vec(self).expect("view state is always serializable");
+        URL_SAFE_NO_PAD.encode(json)
+    }
+
+    pub fn decode(encoded: &str) -> Result<Self, String> {
+        let json = URL_SAFE_NO_PAD
+            .decode(encoded.trim())
+            .map_err(|e| format!("Invalid view link: {}", e))?;
+        serde_json::from_slice(&json).map_err(|e| format!("Invalid view link: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn view_state_survives_encoding_and_decoding() {
+        let view = ViewState {
+            scan_dir: PathBuf::from("/home/me/notes & ideas"),
+            graph_mode: GraphMode::Tags,
+            selected_node: Some(GraphNode::File(PathBuf::from(
+                "/home/me/notes & ideas/ü.md",
+            ))),
+            zoom: 1.75,
+            offset: [-120.5, 42.0],
+        };
+        let encoded = view.encode();
+        // Safe to paste into a shell or a URL unquoted
+        assert!(
+            encoded
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        );
+        assert_eq!(ViewState::decode(&encoded), Ok(view.clone()));
+        assert_eq!(ViewState::decode(&format!("  {}\n", encoded)), Ok(view));
+
+        assert!(ViewState::decode("not base64!").is_err());
+        assert!(ViewState::decode(&URL_SAFE_NO_PAD.encode("{}")).is_err());
+    }
+}
@@ -0,0 +1,199 @@
+// src/watch.rs
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, Sender, channel};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::file_scan::FileScanner;
+
+// How often a sleeping watcher checks whether it was stopped
+const STOP_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+// A running poll watcher. Dropping it stops the thread, so replacing the watcher
+// never leaves an old one walking the tree.
+pub struct PollWatcher {
+    pub root: PathBuf,
+    pub changes: Receiver<PathBuf>,
+    stop: Arc<AtomicBool>,
+}
+
+impl Drop for PollWatcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+// Polls `root` every `interval` and sends each path whose modification time changed,
+// appeared or disappeared. `scanner` supplies the scan's hidden, exclude, .gitignore,
+// size and depth rules, so paths the scan leaves out are never reported.
+pub fn spawn_poll_watcher(
+    mut scanner: FileScanner,
+    root: PathBuf,
+    interval: Duration,
+) -> PollWatcher {
+    let (sender, receiver) = channel();
+    let stop = Arc::new(AtomicBool::new(false));
+    let stopped = stop.clone();
+    let watched = root.clone();
+    thread::spawn(move || {
+        let mut known = scanner.scannable_files(&watched);
+        loop {
+            let wake = Instant::now() + interval;
+            while Instant::now() < wake {
+                if stopped.load(Ordering::Relaxed) {
+                    return;
+                }
+                thread::sleep(STOP_CHECK_INTERVAL.min(interval));
+            }
+            if stopped.load(Ordering::Relaxed) {
+                return;
+            }
+            let current = scanner.scannable_files(&watched);
+            if !send_changes(&known, &current, &sender) {
+                return;
+            }
+            known = current;
+        }
+    });
+    PollWatcher {
+        root,
+        changes: receiver,
+        stop,
+    }
+}
+
+// False once nobody is listening
+fn send_changes(
+    before: &HashMap<PathBuf, SystemTime>,
+    after: &HashMap<PathBuf, SystemTime>,
+    sender: &Sender<PathBuf>,
+) -> bool {
+    let changed = after
+        .iter()
+        .filter(|(path, modified)| before.get(*path) != Some(*modified))
+        .map(|(path, _)| path);
+    let removed = before.keys().filter(|path| !after.contains_key(*path));
+    for path in changed.chain(removed) {
+        if sender.send(path.clone()).is_err() {
+            return false;
+        }
+    }
+    true
+}
+
+// Coalesces change events into one rescan. When batching, every event restarts the
+// wait, so a burst of saves rescans once it is over; otherwise the rescan happens
+// one window after the first event.
+#[derive(Debug, Clone)]
+pub struct RescanDebouncer {
+    pub window: Duration,
+    pub batch_events: bool,
+    first_event: Option<Instant>,
+    last_event: Option<Instant>,
+}
+
+impl RescanDebouncer {
+    pub fn new(window: Duration, batch_events: bool) -> Self {
+        Self {
+            window,
+            batch_events,
+            first_event: None,
+            last_event: None,
+        }
+    }
+
+    pub fn record(&mut self, now: Instant) {
+        self.first_event.get_or_insert(now);
+        self.last_event = Some(now);
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.first_event.is_some()
+    }
+
+    // True exactly once per coalesced group of events, when its window has passed
+    pub fn poll(&mut self, now: Instant) -> bool {
+        let start = if self.batch_events {
+            self.last_event
+        } else {
+            self.first_event
+        };
+        match start {
+            Some(start) if now.duration_since(start) >= self.window => {
+                self.first_event = None;
+                self.last_event = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bursts_of_events_trigger_one_rescan() {
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+
+        // Batched: the window restarts with each event
+        let mut debouncer = RescanDebouncer::new(Duration::from_millis(500), true);
+        for ms in [0, 100, 200, 400] {
+            debouncer.record(at(ms));
+            assert!(!debouncer.poll(at(ms)));
+        }
+        assert!(debouncer.is_pending());
+        assert!(!debouncer.poll(at(800)));
+        let triggers = (900..2000)
+            .step_by(100)
+            .filter(|&ms| debouncer.poll(at(ms)))
+            .count();
+        assert_eq!(triggers, 1);
+        assert!(!debouncer.is_pending());
+
+        // Unbatched: one window after the first event, however many follow
+        let mut debouncer = RescanDebouncer::new(Duration::from_millis(500), false);
+        for ms in [0, 300, 450] {
+            debouncer.record(at(ms));
+        }
+        assert!(debouncer.poll(at(500)));
+        assert!(!debouncer.poll(at(950)));
+    }
+
+    #[test]
+    fn watcher_skips_what_the_scan_skips_and_stops_when_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("a/b")).unwrap();
+        let mut scanner = FileScanner::new(dir.path());
+        scanner.set_max_depth(Some(1));
+        let watcher =
+            spawn_poll_watcher(scanner, dir.path().to_path_buf(), Duration::from_millis(20));
+        // Let it take its first look before anything changes
+        std::thread::sleep(Duration::from_millis(100));
+
+        std::fs::write(dir.path().join(".hidden.md"), "").unwrap();
+        std::fs::write(dir.path().join("a/b/too-deep.md"), "").unwrap();
+        std::fs::write(dir.path().join("a/note.md"), "").unwrap();
+        let changed = watcher
+            .changes
+            .recv_timeout(Duration::from_secs(5))
+            .unwrap();
+        assert_eq!(changed, dir.path().join("a/note.md"));
+        std::thread::sleep(Duration::from_millis(100));
+        assert!(watcher.changes.try_recv().is_err());
+
+        // The thread lets go of the stop flag once it has finished
+        let stop = watcher.stop.clone();
+        drop(watcher);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while Arc::strong_count(&stop) > 1 {
+            assert!(Instant::now() < deadline, "watcher thread kept running");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+}